@@ -1,6 +1,9 @@
 use ak::rng::RngCore;
-use ak::rng::mgk32a::Mgk32a;
+use ak::rng::mgk32a::{Mgk32a, Mgk32aStreams};
+use ak::rng::mt19937::Mt19937;
+use ak::rng::pcg64::Pcg64;
 use ak::rng::sobol::Sobol;
+use ak::rng::xoshiro256::Xoshiro256StarStar;
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 
 fn bench_mgk32a_next_u32(c: &mut Criterion) {
@@ -12,6 +15,51 @@ fn bench_mgk32a_next_u32(c: &mut Criterion) {
     });
 }
 
+fn bench_mgk32a_stream_construction(c: &mut Criterion) {
+    let seed = [12345; 6];
+    let stride = 1u128 << 40;
+    c.bench_function("mgk32a_for_stream", |b| {
+        let mut stream = 0u128;
+        b.iter(|| {
+            stream += 1;
+            black_box(Mgk32a::for_stream(seed, stream, stride).unwrap());
+        })
+    });
+    c.bench_function("mgk32a_streams_next_stream", |b| {
+        let mut streams = Mgk32aStreams::with_stride(seed, stride).unwrap();
+        b.iter(|| {
+            black_box(streams.next_stream());
+        })
+    });
+}
+
+fn bench_xoshiro256_next_u32(c: &mut Criterion) {
+    c.bench_function("xoshiro256starstar_next_u32", |b| {
+        let mut rng = Xoshiro256StarStar::from_seed64(123);
+        b.iter(|| {
+            black_box(rng.next_u32());
+        })
+    });
+}
+
+fn bench_pcg64_next_u32(c: &mut Criterion) {
+    c.bench_function("pcg64_next_u32", |b| {
+        let mut rng = Pcg64::new(123, 0);
+        b.iter(|| {
+            black_box(rng.next_u32());
+        })
+    });
+}
+
+fn bench_mt19937_next_u32(c: &mut Criterion) {
+    c.bench_function("mt19937_next_u32", |b| {
+        let mut rng = Mt19937::new(123);
+        b.iter(|| {
+            black_box(rng.next_u32());
+        })
+    });
+}
+
 fn bench_sobol_dim1_next_point(c: &mut Criterion) {
     c.bench_function("sobol_dim1_next_point", |b| {
         let mut sobol = Sobol::new(1).expect("sobol dim1");
@@ -26,6 +74,10 @@ fn bench_sobol_dim1_next_point(c: &mut Criterion) {
 criterion_group!(
     rng_benches,
     bench_mgk32a_next_u32,
+    bench_mgk32a_stream_construction,
+    bench_xoshiro256_next_u32,
+    bench_pcg64_next_u32,
+    bench_mt19937_next_u32,
     bench_sobol_dim1_next_point
 );
 criterion_main!(rng_benches);
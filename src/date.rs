@@ -3,6 +3,7 @@ use jiff::{Error, ToSpan};
 pub use jiff::civil::Date;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Frequency {
     Daily,
     Weekly,
@@ -12,6 +13,20 @@ pub enum Frequency {
     Annual,
 }
 
+impl Frequency {
+    /// Payment periods per year; daily uses 365 and weekly 52.
+    pub const fn periods_per_year(self) -> u32 {
+        match self {
+            Self::Daily => 365,
+            Self::Weekly => 52,
+            Self::Monthly => 12,
+            Self::Quarterly => 4,
+            Self::SemiAnnual => 2,
+            Self::Annual => 1,
+        }
+    }
+}
+
 pub type DateError = Error;
 
 /// Returns the cashflow date at a given period index from the start date.
@@ -48,6 +63,24 @@ pub fn generate_cashflow_dates(
     Ok(dates)
 }
 
+/// Number of days in period `index` of a schedule starting at `start`.
+pub fn period_days(start: Date, index: usize, frequency: Frequency) -> Result<i32, Error> {
+    let from = cashflow_date_at(start, index, frequency)?;
+    let to = cashflow_date_at(start, index + 1, frequency)?;
+    Ok(from.until(to)?.get_days())
+}
+
+/// Smallest number of periods from `start` that covers at least `days` days,
+/// such as an elimination period expressed in days.
+pub fn periods_spanning_days(start: Date, days: u32, frequency: Frequency) -> Result<usize, Error> {
+    let end = start.checked_add(i64::from(days).days())?;
+    let mut periods = 0;
+    while cashflow_date_at(start, periods, frequency)? < end {
+        periods += 1;
+    }
+    Ok(periods)
+}
+
 pub fn is_leap_year(year: i16) -> Result<bool, Error> {
     Ok(Date::new(year, 1, 1)?.in_leap_year())
 }
@@ -90,6 +123,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn converts_days_to_periods() -> Result<(), DateError> {
+        let start = Date::new(2024, 1, 1)?;
+        assert_eq!(period_days(start, 1, Frequency::Monthly)?, 29);
+        assert_eq!(period_days(start, 0, Frequency::Annual)?, 366);
+        assert_eq!(periods_spanning_days(start, 0, Frequency::Monthly)?, 0);
+        assert_eq!(periods_spanning_days(start, 90, Frequency::Monthly)?, 3);
+        assert_eq!(periods_spanning_days(start, 92, Frequency::Monthly)?, 4);
+        assert_eq!(periods_spanning_days(start, 90, Frequency::Weekly)?, 13);
+        assert_eq!(periods_spanning_days(start, 90, Frequency::Annual)?, 1);
+        Ok(())
+    }
+
     #[test]
     fn generates_monthly_cashflow_dates() -> Result<(), DateError> {
         let start = Date::new(2023, 1, 31)?;
@@ -0,0 +1,156 @@
+//! Arrow record batches and Parquet files in the long `date,state,kind,amount` layout.
+
+use std::sync::Arc;
+
+use arrow_array::{Date32Array, Float64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+
+use super::{CashflowLabels, ExportError};
+use crate::Date;
+use crate::product::CashflowBuffer;
+
+/// Schema shared by [`to_record_batch`] and the Parquet writer.
+pub fn cashflow_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("date", DataType::Date32, false),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("amount", DataType::Float64, false),
+    ])
+}
+
+/// Builds a record batch with one row per cell, ordered by state, kind, step.
+pub fn to_record_batch(
+    buffer: &CashflowBuffer,
+    labels: &CashflowLabels,
+) -> Result<RecordBatch, ExportError> {
+    labels.check(buffer)?;
+    let epoch = Date::constant(1970, 1, 1);
+    let days: Vec<i32> = buffer
+        .times()
+        .iter()
+        .map(|&date| (date - epoch).get_days())
+        .collect();
+
+    let rows = buffer.amounts().len();
+    let mut dates = Vec::with_capacity(rows);
+    let mut states = Vec::with_capacity(rows);
+    let mut kinds = Vec::with_capacity(rows);
+    let mut amounts = Vec::with_capacity(rows);
+    for (state, state_label) in labels.states().iter().enumerate() {
+        for (kind, kind_label) in labels.kinds().iter().enumerate() {
+            for (&day, amount) in days.iter().zip(buffer.series(state, kind)) {
+                dates.push(day);
+                states.push(state_label.as_str());
+                kinds.push(kind_label.as_str());
+                amounts.push(amount.value());
+            }
+        }
+    }
+
+    RecordBatch::try_new(
+        Arc::new(cashflow_schema()),
+        vec![
+            Arc::new(Date32Array::from(dates)),
+            Arc::new(StringArray::from(states)),
+            Arc::new(StringArray::from(kinds)),
+            Arc::new(Float64Array::from(amounts)),
+        ],
+    )
+    .map_err(ExportError::Arrow)
+}
+
+/// Writes the buffer as a single-row-group Parquet file.
+#[cfg(feature = "parquet")]
+pub fn write_parquet<W: std::io::Write + Send>(
+    buffer: &CashflowBuffer,
+    labels: &CashflowLabels,
+    writer: W,
+) -> Result<(), ExportError> {
+    let batch = to_record_batch(buffer, labels)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)
+        .map_err(ExportError::Parquet)?;
+    writer.write(&batch).map_err(ExportError::Parquet)?;
+    writer.close().map_err(ExportError::Parquet)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DateError;
+    use crate::product::Amount;
+    use arrow_array::Array;
+
+    fn sample() -> Result<CashflowBuffer, DateError> {
+        let times = vec![Date::new(1970, 1, 2)?, Date::new(1970, 1, 3)?];
+        let mut buffer = CashflowBuffer::new(2, 1, times).unwrap();
+        *buffer.amount_mut(1, 0, 1) = Amount::from_f64(7.5);
+        Ok(buffer)
+    }
+
+    #[test]
+    fn record_batch_uses_long_layout() -> Result<(), DateError> {
+        let buffer = sample()?;
+        let labels = CashflowLabels::new(vec!["a".into(), "b".into()], vec!["claims".into()]);
+        let batch = to_record_batch(&buffer, &labels).unwrap();
+
+        assert_eq!(batch.num_rows(), 4);
+        assert_eq!(batch.schema().as_ref(), &cashflow_schema());
+        let dates = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Date32Array>()
+            .unwrap();
+        assert_eq!(dates.value(3), 2);
+        let states = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(states.value(3), "b");
+        let amounts = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!(amounts.value(3), 7.5);
+        assert_eq!(amounts.null_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn record_batch_rejects_mismatched_labels() -> Result<(), DateError> {
+        let buffer = sample()?;
+        let labels = CashflowLabels::indexed(1, 1);
+        assert!(matches!(
+            to_record_batch(&buffer, &labels),
+            Err(ExportError::Shape)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn parquet_round_trips_record_batch() -> Result<(), DateError> {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let buffer = sample()?;
+        let labels = CashflowLabels::indexed(2, 1);
+        let path = std::env::temp_dir().join(format!("ak-export-{}.parquet", std::process::id()));
+        let file = std::fs::File::create(&path).unwrap();
+        write_parquet(&buffer, &labels, file).unwrap();
+
+        let file = std::fs::File::open(&path).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0], to_record_batch(&buffer, &labels).unwrap());
+        Ok(())
+    }
+}
@@ -0,0 +1,308 @@
+//! Writers that turn projection results into tabular files.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::product::{CashflowBuffer, FieldScope, ProductDefinition, RequiredDataHistory};
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// Labels do not match the buffer dimensions.
+    Shape,
+    Io(io::Error),
+    #[cfg(feature = "arrow")]
+    Arrow(arrow_schema::ArrowError),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::errors::ParquetError),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shape => f.write_str("labels do not match buffer dimensions"),
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            #[cfg(feature = "arrow")]
+            Self::Arrow(err) => write!(f, "arrow error: {err}"),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(err) => write!(f, "parquet error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<io::Error> for ExportError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// State and cashflow kind names written alongside exported amounts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CashflowLabels {
+    states: Vec<String>,
+    kinds: Vec<String>,
+}
+
+impl CashflowLabels {
+    pub fn new(states: Vec<String>, kinds: Vec<String>) -> Self {
+        Self { states, kinds }
+    }
+
+    /// Labels each state and kind by its index (`"0"`, `"1"`, ...).
+    pub fn indexed(n_states: usize, n_kinds: usize) -> Self {
+        Self {
+            states: (0..n_states).map(|i| i.to_string()).collect(),
+            kinds: (0..n_kinds).map(|i| i.to_string()).collect(),
+        }
+    }
+
+    /// Uses the state and kind names of a product definition.
+    pub fn from_definition(definition: &ProductDefinition) -> Self {
+        Self {
+            states: definition.state_names().to_vec(),
+            kinds: definition.kinds().iter().map(|k| k.name.clone()).collect(),
+        }
+    }
+
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    pub fn kinds(&self) -> &[String] {
+        &self.kinds
+    }
+
+    pub(crate) fn check(&self, buffer: &CashflowBuffer) -> Result<(), ExportError> {
+        if self.states.len() != buffer.n_states() || self.kinds.len() != buffer.n_kinds() {
+            return Err(ExportError::Shape);
+        }
+        Ok(())
+    }
+}
+
+/// Writes one row per cell with header `date,state,kind,amount`.
+///
+/// Rows are ordered by state, then kind, then step.
+pub fn write_csv<W: Write>(
+    buffer: &CashflowBuffer,
+    labels: &CashflowLabels,
+    mut writer: W,
+) -> Result<(), ExportError> {
+    labels.check(buffer)?;
+    writeln!(writer, "date,state,kind,amount")?;
+    for (state, state_label) in labels.states.iter().enumerate() {
+        let state_label = escape(state_label);
+        for (kind, kind_label) in labels.kinds.iter().enumerate() {
+            let kind_label = escape(kind_label);
+            for (date, amount) in buffer.times().iter().zip(buffer.series(state, kind)) {
+                writeln!(
+                    writer,
+                    "{date},{state_label},{kind_label},{}",
+                    amount.value()
+                )?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes one row per state and step, with one column per cashflow kind.
+///
+/// The header is `date,state,<kind 0>,<kind 1>,...`.
+pub fn write_csv_wide<W: Write>(
+    buffer: &CashflowBuffer,
+    labels: &CashflowLabels,
+    mut writer: W,
+) -> Result<(), ExportError> {
+    labels.check(buffer)?;
+    write!(writer, "date,state")?;
+    for kind in &labels.kinds {
+        write!(writer, ",{}", escape(kind))?;
+    }
+    writeln!(writer)?;
+    for (state, state_label) in labels.states.iter().enumerate() {
+        let state_label = escape(state_label);
+        for (step, date) in buffer.times().iter().enumerate() {
+            write!(writer, "{date},{state_label}")?;
+            for kind in 0..buffer.n_kinds() {
+                write!(writer, ",{}", buffer.amount(state, kind, step).value())?;
+            }
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes recorded required data with header `date,field,state,value`.
+///
+/// Fields use their layout names, or `scalar_<i>` / `vector_<i>` in anonymous
+/// layouts. Policy scalars leave `state` empty. Rows are ordered by field, then
+/// state, then step.
+pub fn write_required_data_csv<W: Write>(
+    history: &RequiredDataHistory,
+    mut writer: W,
+) -> Result<(), ExportError> {
+    let layout = history.layout();
+    let name = |scope, index: usize| -> String {
+        layout
+            .fields()
+            .iter()
+            .filter(|f| f.scope == scope)
+            .nth(index)
+            .map(|f| f.name.clone())
+            .unwrap_or_else(|| match scope {
+                FieldScope::PolicyScalar => format!("scalar_{index}"),
+                FieldScope::StateVector => format!("vector_{index}"),
+            })
+    };
+
+    writeln!(writer, "date,field,state,value")?;
+    for field in 0..layout.policy_scalars() {
+        let label = name(FieldScope::PolicyScalar, field);
+        let label = escape(&label);
+        for (date, value) in history
+            .times()
+            .iter()
+            .zip(history.policy_scalar_series(field))
+        {
+            writeln!(writer, "{date},{label},,{value}")?;
+        }
+    }
+    for field in 0..layout.state_vectors() {
+        let label = name(FieldScope::StateVector, field);
+        let label = escape(&label);
+        for state in 0..history.n_states() {
+            let series = history.state_vector_series(field, state);
+            for (date, value) in history.times().iter().zip(series) {
+                writeln!(writer, "{date},{label},{state},{value}")?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+pub(crate) fn escape(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::Amount;
+    use crate::{Date, DateError};
+
+    fn sample() -> Result<CashflowBuffer, DateError> {
+        let times = vec![Date::new(2024, 1, 31)?, Date::new(2024, 2, 29)?];
+        let mut buffer = CashflowBuffer::new(1, 2, times).unwrap();
+        *buffer.amount_mut(0, 0, 0) = Amount::from_f64(100.0);
+        *buffer.amount_mut(0, 1, 1) = Amount::from_f64(-12.5);
+        Ok(buffer)
+    }
+
+    #[test]
+    fn writes_long_format_csv_with_labels() -> Result<(), DateError> {
+        let buffer = sample()?;
+        let labels = CashflowLabels::new(
+            vec!["active".into()],
+            vec!["premium".into(), "death, claims".into()],
+        );
+        let mut out = Vec::new();
+        write_csv(&buffer, &labels, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let expected = "date,state,kind,amount\n\
+            2024-01-31,active,premium,100\n\
+            2024-02-29,active,premium,0\n\
+            2024-01-31,active,\"death, claims\",0\n\
+            2024-02-29,active,\"death, claims\",-12.5\n";
+        assert_eq!(text, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn writes_wide_format_csv_with_kind_columns() -> Result<(), DateError> {
+        let buffer = sample()?;
+        let labels = CashflowLabels::indexed(1, 2);
+        let mut out = Vec::new();
+        write_csv_wide(&buffer, &labels, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let expected = "date,state,0,1\n2024-01-31,0,100,0\n2024-02-29,0,0,-12.5\n";
+        assert_eq!(text, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_labels_with_wrong_shape() -> Result<(), DateError> {
+        let buffer = sample()?;
+        let labels = CashflowLabels::indexed(2, 2);
+        let mut out = Vec::new();
+        assert!(matches!(
+            write_csv(&buffer, &labels, &mut out),
+            Err(ExportError::Shape)
+        ));
+        assert!(write_csv_wide(&buffer, &labels, &mut out).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn labels_follow_product_definition_names() {
+        use crate::product::{CashflowCategory, FlowDirection, KindMetadata, RequiredDataLayout};
+
+        let definition = ProductDefinition::named(
+            vec!["active".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("claims", FlowDirection::Outflow, CashflowCategory::Benefit),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let labels = CashflowLabels::from_definition(&definition);
+        assert_eq!(labels.states(), &["active"]);
+        assert_eq!(labels.kinds(), &["premium", "claims"]);
+    }
+
+    #[test]
+    fn writes_required_data_history() -> Result<(), DateError> {
+        use crate::product::{RateField, RequiredDataBuffer, RequiredDataLayout};
+
+        let mut builder = RequiredDataLayout::builder();
+        let interest = builder.policy_scalar::<RateField>("interest");
+        let qx = builder.state_vector::<RateField>("q_x");
+        let mut data = RequiredDataBuffer::new(builder.build().unwrap(), 1).unwrap();
+        data.enable_history(vec![Date::new(2024, 1, 31)?, Date::new(2024, 2, 29)?])
+            .unwrap();
+        for step in 0..2 {
+            data.set(interest, 0.25);
+            data.set_state(qx, 0, 0.5 * step as f64);
+            data.record(step);
+        }
+
+        let mut out = Vec::new();
+        write_required_data_csv(data.history().unwrap(), &mut out).unwrap();
+        let expected = "date,field,state,value\n\
+            2024-01-31,interest,,0.25\n\
+            2024-02-29,interest,,0.25\n\
+            2024-01-31,q_x,0,0\n\
+            2024-02-29,q_x,0,0.5\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn escapes_quotes_in_labels() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Date;
+use crate::product::{Amount, CashflowBuffer, Currency, CurrencyCashflowBuffer, Money};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FxError {
+    /// No quote covers the currency on the requested date.
+    MissingRate { currency: Currency, date: Date },
+    /// Rates must be finite and strictly positive.
+    InvalidRate,
+    /// The table base currency is always 1 and cannot be quoted.
+    BaseCurrencyQuote,
+}
+
+impl fmt::Display for FxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingRate { currency, date } => {
+                write!(f, "no {currency} rate on {date}")
+            }
+            Self::InvalidRate => f.write_str("FX rate must be finite and positive"),
+            Self::BaseCurrencyQuote => f.write_str("the base currency cannot be quoted"),
+        }
+    }
+}
+
+impl std::error::Error for FxError {}
+
+/// Forward FX curve from covered interest parity with annually compounded rates.
+///
+/// Quotes are units of the base currency per unit of the foreign currency:
+/// `F(t) = S * ((1 + r_base) / (1 + r_foreign))^t`, with `t` in years ACT/365F
+/// from the spot date.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FxForwardCurve {
+    spot_date: Date,
+    spot: f64,
+    base_rate: f64,
+    foreign_rate: f64,
+}
+
+impl FxForwardCurve {
+    pub fn new(
+        spot_date: Date,
+        spot: f64,
+        base_rate: f64,
+        foreign_rate: f64,
+    ) -> Result<Self, FxError> {
+        validate_rate(spot)?;
+        if !(base_rate > -1.0 && foreign_rate > -1.0) {
+            return Err(FxError::InvalidRate);
+        }
+        Ok(Self {
+            spot_date,
+            spot,
+            base_rate,
+            foreign_rate,
+        })
+    }
+
+    pub const fn spot_date(&self) -> Date {
+        self.spot_date
+    }
+
+    pub fn rate_at(&self, date: Date) -> f64 {
+        let days = (date - self.spot_date).get_days() as f64;
+        let years = days / 365.0;
+        self.spot * ((1.0 + self.base_rate) / (1.0 + self.foreign_rate)).powf(years)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct FxQuotes {
+    spots: BTreeMap<Date, f64>,
+    forward: Option<FxForwardCurve>,
+}
+
+/// FX rates keyed by date, quoted against a single base currency.
+///
+/// A rate on a given date comes from the currency's forward curve when the date
+/// is on or after the curve's spot date, and otherwise from the latest spot
+/// quote on or before the date.
+#[derive(Debug, Clone)]
+pub struct FxRateTable {
+    base: Currency,
+    quotes: BTreeMap<Currency, FxQuotes>,
+}
+
+impl FxRateTable {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            quotes: BTreeMap::new(),
+        }
+    }
+
+    pub const fn base(&self) -> Currency {
+        self.base
+    }
+
+    /// Records `rate` units of the base currency per unit of `currency` on `date`.
+    pub fn insert_spot(
+        &mut self,
+        currency: Currency,
+        date: Date,
+        rate: f64,
+    ) -> Result<(), FxError> {
+        self.ensure_foreign(currency)?;
+        validate_rate(rate)?;
+        self.quotes
+            .entry(currency)
+            .or_default()
+            .spots
+            .insert(date, rate);
+        Ok(())
+    }
+
+    pub fn set_forward_curve(
+        &mut self,
+        currency: Currency,
+        curve: FxForwardCurve,
+    ) -> Result<(), FxError> {
+        self.ensure_foreign(currency)?;
+        self.quotes.entry(currency).or_default().forward = Some(curve);
+        Ok(())
+    }
+
+    /// Units of the base currency per unit of `currency` on `date`.
+    pub fn rate(&self, currency: Currency, date: Date) -> Result<f64, FxError> {
+        if currency == self.base {
+            return Ok(1.0);
+        }
+        let missing = FxError::MissingRate { currency, date };
+        let quotes = self.quotes.get(&currency).ok_or(missing)?;
+        if let Some(curve) = &quotes.forward
+            && date >= curve.spot_date
+        {
+            return Ok(curve.rate_at(date));
+        }
+        quotes
+            .spots
+            .range(..=date)
+            .next_back()
+            .map(|(_, &rate)| rate)
+            .ok_or(missing)
+    }
+
+    /// Units of `to` per unit of `from` on `date`.
+    pub fn cross_rate(&self, from: Currency, to: Currency, date: Date) -> Result<f64, FxError> {
+        if from == to {
+            return Ok(1.0);
+        }
+        Ok(self.rate(from, date)? / self.rate(to, date)?)
+    }
+
+    pub fn convert(&self, money: Money, to: Currency, date: Date) -> Result<Money, FxError> {
+        let rate = self.cross_rate(money.currency(), to, date)?;
+        Ok(Money::new(
+            Amount::from_f64(money.amount().value() * rate),
+            to,
+        ))
+    }
+
+    /// Converts every cell to `reporting` at the rate on its projection date.
+    pub fn convert_buffer(
+        &self,
+        buffer: &CurrencyCashflowBuffer,
+        reporting: Currency,
+    ) -> Result<CashflowBuffer, FxError> {
+        let source = buffer.buffer();
+        let times = source.times();
+        let mut rates = Vec::with_capacity(source.n_kinds() * times.len());
+        for &currency in buffer.currencies() {
+            for &date in times {
+                rates.push(self.cross_rate(currency, reporting, date)?);
+            }
+        }
+
+        let mut out = source.clone();
+        for state in 0..source.n_states() {
+            for kind in 0..source.n_kinds() {
+                for step in 0..times.len() {
+                    let rate = rates[kind * times.len() + step];
+                    let cell = out.amount_mut(state, kind, step);
+                    *cell = Amount::from_f64(cell.value() * rate);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn ensure_foreign(&self, currency: Currency) -> Result<(), FxError> {
+        if currency == self.base {
+            return Err(FxError::BaseCurrencyQuote);
+        }
+        Ok(())
+    }
+}
+
+fn validate_rate(rate: f64) -> Result<(), FxError> {
+    if !(rate.is_finite() && rate > 0.0) {
+        return Err(FxError::InvalidRate);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DateError;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn spot_rates_step_forward_from_latest_quote() -> Result<(), DateError> {
+        let mut table = FxRateTable::new(Currency::USD);
+        table
+            .insert_spot(Currency::EUR, Date::new(2024, 1, 1)?, 1.10)
+            .unwrap();
+        table
+            .insert_spot(Currency::EUR, Date::new(2024, 2, 1)?, 1.08)
+            .unwrap();
+
+        assert_eq!(table.rate(Currency::USD, Date::new(2023, 1, 1)?), Ok(1.0));
+        assert_eq!(table.rate(Currency::EUR, Date::new(2024, 1, 15)?), Ok(1.10));
+        assert_eq!(table.rate(Currency::EUR, Date::new(2024, 3, 1)?), Ok(1.08));
+        assert_eq!(
+            table.rate(Currency::EUR, Date::new(2023, 12, 31)?),
+            Err(FxError::MissingRate {
+                currency: Currency::EUR,
+                date: Date::new(2023, 12, 31)?,
+            })
+        );
+        let missing = table
+            .rate(Currency::GBP, Date::new(2024, 1, 1)?)
+            .unwrap_err();
+        assert_eq!(missing.to_string(), "no GBP rate on 2024-01-01");
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_invalid_quotes() -> Result<(), DateError> {
+        let mut table = FxRateTable::new(Currency::USD);
+        let date = Date::new(2024, 1, 1)?;
+        assert_eq!(
+            table.insert_spot(Currency::USD, date, 1.0),
+            Err(FxError::BaseCurrencyQuote)
+        );
+        assert_eq!(
+            table.insert_spot(Currency::EUR, date, 0.0),
+            Err(FxError::InvalidRate)
+        );
+        assert_eq!(
+            table.insert_spot(Currency::EUR, date, f64::NAN),
+            Err(FxError::InvalidRate)
+        );
+        assert!(FxForwardCurve::new(date, 1.0, -1.0, 0.0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn forward_curve_follows_interest_parity() -> Result<(), DateError> {
+        let spot_date = Date::new(2024, 1, 1)?;
+        let curve = FxForwardCurve::new(spot_date, 1.10, 0.05, 0.03).unwrap();
+        let mut table = FxRateTable::new(Currency::USD);
+        table
+            .insert_spot(Currency::EUR, Date::new(2023, 6, 1)?, 1.05)
+            .unwrap();
+        table.set_forward_curve(Currency::EUR, curve).unwrap();
+
+        let one_year = Date::new(2024, 12, 31)?;
+        let expected = 1.10 * 1.05 / 1.03;
+        assert!(approx_eq(
+            table.rate(Currency::EUR, one_year).unwrap(),
+            expected
+        ));
+        assert_eq!(table.rate(Currency::EUR, spot_date), Ok(1.10));
+        assert_eq!(table.rate(Currency::EUR, Date::new(2023, 7, 1)?), Ok(1.05));
+        Ok(())
+    }
+
+    #[test]
+    fn converts_money_through_cross_rates() -> Result<(), DateError> {
+        let date = Date::new(2024, 1, 1)?;
+        let mut table = FxRateTable::new(Currency::USD);
+        table.insert_spot(Currency::EUR, date, 1.10).unwrap();
+        table.insert_spot(Currency::GBP, date, 1.25).unwrap();
+
+        let eur = Money::new(Amount::from_f64(100.0), Currency::EUR);
+        let gbp = table.convert(eur, Currency::GBP, date).unwrap();
+        assert_eq!(gbp.currency(), Currency::GBP);
+        assert!(approx_eq(gbp.amount().value(), 88.0));
+        assert_eq!(table.convert(eur, Currency::EUR, date), Ok(eur));
+        Ok(())
+    }
+
+    #[test]
+    fn converts_buffer_to_reporting_currency_per_date() -> Result<(), DateError> {
+        let times = vec![Date::new(2024, 1, 1)?, Date::new(2024, 2, 1)?];
+        let mut buffer = CashflowBuffer::new(1, 2, times.clone()).unwrap();
+        *buffer.amount_mut(0, 0, 0) = Amount::from_f64(10.0);
+        *buffer.amount_mut(0, 1, 0) = Amount::from_f64(10.0);
+        *buffer.amount_mut(0, 1, 1) = Amount::from_f64(10.0);
+        let buffer =
+            CurrencyCashflowBuffer::new(buffer, vec![Currency::USD, Currency::EUR]).unwrap();
+
+        let mut table = FxRateTable::new(Currency::USD);
+        table.insert_spot(Currency::EUR, times[0], 1.10).unwrap();
+        table.insert_spot(Currency::EUR, times[1], 1.20).unwrap();
+
+        let converted = table.convert_buffer(&buffer, Currency::USD).unwrap();
+        assert_eq!(converted.amount(0, 0, 0), Amount::from_f64(10.0));
+        assert!(approx_eq(converted.amount(0, 1, 0).value(), 11.0));
+        assert!(approx_eq(converted.amount(0, 1, 1).value(), 12.0));
+
+        let mut sparse = FxRateTable::new(Currency::USD);
+        sparse.insert_spot(Currency::EUR, times[1], 1.20).unwrap();
+        assert!(sparse.convert_buffer(&buffer, Currency::USD).is_err());
+        Ok(())
+    }
+}
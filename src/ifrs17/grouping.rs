@@ -0,0 +1,242 @@
+use super::{Ifrs17Error, Measurement};
+use crate::Date;
+use crate::product::{Amount, CashflowBuffer, ProductDefinition};
+use crate::valuation::group_cashflows;
+
+/// Profitability at initial recognition, which separates groups within a
+/// cohort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Profitability {
+    Onerous,
+    /// No significant possibility of becoming onerous subsequently.
+    NoSignificantRisk,
+    Remaining,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupKey {
+    pub portfolio: String,
+    /// Calendar year of issue; annual cohorts.
+    pub cohort: i16,
+    pub profitability: Profitability,
+}
+
+/// Projection of one contract, or model point, at initial recognition.
+#[derive(Debug, Clone)]
+pub struct Contract {
+    pub portfolio: String,
+    pub issue_date: Date,
+    pub cashflows: CashflowBuffer,
+    pub coverage_units: Vec<f64>,
+    /// Capital held at each step, which a cost-of-capital
+    /// [`RiskAdjustment`](super::RiskAdjustment) charges for; may be empty
+    /// under other methods.
+    pub capital: Vec<Amount>,
+}
+
+/// Contracts sharing a [`GroupKey`], with their cashflows, coverage units
+/// and capital summed.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub key: GroupKey,
+    pub cashflows: CashflowBuffer,
+    pub coverage_units: Vec<f64>,
+    /// Capital to measure the group with under a cost-of-capital risk
+    /// adjustment.
+    pub capital: Vec<Amount>,
+    /// Indices of the grouped contracts in the input.
+    pub contracts: Vec<usize>,
+}
+
+impl Measurement {
+    /// Groups contracts by portfolio, annual cohort and profitability, in key
+    /// order.
+    ///
+    /// Profitability is judged on each contract's own risk adjustment, from
+    /// its [`Contract::capital`] under the cost-of-capital method. Contracts
+    /// in a group must be projected over the same dates, with capital over
+    /// all or none of the steps.
+    pub fn group(
+        &self,
+        definition: &ProductDefinition,
+        contracts: &[Contract],
+    ) -> Result<Vec<Group>, Ifrs17Error> {
+        let groups = group_cashflows(
+            contracts,
+            |contract| &contract.cashflows,
+            |contract| {
+                if contract.coverage_units.len() != contract.cashflows.len_steps() {
+                    return Err(Ifrs17Error::Shape);
+                }
+                Ok(GroupKey {
+                    portfolio: contract.portfolio.clone(),
+                    cohort: contract.issue_date.year(),
+                    profitability: self.profitability_with(
+                        definition,
+                        &contract.cashflows,
+                        Some(&contract.capital),
+                    )?,
+                })
+            },
+            Ifrs17Error::Shape,
+        )?;
+        groups
+            .into_iter()
+            .map(|group| {
+                let mut coverage_units = vec![0.0; group.cashflows.len_steps()];
+                let mut capital = contracts[group.members[0]].capital.clone();
+                for &index in &group.members {
+                    let contract = &contracts[index];
+                    for (total, units) in coverage_units.iter_mut().zip(&contract.coverage_units) {
+                        *total += units;
+                    }
+                    if contract.capital.len() != capital.len() {
+                        return Err(Ifrs17Error::Shape);
+                    }
+                    if index != group.members[0] {
+                        for (total, &held) in capital.iter_mut().zip(&contract.capital) {
+                            *total += held;
+                        }
+                    }
+                }
+                Ok(Group {
+                    key: group.key,
+                    cashflows: group.cashflows,
+                    coverage_units,
+                    capital,
+                    contracts: group.members,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{cashflows, definition};
+    use super::*;
+    use crate::Frequency;
+    use crate::ifrs17::RiskAdjustment;
+    use crate::product::Amount;
+    use crate::valuation::DiscountCurve;
+
+    #[test]
+    fn groups_by_portfolio_cohort_and_profitability() {
+        let measurement = Measurement::new(
+            DiscountCurve::flat(0.0).unwrap(),
+            DiscountCurve::flat(0.0).unwrap(),
+            Frequency::Annual,
+            RiskAdjustment::cost_of_capital(0.0, vec![Amount::zero(); 4]).unwrap(),
+        )
+        .with_profitability_margin(0.2)
+        .unwrap();
+        let start = Date::constant(2024, 1, 1);
+        let contract = |portfolio: &str, issued: Date, premium: f64| Contract {
+            portfolio: portfolio.into(),
+            issue_date: issued,
+            cashflows: cashflows(start, premium, 60.0),
+            coverage_units: vec![1.0; 4],
+            capital: vec![Amount::zero(); 4],
+        };
+        // The second contract's claims are raised to leave a margin of 0.1
+        // over premiums; the third is onerous and the others earn 0.3.
+        let mut contracts = vec![
+            contract("term", Date::constant(2024, 3, 1), 100.0),
+            contract("term", Date::constant(2024, 6, 1), 100.0),
+            contract("term", Date::constant(2024, 9, 1), 65.0),
+            contract("term", Date::constant(2023, 12, 1), 100.0),
+            contract("annuity", Date::constant(2024, 1, 1), 100.0),
+        ];
+        contracts[1]
+            .cashflows
+            .series_mut(0, 1)
+            .fill(Amount::from_f64(80.0));
+
+        let groups = measurement.group(&definition(), &contracts).unwrap();
+        let keys: Vec<(&str, i16, Profitability)> = groups
+            .iter()
+            .map(|g| (g.key.portfolio.as_str(), g.key.cohort, g.key.profitability))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("annuity", 2024, Profitability::NoSignificantRisk),
+                ("term", 2023, Profitability::NoSignificantRisk),
+                ("term", 2024, Profitability::Onerous),
+                ("term", 2024, Profitability::NoSignificantRisk),
+                ("term", 2024, Profitability::Remaining),
+            ]
+        );
+        assert_eq!(groups[3].contracts, [0]);
+        assert_eq!(groups[4].contracts, [1]);
+
+        let mut pair = contracts[..2].to_vec();
+        pair[1]
+            .cashflows
+            .series_mut(0, 1)
+            .fill(Amount::from_f64(60.0));
+        let grouped = measurement.group(&definition(), &pair).unwrap();
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].contracts, [0, 1]);
+        assert_eq!(grouped[0].coverage_units, [2.0; 4]);
+        assert_eq!(
+            grouped[0].cashflows.amount(0, 0, 0),
+            Amount::from_f64(200.0)
+        );
+
+        pair[1].coverage_units.pop();
+        assert_eq!(
+            measurement.group(&definition(), &pair).unwrap_err(),
+            Ifrs17Error::Shape
+        );
+    }
+
+    #[test]
+    fn cost_of_capital_is_charged_on_each_contracts_own_capital() {
+        // The group capital alone would make every contract onerous.
+        let measurement = Measurement::new(
+            DiscountCurve::flat(0.0).unwrap(),
+            DiscountCurve::flat(0.0).unwrap(),
+            Frequency::Annual,
+            RiskAdjustment::cost_of_capital(0.06, vec![Amount::from_f64(1000.0); 4]).unwrap(),
+        )
+        .with_profitability_margin(0.2)
+        .unwrap();
+        let start = Date::constant(2024, 1, 1);
+        // Each contract earns 120 before a risk adjustment of 0.24 of its
+        // capital per step.
+        let contract = |capital: f64| Contract {
+            portfolio: "term".into(),
+            issue_date: start,
+            cashflows: cashflows(start, 100.0, 60.0),
+            coverage_units: vec![1.0; 4],
+            capital: vec![Amount::from_f64(capital); 4],
+        };
+        let contracts = vec![contract(100.0), contract(600.0), contract(200.0)];
+        let groups = measurement.group(&definition(), &contracts).unwrap();
+        let summary: Vec<(Profitability, Vec<usize>)> = groups
+            .iter()
+            .map(|g| (g.key.profitability, g.contracts.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (Profitability::Onerous, vec![1]),
+                (Profitability::NoSignificantRisk, vec![0]),
+                (Profitability::Remaining, vec![2]),
+            ]
+        );
+        assert_eq!(groups[0].capital, [Amount::from_f64(600.0); 4]);
+
+        let mut pair = vec![contract(100.0), contract(100.0)];
+        let grouped = measurement.group(&definition(), &pair).unwrap();
+        assert_eq!(grouped[0].capital, [Amount::from_f64(200.0); 4]);
+        pair[1].capital.pop();
+        assert_eq!(
+            measurement.group(&definition(), &pair).unwrap_err(),
+            Ifrs17Error::Shape
+        );
+    }
+}
@@ -0,0 +1,700 @@
+//! IFRS 17 general measurement of groups of insurance contracts from
+//! projected cashflows.
+
+mod grouping;
+
+pub use crate::valuation::Revision;
+pub use grouping::{Contract, Group, GroupKey, Profitability};
+
+use std::fmt;
+
+use crate::Frequency;
+use crate::product::{Amount, CashflowBuffer, CashflowCategory, ProductDefinition};
+use crate::valuation::{DiscountCurve, ReserveBasis, Valuation, ValuationError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ifrs17Error {
+    /// Confidence levels, rates, periods or coverage units are out of range.
+    InvalidParameter(&'static str),
+    /// Cashflows, coverage units or capital do not match in length or shape.
+    Shape,
+    /// A revision is not at the start of a later reporting period.
+    Revision,
+    Valuation(ValuationError),
+}
+
+impl fmt::Display for Ifrs17Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameter(name) => write!(f, "invalid {name}"),
+            Self::Shape => f.write_str("cashflows, coverage units or capital do not match"),
+            Self::Revision => f.write_str("revisions must start later reporting periods in order"),
+            Self::Valuation(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Ifrs17Error {}
+
+impl From<ValuationError> for Ifrs17Error {
+    fn from(err: ValuationError) -> Self {
+        Self::Valuation(err)
+    }
+}
+
+/// Categories of fulfilment cashflows paid by the insurer.
+const OUTGO: [CashflowCategory; 3] = [
+    CashflowCategory::Benefit,
+    CashflowCategory::Expense,
+    CashflowCategory::Commission,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum RiskMethod {
+    /// Fraction of the present value of outgo.
+    Loading(f64),
+    CostOfCapital {
+        rate: f64,
+        capital: Vec<Amount>,
+    },
+}
+
+/// Compensation for non-financial risk.
+///
+/// It is reported at current rates; the CSM is set against the same
+/// adjustment discounted at locked-in rates, so that the effect of changes in
+/// current rates stays out of the CSM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskAdjustment(RiskMethod);
+
+impl RiskAdjustment {
+    /// Margin that takes the mean of `scenarios`, present values of outgo at
+    /// initial recognition, to their `level` quantile.
+    ///
+    /// The margin is held as a fraction of the mean and applied to the
+    /// present value of outgo at every step, so it runs off with the
+    /// liability.
+    pub fn confidence_level(level: f64, scenarios: &[f64]) -> Result<Self, Ifrs17Error> {
+        if !(level > 0.0 && level < 1.0) {
+            return Err(Ifrs17Error::InvalidParameter("confidence level"));
+        }
+        if scenarios.is_empty() || scenarios.iter().any(|v| !v.is_finite()) {
+            return Err(Ifrs17Error::InvalidParameter("scenarios"));
+        }
+        let mean = scenarios.iter().sum::<f64>() / scenarios.len() as f64;
+        if mean <= 0.0 {
+            return Err(Ifrs17Error::InvalidParameter("scenarios"));
+        }
+        let mut sorted = scenarios.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        // Smallest scenario with at least `level` of them at or below it.
+        let rank = (level * sorted.len() as f64).ceil() as usize;
+        let quantile = sorted[rank.clamp(1, sorted.len()) - 1];
+        Ok(Self(RiskMethod::Loading((quantile - mean).max(0.0) / mean)))
+    }
+
+    /// Cost at `rate` of holding `capital` over each step, paid at the end
+    /// of the step.
+    ///
+    /// `capital` is that of the group being measured; [`Measurement::group`]
+    /// charges each contract for its own [`Contract::capital`] instead.
+    pub fn cost_of_capital(rate: f64, capital: Vec<Amount>) -> Result<Self, Ifrs17Error> {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err(Ifrs17Error::InvalidParameter("cost of capital rate"));
+        }
+        Ok(Self(RiskMethod::CostOfCapital { rate, capital }))
+    }
+}
+
+/// Best estimate liability and risk adjustment at locked-in and current
+/// rates at the start of each step, for the outgo less income of that step
+/// and later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fulfilment {
+    pub bel_locked_in: Vec<Amount>,
+    pub bel_current: Vec<Amount>,
+    /// Risk adjustment at current rates, as reported.
+    pub risk_adjustment: Vec<Amount>,
+    pub risk_adjustment_locked_in: Vec<Amount>,
+}
+
+impl Fulfilment {
+    /// Fulfilment cashflows at locked-in rates, which the CSM is set against.
+    fn locked_in(&self, step: usize) -> Amount {
+        self.bel_locked_in[step] + self.risk_adjustment_locked_in[step]
+    }
+
+    /// Effect of current over locked-in rates on the BEL, taken to OCI.
+    pub fn discount_rate_effect(&self, step: usize) -> Amount {
+        self.bel_current[step] - self.bel_locked_in[step]
+    }
+}
+
+/// Analysis of movement of the CSM over one reporting period.
+///
+/// `closing = opening + new_business + unlocking + accretion - release`.
+/// `loss` is recognised in profit or loss, negative for a reversal.
+/// `loss_allocation` is the part of the period's release of fulfilment
+/// cashflows allocated to the loss component rather than to insurance
+/// revenue, and `loss_component` is the running total of losses less these
+/// allocations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsmMovement {
+    pub start_step: usize,
+    pub end_step: usize,
+    pub opening: Amount,
+    pub new_business: Amount,
+    pub unlocking: Amount,
+    pub accretion: Amount,
+    pub release: Amount,
+    pub closing: Amount,
+    pub loss: Amount,
+    pub loss_allocation: Amount,
+    pub loss_component: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupMeasurement {
+    /// Fulfilment values from the estimate in force at each step.
+    pub fulfilment: Fulfilment,
+    pub movements: Vec<CsmMovement>,
+}
+
+/// General measurement model for a group of contracts.
+///
+/// At initial recognition the CSM is the excess of the present value of
+/// income over outgo and the risk adjustment at locked-in rates; a deficit
+/// is a loss component instead. Each reporting period the CSM is first
+/// unlocked for the change in fulfilment cashflows at locked-in rates from a
+/// [`Revision`] starting the period, whose estimate from its step on replaces
+/// the one in force: favourable changes reverse any loss
+/// component before adding to the CSM, and adverse changes beyond the CSM are
+/// losses. Interest then accretes at the locked-in forward rate over the
+/// period, and the CSM is released in the ratio of the period's coverage
+/// units to those of the period and all later steps. While there is a loss
+/// component, the period's release of the present value of outgo and the
+/// risk adjustment at locked-in rates is allocated to it in the ratio of the
+/// loss component to that present value at the start of the period, so the
+/// loss component runs off with the coverage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    locked_in: Valuation,
+    current: Valuation,
+    frequency: Frequency,
+    risk_adjustment: RiskAdjustment,
+    period_steps: usize,
+    profitability_margin: f64,
+}
+
+impl Measurement {
+    /// Measurement over reporting periods of one year of steps, with no
+    /// contracts judged free of significant risk of becoming onerous.
+    pub fn new(
+        locked_in: DiscountCurve,
+        current: DiscountCurve,
+        frequency: Frequency,
+        risk_adjustment: RiskAdjustment,
+    ) -> Self {
+        Self {
+            locked_in: Valuation::new(locked_in, frequency),
+            current: Valuation::new(current, frequency),
+            frequency,
+            risk_adjustment,
+            period_steps: frequency.periods_per_year() as usize,
+            profitability_margin: f64::INFINITY,
+        }
+    }
+
+    pub fn with_period_steps(mut self, period_steps: usize) -> Result<Self, Ifrs17Error> {
+        if period_steps == 0 {
+            return Err(Ifrs17Error::InvalidParameter("period steps"));
+        }
+        self.period_steps = period_steps;
+        Ok(self)
+    }
+
+    /// Contracts whose CSM at initial recognition is at least `margin` of the
+    /// present value of premiums have no significant possibility of becoming
+    /// onerous.
+    pub fn with_profitability_margin(mut self, margin: f64) -> Result<Self, Ifrs17Error> {
+        if margin.is_nan() || margin < 0.0 {
+            return Err(Ifrs17Error::InvalidParameter("profitability margin"));
+        }
+        self.profitability_margin = margin;
+        Ok(self)
+    }
+
+    pub fn fulfilment(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+    ) -> Result<Fulfilment, Ifrs17Error> {
+        self.fulfilment_with(definition, cashflows, None)
+    }
+
+    /// [`fulfilment`](Self::fulfilment) with `capital` in place of the
+    /// group capital of a cost-of-capital risk adjustment.
+    fn fulfilment_with(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        capital: Option<&[Amount]>,
+    ) -> Result<Fulfilment, Ifrs17Error> {
+        let basis = ReserveBasis::GrossPremium;
+        let bel_locked_in = self
+            .locked_in
+            .prospective(definition, cashflows, basis)?
+            .values()
+            .to_vec();
+        let bel_current = self
+            .current
+            .prospective(definition, cashflows, basis)?
+            .values()
+            .to_vec();
+        let risk_adjustment =
+            self.risk_adjustment_on(&self.current, definition, cashflows, capital)?;
+        let risk_adjustment_locked_in =
+            self.risk_adjustment_on(&self.locked_in, definition, cashflows, capital)?;
+        Ok(Fulfilment {
+            bel_locked_in,
+            bel_current,
+            risk_adjustment,
+            risk_adjustment_locked_in,
+        })
+    }
+
+    /// Risk adjustment at the start of each step on the curve of `valuation`.
+    fn risk_adjustment_on(
+        &self,
+        valuation: &Valuation,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        capital: Option<&[Amount]>,
+    ) -> Result<Vec<Amount>, Ifrs17Error> {
+        let steps = cashflows.len_steps();
+        Ok(match &self.risk_adjustment.0 {
+            RiskMethod::Loading(loading) => valuation
+                .present_value(definition, cashflows, &OUTGO)?
+                .into_iter()
+                .map(|value| -value * *loading)
+                .collect(),
+            RiskMethod::CostOfCapital {
+                rate,
+                capital: group,
+            } => {
+                let capital = capital.unwrap_or(group);
+                if capital.len() != steps {
+                    return Err(Ifrs17Error::Shape);
+                }
+                let curve = valuation.curve();
+                let periods = f64::from(self.frequency.periods_per_year());
+                let discount = |step: usize| curve.discount(step as f64 / periods);
+                let mut values = vec![Amount::zero(); steps];
+                let mut total = Amount::zero();
+                for step in (0..steps).rev() {
+                    total += capital[step] * (rate * discount(step + 1));
+                    values[step] = total / discount(step);
+                }
+                values
+            }
+        })
+    }
+
+    pub fn profitability(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+    ) -> Result<Profitability, Ifrs17Error> {
+        self.profitability_with(definition, cashflows, None)
+    }
+
+    fn profitability_with(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        capital: Option<&[Amount]>,
+    ) -> Result<Profitability, Ifrs17Error> {
+        if cashflows.len_steps() == 0 {
+            return Err(Ifrs17Error::Shape);
+        }
+        let fulfilment = self
+            .fulfilment_with(definition, cashflows, capital)?
+            .locked_in(0)
+            .value();
+        if fulfilment > 0.0 {
+            return Ok(Profitability::Onerous);
+        }
+        let premiums =
+            self.locked_in
+                .present_value(definition, cashflows, &[CashflowCategory::Premium])?[0]
+                .value();
+        if -fulfilment >= self.profitability_margin * premiums {
+            Ok(Profitability::NoSignificantRisk)
+        } else {
+            Ok(Profitability::Remaining)
+        }
+    }
+
+    /// Measures a group from its cashflows at initial recognition, coverage
+    /// units by step and revisions in step order.
+    pub fn measure(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        coverage_units: &[f64],
+        revisions: &[Revision],
+    ) -> Result<GroupMeasurement, Ifrs17Error> {
+        let steps = cashflows.len_steps();
+        if steps == 0 || coverage_units.len() != steps {
+            return Err(Ifrs17Error::Shape);
+        }
+        if coverage_units.iter().any(|u| !u.is_finite() || *u < 0.0) {
+            return Err(Ifrs17Error::InvalidParameter("coverage units"));
+        }
+        let mut previous = 0;
+        for revision in revisions {
+            let valid = revision.step > previous
+                && revision.step < steps
+                && revision.step % self.period_steps == 0
+                && revision.cashflows.is_compatible(cashflows);
+            if !valid {
+                return Err(Ifrs17Error::Revision);
+            }
+            previous = revision.step;
+        }
+
+        let mut estimate = self.fulfilment(definition, cashflows)?;
+        let mut outgo = self.outgo(definition, cashflows, &estimate)?;
+        let mut fulfilment = estimate.clone();
+        let mut revisions = revisions.iter().peekable();
+        let curve = self.locked_in.curve();
+        let periods = f64::from(self.frequency.periods_per_year());
+        let discount = |step: usize| curve.discount(step as f64 / periods);
+
+        let initial = estimate.locked_in(0);
+        let mut csm = Amount::zero();
+        let mut loss_component = Amount::zero();
+        let mut movements = Vec::with_capacity(steps.div_ceil(self.period_steps));
+        for start in (0..steps).step_by(self.period_steps) {
+            let end = (start + self.period_steps).min(steps);
+            let mut movement = CsmMovement {
+                start_step: start,
+                end_step: end,
+                opening: csm,
+                new_business: Amount::zero(),
+                unlocking: Amount::zero(),
+                accretion: Amount::zero(),
+                release: Amount::zero(),
+                closing: Amount::zero(),
+                loss: Amount::zero(),
+                loss_allocation: Amount::zero(),
+                loss_component,
+            };
+            if start == 0 {
+                if initial.value() > 0.0 {
+                    movement.loss = initial;
+                } else {
+                    movement.new_business = -initial;
+                }
+            }
+            let mut balance = movement.opening + movement.new_business;
+            loss_component += movement.loss;
+
+            if let Some(revision) = revisions.next_if(|r| r.step == start) {
+                let revised = self.fulfilment(definition, &revision.cashflows)?;
+                let change = revised.locked_in(start) - estimate.locked_in(start);
+                if change.value() < 0.0 {
+                    let reversal = if -change.value() < loss_component.value() {
+                        -change
+                    } else {
+                        loss_component
+                    };
+                    movement.loss = -reversal;
+                    movement.unlocking = -change - reversal;
+                } else {
+                    let absorbed = if change.value() < balance.value() {
+                        change
+                    } else {
+                        balance
+                    };
+                    movement.unlocking = -absorbed;
+                    movement.loss = change - absorbed;
+                }
+                loss_component += movement.loss;
+                balance += movement.unlocking;
+                for step in start..steps {
+                    fulfilment.bel_locked_in[step] = revised.bel_locked_in[step];
+                    fulfilment.bel_current[step] = revised.bel_current[step];
+                    fulfilment.risk_adjustment[step] = revised.risk_adjustment[step];
+                    fulfilment.risk_adjustment_locked_in[step] =
+                        revised.risk_adjustment_locked_in[step];
+                }
+                outgo = self.outgo(definition, &revision.cashflows, &revised)?;
+                estimate = revised;
+            }
+
+            let opening = outgo[start];
+            let closing = outgo.get(end).copied().unwrap_or(0.0);
+            if loss_component.value() > 0.0 && opening > 0.0 {
+                let ratio = ((opening - closing) / opening).clamp(0.0, 1.0);
+                movement.loss_allocation = loss_component * ratio;
+                loss_component -= movement.loss_allocation;
+            }
+
+            movement.accretion = balance * (discount(start) / discount(end) - 1.0);
+            balance += movement.accretion;
+            let remaining: f64 = coverage_units[start..].iter().sum();
+            let provided: f64 = coverage_units[start..end].iter().sum();
+            movement.release = if remaining > 0.0 {
+                balance * (provided / remaining)
+            } else {
+                balance
+            };
+            movement.closing = balance - movement.release;
+            movement.loss_component = loss_component;
+            csm = movement.closing;
+            movements.push(movement);
+        }
+        Ok(GroupMeasurement {
+            fulfilment,
+            movements,
+        })
+    }
+
+    /// Present value of outgo plus the risk adjustment at locked-in rates at
+    /// the start of each step, which the loss component is allocated against.
+    fn outgo(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        fulfilment: &Fulfilment,
+    ) -> Result<Vec<f64>, Ifrs17Error> {
+        Ok(self
+            .locked_in
+            .present_value(definition, cashflows, &OUTGO)?
+            .into_iter()
+            .zip(&fulfilment.risk_adjustment_locked_in)
+            .map(|(outgo, risk)| risk.value() - outgo.value())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{CashflowTiming, FlowDirection, KindMetadata, RequiredDataLayout};
+    use crate::{Date, generate_cashflow_dates};
+
+    pub(super) fn definition() -> ProductDefinition {
+        ProductDefinition::named(
+            vec!["active".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("claim", FlowDirection::Outflow, CashflowCategory::Benefit)
+                    .with_timing(CashflowTiming::EndOfStep),
+                KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// Four annual steps of level premium, claims and expenses.
+    pub(super) fn cashflows(start: Date, premium: f64, claim: f64) -> CashflowBuffer {
+        let times = generate_cashflow_dates(start, 4, Frequency::Annual).unwrap();
+        let mut buffer = CashflowBuffer::new(1, 3, times).unwrap();
+        buffer.series_mut(0, 0).fill(Amount::from_f64(premium));
+        buffer.series_mut(0, 1).fill(Amount::from_f64(claim));
+        buffer.series_mut(0, 2).fill(Amount::from_f64(10.0));
+        buffer
+    }
+
+    fn measurement() -> Measurement {
+        let capital = [50.0, 40.0, 30.0, 20.0].map(Amount::from_f64).to_vec();
+        Measurement::new(
+            DiscountCurve::flat(0.1).unwrap(),
+            DiscountCurve::flat(0.05).unwrap(),
+            Frequency::Annual,
+            RiskAdjustment::cost_of_capital(0.06, capital).unwrap(),
+        )
+    }
+
+    fn assert_close(actual: Amount, expected: f64) {
+        assert!(
+            (actual.value() - expected).abs() < 1e-9,
+            "{} != {expected}",
+            actual.value()
+        );
+    }
+
+    #[test]
+    fn csm_accretes_and_releases_by_coverage_units() {
+        let start = Date::constant(2024, 1, 1);
+        let gross = cashflows(start, 100.0, 60.0);
+        let measured = measurement()
+            .measure(&definition(), &gross, &[1.0; 4], &[])
+            .unwrap();
+
+        let v: f64 = 1.0 / 1.1;
+        let annuity: f64 = (0..4).map(|t| v.powi(t)).sum();
+        let arrears: f64 = (1..=4).map(|t| v.powi(t)).sum();
+        let bel = 60.0 * arrears - 90.0 * annuity;
+        let w: f64 = 1.0 / 1.05;
+        let cost = |d: f64| 0.06 * (50.0 * d + 40.0 * d * d + 30.0 * d.powi(3) + 20.0 * d.powi(4));
+        // The CSM is set against the risk adjustment at locked-in rates.
+        let ra = cost(v);
+        let fulfilment = &measured.fulfilment;
+        assert_close(fulfilment.bel_locked_in[0], bel);
+        assert_close(fulfilment.risk_adjustment[0], cost(w));
+        assert_close(fulfilment.risk_adjustment_locked_in[0], ra);
+        let current: f64 = 60.0 * (1..=4).map(|t| w.powi(t)).sum::<f64>()
+            - 90.0 * (0..4).map(|t| w.powi(t)).sum::<f64>();
+        assert_close(fulfilment.discount_rate_effect(0), current - bel);
+
+        let movements = &measured.movements;
+        assert_eq!(movements.len(), 4);
+        assert_close(movements[0].new_business, -(bel + ra));
+        assert_close(movements[0].accretion, -(bel + ra) * 0.1);
+        assert_close(movements[0].release, -(bel + ra) * 1.1 / 4.0);
+        for (t, movement) in movements.iter().enumerate() {
+            let rolled =
+                movement.opening + movement.new_business + movement.unlocking + movement.accretion
+                    - movement.release;
+            assert_close(movement.closing, rolled.value());
+            if t < 3 {
+                assert_close(movement.release, movement.closing.value() / (3 - t) as f64);
+            }
+            assert_eq!(movement.loss, Amount::zero());
+            if t > 0 {
+                assert_eq!(movement.opening, movements[t - 1].closing);
+            }
+        }
+        assert_eq!(movements[3].closing, Amount::zero());
+    }
+
+    #[test]
+    fn revisions_unlock_the_csm_and_losses_beyond_it() {
+        let start = Date::constant(2024, 1, 1);
+        let definition = definition();
+        let measurement = measurement();
+        let revise = |claim| Revision {
+            step: 2,
+            cashflows: cashflows(start, 100.0, claim),
+        };
+        let v: f64 = 1.0 / 1.1;
+        let remaining = v + v * v;
+
+        let favourable = measurement
+            .measure(
+                &definition,
+                &cashflows(start, 100.0, 60.0),
+                &[1.0; 4],
+                &[revise(40.0)],
+            )
+            .unwrap();
+        assert_close(favourable.movements[2].unlocking, 20.0 * remaining);
+        assert_eq!(favourable.movements[2].loss, Amount::zero());
+        assert_close(favourable.fulfilment.bel_locked_in[3], 40.0 * v - 90.0);
+
+        let adverse = measurement
+            .measure(
+                &definition,
+                &cashflows(start, 100.0, 60.0),
+                &[1.0; 4],
+                &[revise(120.0)],
+            )
+            .unwrap();
+        let movement = adverse.movements[2];
+        let balance = movement.opening;
+        assert_close(movement.unlocking, -balance.value());
+        assert_close(movement.loss, 60.0 * remaining - balance.value());
+        assert_eq!(movement.closing, Amount::zero());
+        assert!(movement.loss_allocation.value() > 0.0);
+        assert_close(
+            movement.loss_component,
+            (movement.loss - movement.loss_allocation).value(),
+        );
+
+        // An onerous group recognises its loss at once; a later favourable
+        // revision reverses the loss component before building a CSM.
+        let onerous = cashflows(start, 65.0, 60.0);
+        assert_eq!(
+            measurement.profitability(&definition, &onerous).unwrap(),
+            Profitability::Onerous
+        );
+        let reversed = measurement
+            .measure(&definition, &onerous, &[1.0; 4], &[revise(0.0)])
+            .unwrap();
+        let initial = reversed.movements[0].loss;
+        assert!(initial.value() > 0.0);
+        assert_eq!(reversed.movements[0].new_business, Amount::zero());
+        let remaining = reversed.movements[1].loss_component;
+        assert!(remaining.value() > 0.0 && remaining.value() < initial.value());
+        assert_eq!(reversed.movements[2].loss, -remaining);
+        assert_eq!(reversed.movements[2].loss_component, Amount::zero());
+        assert!(reversed.movements[2].unlocking.value() > 0.0);
+
+        assert_eq!(
+            measurement
+                .measure(
+                    &definition,
+                    &onerous,
+                    &[1.0; 4],
+                    &[Revision {
+                        step: 0,
+                        ..revise(0.0)
+                    }]
+                )
+                .unwrap_err(),
+            Ifrs17Error::Revision
+        );
+        assert_eq!(
+            measurement
+                .measure(&definition, &onerous, &[1.0; 3], &[])
+                .unwrap_err(),
+            Ifrs17Error::Shape
+        );
+    }
+
+    #[test]
+    fn loss_component_runs_off_with_coverage() {
+        let onerous = cashflows(Date::constant(2024, 1, 1), 65.0, 60.0);
+        let measured = measurement()
+            .measure(&definition(), &onerous, &[1.0; 4], &[])
+            .unwrap();
+        let movements = &measured.movements;
+        let initial = movements[0].loss;
+        assert!(initial.value() > 0.0);
+        let allocated: Amount = movements.iter().map(|m| m.loss_allocation).sum();
+        assert_close(allocated, initial.value());
+        for pair in movements.windows(2) {
+            assert!(pair[1].loss_component.value() < pair[0].loss_component.value());
+            assert_eq!(pair[1].loss, Amount::zero());
+        }
+        assert_close(movements[3].loss_component, 0.0);
+        assert!(movements.iter().all(|m| m.closing == Amount::zero()));
+    }
+
+    #[test]
+    fn confidence_level_loads_the_present_value_of_outgo() {
+        let scenarios: Vec<f64> = (1..=100).map(f64::from).collect();
+        let adjustment = RiskAdjustment::confidence_level(0.75, &scenarios).unwrap();
+        assert_eq!(
+            adjustment,
+            RiskAdjustment(RiskMethod::Loading((75.0 - 50.5) / 50.5))
+        );
+        assert!(RiskAdjustment::confidence_level(1.0, &scenarios).is_err());
+        assert!(RiskAdjustment::confidence_level(0.5, &[]).is_err());
+
+        let measurement = Measurement::new(
+            DiscountCurve::flat(0.0).unwrap(),
+            DiscountCurve::flat(0.0).unwrap(),
+            Frequency::Annual,
+            adjustment,
+        );
+        let gross = cashflows(Date::constant(2024, 1, 1), 100.0, 60.0);
+        let fulfilment = measurement.fulfilment(&definition(), &gross).unwrap();
+        assert_close(fulfilment.risk_adjustment[1], 210.0 * (24.5 / 50.5));
+        assert_close(fulfilment.bel_current[1], -90.0);
+    }
+}
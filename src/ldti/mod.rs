@@ -0,0 +1,417 @@
+//! US GAAP long-duration targeted improvements: the liability for future
+//! policy benefits and market risk benefits.
+
+mod mrb;
+
+pub use crate::valuation::Revision;
+pub use mrb::MarketRiskBenefit;
+
+use std::fmt;
+
+use crate::product::{Amount, CashflowBuffer, CashflowCategory, ProductDefinition};
+use crate::valuation::{DiscountCurve, Valuation, ValuationError, group_cashflows};
+use crate::{Date, Frequency};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LdtiError {
+    /// Fee attributions or kinds are out of range.
+    InvalidParameter(&'static str),
+    /// Cashflows of a cohort or revision do not share one shape and dates.
+    Shape,
+    /// Revisions must fall on later steps of the projection, in order.
+    Revision,
+    Valuation(ValuationError),
+}
+
+impl fmt::Display for LdtiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameter(name) => write!(f, "invalid {name}"),
+            Self::Shape => f.write_str("cashflows do not share one shape and dates"),
+            Self::Revision => f.write_str("revisions must fall on later steps in order"),
+            Self::Valuation(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LdtiError {}
+
+impl From<ValuationError> for LdtiError {
+    fn from(err: ValuationError) -> Self {
+        Self::Valuation(err)
+    }
+}
+
+/// Benefits and related expenses valued in the liability; commissions are
+/// acquisition costs and excluded.
+const BENEFITS: [CashflowCategory; 2] = [CashflowCategory::Benefit, CashflowCategory::Expense];
+
+/// Projection of one contract, or model point, from the start of its
+/// cohort's projection.
+#[derive(Debug, Clone)]
+pub struct Contract {
+    pub issue_date: Date,
+    pub cashflows: CashflowBuffer,
+}
+
+/// Contracts issued in one calendar year, with their cashflows summed.
+#[derive(Debug, Clone)]
+pub struct Cohort {
+    pub year: i16,
+    pub cashflows: CashflowBuffer,
+    /// Indices of the contracts in the input.
+    pub contracts: Vec<usize>,
+}
+
+impl Cohort {
+    /// Annual cohorts of `contracts` in year order; contracts of a cohort
+    /// must be projected over the same dates.
+    pub fn group(contracts: &[Contract]) -> Result<Vec<Self>, LdtiError> {
+        let groups = group_cashflows(
+            contracts,
+            |contract| &contract.cashflows,
+            |contract| Ok(contract.issue_date.year()),
+            LdtiError::Shape,
+        )?;
+        Ok(groups
+            .into_iter()
+            .map(|group| Self {
+                year: group.key,
+                cashflows: group.cashflows,
+                contracts: group.members,
+            })
+            .collect())
+    }
+}
+
+/// Change in the liability when the net premium ratio is updated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Remeasurement {
+    pub step: usize,
+    pub previous_ratio: f64,
+    pub net_premium_ratio: f64,
+    /// Liability at the updated ratio less the liability rolled forward at
+    /// the previous ratio with actual cashflows; positive for a loss.
+    pub gain_loss: Amount,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CohortMeasurement {
+    /// Ratio in force at each step.
+    pub net_premium_ratio: Vec<f64>,
+    /// Liability at the start of each step at the locked-in rate.
+    pub locked_in: Vec<Amount>,
+    /// Liability at the start of each step at the current rate.
+    pub current: Vec<Amount>,
+    /// Updates at issue and at each revision.
+    pub remeasurements: Vec<Remeasurement>,
+}
+
+impl CohortMeasurement {
+    /// Effect of the current over the locked-in discount rate, recognised in
+    /// other comprehensive income.
+    pub fn oci(&self, step: usize) -> Amount {
+        self.current[step] - self.locked_in[step]
+    }
+}
+
+/// Liability for future policy benefits of a cohort under the net premium
+/// approach.
+///
+/// The liability is the present value of future benefits and related
+/// expenses less the net premium ratio times the present value of future
+/// gross premiums. The ratio is the present value of benefits over that of
+/// premiums, both at issue and at the upper-medium grade rate locked in then,
+/// capped at one with the excess recognised as a loss. A [`Revision`] holds
+/// actual amounts before its step and re-estimated amounts from it on, and
+/// unlocks the ratio retrospectively: it is recalculated from actual
+/// cashflows to date and the updated estimate after, and the difference
+/// between the resulting liability and the liability rolled forward at the
+/// previous ratio is the remeasurement gain or loss. The current-rate
+/// liability uses the same ratio with the current rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuturePolicyBenefits {
+    locked_in: Valuation,
+    current: Valuation,
+    frequency: Frequency,
+}
+
+/// Present values of an estimate at each step, at the locked-in and current
+/// rates.
+struct Values {
+    benefits: Vec<f64>,
+    premiums: Vec<f64>,
+    current_benefits: Vec<f64>,
+    current_premiums: Vec<f64>,
+}
+
+impl FuturePolicyBenefits {
+    pub fn new(locked_in: DiscountCurve, current: DiscountCurve, frequency: Frequency) -> Self {
+        Self {
+            locked_in: Valuation::new(locked_in, frequency),
+            current: Valuation::new(current, frequency),
+            frequency,
+        }
+    }
+
+    pub fn measure(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        revisions: &[Revision],
+    ) -> Result<CohortMeasurement, LdtiError> {
+        let steps = cashflows.len_steps();
+        if steps == 0 {
+            return Err(LdtiError::Shape);
+        }
+        let mut previous = 0;
+        for revision in revisions {
+            let valid = revision.step > previous
+                && revision.step < steps
+                && revision.cashflows.is_compatible(cashflows);
+            if !valid {
+                return Err(LdtiError::Revision);
+            }
+            previous = revision.step;
+        }
+
+        let values = self.values(definition, cashflows)?;
+        let uncapped = ratio(values.benefits[0], values.premiums[0]);
+        let mut npr = uncapped.min(1.0);
+        // Liability at issue at the ratio in force, nonzero when capped.
+        let mut opening = values.benefits[0] - npr * values.premiums[0];
+        let mut measurement = CohortMeasurement {
+            net_premium_ratio: vec![npr; steps],
+            locked_in: vec![Amount::zero(); steps],
+            current: vec![Amount::zero(); steps],
+            remeasurements: Vec::new(),
+        };
+        measurement.fill(&values, npr, 0);
+        if uncapped > 1.0 {
+            measurement.remeasurements.push(Remeasurement {
+                step: 0,
+                previous_ratio: uncapped,
+                net_premium_ratio: npr,
+                gain_loss: measurement.locked_in[0],
+            });
+        }
+
+        let periods = f64::from(self.frequency.periods_per_year());
+        for revision in revisions {
+            let step = revision.step;
+            let values = self.values(definition, &revision.cashflows)?;
+            let discount = self.locked_in.curve().discount(step as f64 / periods);
+            // Values at issue of the revised cashflows before `step`.
+            let past_benefits = values.benefits[0] - values.benefits[step] * discount;
+            let past_premiums = values.premiums[0] - values.premiums[step] * discount;
+            let rolled_forward = (opening + npr * past_premiums - past_benefits) / discount;
+
+            let updated = ratio(values.benefits[0], values.premiums[0]).min(1.0);
+            opening = values.benefits[0] - updated * values.premiums[0];
+            measurement.fill(&values, updated, step);
+            measurement.remeasurements.push(Remeasurement {
+                step,
+                previous_ratio: npr,
+                net_premium_ratio: updated,
+                gain_loss: measurement.locked_in[step] - Amount::from_f64(rolled_forward),
+            });
+            npr = updated;
+        }
+        Ok(measurement)
+    }
+
+    fn values(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+    ) -> Result<Values, LdtiError> {
+        let value = |valuation: &Valuation, categories: &[CashflowCategory], sign: f64| {
+            valuation
+                .present_value(definition, cashflows, categories)
+                .map(|values| values.into_iter().map(|v| sign * v.value()).collect())
+        };
+        let premium = [CashflowCategory::Premium];
+        Ok(Values {
+            benefits: value(&self.locked_in, &BENEFITS, -1.0)?,
+            premiums: value(&self.locked_in, &premium, 1.0)?,
+            current_benefits: value(&self.current, &BENEFITS, -1.0)?,
+            current_premiums: value(&self.current, &premium, 1.0)?,
+        })
+    }
+}
+
+impl CohortMeasurement {
+    /// Sets the ratio and liabilities from `from` on.
+    fn fill(&mut self, values: &Values, npr: f64, from: usize) {
+        for step in from..self.locked_in.len() {
+            self.net_premium_ratio[step] = npr;
+            self.locked_in[step] =
+                Amount::from_f64(values.benefits[step] - npr * values.premiums[step]);
+            self.current[step] = Amount::from_f64(
+                values.current_benefits[step] - npr * values.current_premiums[step],
+            );
+        }
+    }
+}
+
+/// Benefits over premiums; zero without premiums.
+fn ratio(benefits: f64, premiums: f64) -> f64 {
+    if premiums > 0.0 {
+        benefits / premiums
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_cashflow_dates;
+    use crate::product::{CashflowTiming, FlowDirection, KindMetadata, RequiredDataLayout};
+
+    pub(super) fn definition() -> ProductDefinition {
+        ProductDefinition::named(
+            vec!["active".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("claim", FlowDirection::Outflow, CashflowCategory::Benefit)
+                    .with_timing(CashflowTiming::EndOfStep),
+                KindMetadata::new(
+                    "commission",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Commission,
+                ),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// Four annual steps of level premiums and the given claims.
+    fn cashflows(premium: f64, claims: [f64; 4]) -> CashflowBuffer {
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 4, Frequency::Annual).unwrap();
+        let mut buffer = CashflowBuffer::new(1, 3, times).unwrap();
+        buffer.series_mut(0, 0).fill(Amount::from_f64(premium));
+        buffer
+            .series_mut(0, 1)
+            .copy_from_slice(&claims.map(Amount::from_f64));
+        buffer.series_mut(0, 2).fill(Amount::from_f64(25.0));
+        buffer
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn net_premium_ratio_unlocks_retrospectively() {
+        let lfpb = FuturePolicyBenefits::new(
+            DiscountCurve::flat(0.0).unwrap(),
+            DiscountCurve::flat(0.05).unwrap(),
+            Frequency::Annual,
+        );
+        let definition = definition();
+        let expected = cashflows(100.0, [40.0, 60.0, 80.0, 100.0]);
+        let measured = lfpb.measure(&definition, &expected, &[]).unwrap();
+        // Undiscounted: 280 of claims over 400 of premiums; commissions are
+        // not benefits.
+        assert_close(measured.net_premium_ratio[0], 0.7);
+        let locked_in: Vec<f64> = measured.locked_in.iter().map(|a| a.value()).collect();
+        assert_close(locked_in[0], 0.0);
+        assert_close(locked_in[2], 180.0 - 0.7 * 200.0);
+        assert!(measured.remeasurements.is_empty());
+
+        let w: f64 = 1.0 / 1.05;
+        let current = 80.0 * w + 100.0 * w * w - 70.0 * (1.0 + w);
+        assert_close(measured.oci(2).value(), current - locked_in[2]);
+
+        // Actual claims of 50 in the second year and 90 expected later.
+        let revised = cashflows(100.0, [40.0, 50.0, 90.0, 90.0]);
+        let measured = lfpb
+            .measure(
+                &definition,
+                &expected,
+                &[Revision {
+                    step: 2,
+                    cashflows: revised,
+                }],
+            )
+            .unwrap();
+        let remeasurement = measured.remeasurements[0];
+        assert_close(remeasurement.net_premium_ratio, 0.675);
+        assert_eq!(measured.net_premium_ratio[1], 0.7);
+        assert_eq!(measured.net_premium_ratio[2], 0.675);
+        // Rolled forward at 0.7: 140 of net premiums less 90 of claims.
+        let rolled_forward = 0.7 * 200.0 - 90.0;
+        let liability = 180.0 - 0.675 * 200.0;
+        assert_close(measured.locked_in[2].value(), liability);
+        assert_close(remeasurement.gain_loss.value(), liability - rolled_forward);
+        assert_close(remeasurement.gain_loss.value(), (0.675 - 0.7) * 200.0);
+
+        assert_eq!(
+            lfpb.measure(
+                &definition,
+                &expected,
+                &[Revision {
+                    step: 4,
+                    cashflows: expected.clone(),
+                }],
+            )
+            .unwrap_err(),
+            LdtiError::Revision
+        );
+    }
+
+    #[test]
+    fn net_premium_ratio_is_capped_with_a_loss() {
+        let lfpb = FuturePolicyBenefits::new(
+            DiscountCurve::flat(0.0).unwrap(),
+            DiscountCurve::flat(0.0).unwrap(),
+            Frequency::Annual,
+        );
+        let measured = lfpb
+            .measure(&definition(), &cashflows(50.0, [60.0; 4]), &[])
+            .unwrap();
+        assert_eq!(measured.net_premium_ratio[0], 1.0);
+        assert_eq!(measured.remeasurements[0].previous_ratio, 1.2);
+        assert_close(measured.remeasurements[0].gain_loss.value(), 40.0);
+        assert_close(measured.locked_in[1].value(), 30.0);
+
+        // An unchanged estimate rolls the issue loss forward without a
+        // second one.
+        let measured = lfpb
+            .measure(
+                &definition(),
+                &cashflows(50.0, [60.0; 4]),
+                &[Revision {
+                    step: 2,
+                    cashflows: cashflows(50.0, [60.0; 4]),
+                }],
+            )
+            .unwrap();
+        assert_close(measured.locked_in[2].value(), 20.0);
+        assert_close(measured.remeasurements[1].gain_loss.value(), 0.0);
+    }
+
+    #[test]
+    fn groups_contracts_by_issue_year() {
+        let contract = |year, premium| Contract {
+            issue_date: Date::constant(year, 6, 1),
+            cashflows: cashflows(premium, [10.0; 4]),
+        };
+        let cohorts = Cohort::group(&[
+            contract(2024, 100.0),
+            contract(2023, 80.0),
+            contract(2024, 50.0),
+        ])
+        .unwrap();
+        assert_eq!(cohorts.len(), 2);
+        assert_eq!((cohorts[0].year, cohorts[1].year), (2023, 2024));
+        assert_eq!(cohorts[1].contracts, [0, 2]);
+        assert_eq!(
+            cohorts[1].cashflows.amount(0, 0, 3),
+            Amount::from_f64(150.0)
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use super::LdtiError;
+use crate::Frequency;
+use crate::product::{Amount, CashflowBuffer, CashflowKindId, ProductDefinition};
+use crate::valuation::{DiscountCurve, Valuation};
+
+/// Market risk benefit measured at fair value.
+///
+/// The fair value is the present value of expected guarantee claims less the
+/// attributed fees, from cashflows averaged over risk-neutral scenarios and
+/// discounted on a curve that includes the insurer's own credit spread. The
+/// attributed fraction of fees is fixed at issue so that the fair value is
+/// then zero, capped at all of the fees; a guarantee the fees cannot fund
+/// starts with a liability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketRiskBenefit {
+    benefits: Vec<CashflowKindId>,
+    fees: Vec<CashflowKindId>,
+    attributed_fraction: f64,
+}
+
+impl MarketRiskBenefit {
+    /// Attributes fees from the projection at issue.
+    pub fn at_issue(
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        benefits: Vec<CashflowKindId>,
+        fees: Vec<CashflowKindId>,
+        curve: DiscountCurve,
+        frequency: Frequency,
+    ) -> Result<Self, LdtiError> {
+        if benefits.is_empty() || fees.is_empty() || cashflows.len_steps() == 0 {
+            return Err(LdtiError::InvalidParameter("market risk benefit kinds"));
+        }
+        let valuation = Valuation::new(curve, frequency);
+        let claims = -valuation.present_value_of(definition, cashflows, &benefits)?[0].value();
+        let income = valuation.present_value_of(definition, cashflows, &fees)?[0].value();
+        let attributed_fraction = if income > 0.0 {
+            (claims / income).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Ok(Self {
+            benefits,
+            fees,
+            attributed_fraction,
+        })
+    }
+
+    pub fn attributed_fraction(&self) -> f64 {
+        self.attributed_fraction
+    }
+
+    /// Fair value at the start of each step, positive for a liability.
+    pub fn fair_value(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        curve: DiscountCurve,
+        frequency: Frequency,
+    ) -> Result<Vec<Amount>, LdtiError> {
+        let valuation = Valuation::new(curve, frequency);
+        let claims = valuation.present_value_of(definition, cashflows, &self.benefits)?;
+        let fees = valuation.present_value_of(definition, cashflows, &self.fees)?;
+        Ok(claims
+            .into_iter()
+            .zip(fees)
+            .map(|(claims, fees)| -(claims + fees * self.attributed_fraction))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{
+        CashflowCategory, CashflowTiming, FlowDirection, KindMetadata, RequiredDataLayout,
+    };
+    use crate::{Date, generate_cashflow_dates};
+
+    const FEE: CashflowKindId = CashflowKindId(0);
+    const GMAB: CashflowKindId = CashflowKindId(1);
+
+    #[test]
+    fn attributed_fees_set_the_fair_value_at_issue_to_zero() {
+        let end = |name, direction, category| {
+            KindMetadata::new(name, direction, category).with_timing(CashflowTiming::EndOfStep)
+        };
+        let definition = ProductDefinition::named(
+            vec!["active".into()],
+            vec![
+                end("rider_fee", FlowDirection::Inflow, CashflowCategory::Other),
+                end("gmab", FlowDirection::Outflow, CashflowCategory::Benefit),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 4, Frequency::Annual).unwrap();
+        let mut cashflows = CashflowBuffer::new(1, 2, times).unwrap();
+        cashflows.series_mut(0, FEE.0).fill(Amount::from_f64(5.0));
+        *cashflows.amount_mut(0, GMAB.0, 3) = Amount::from_f64(12.0);
+
+        let flat = |rate| DiscountCurve::flat(rate).unwrap();
+        let mrb = MarketRiskBenefit::at_issue(
+            &definition,
+            &cashflows,
+            vec![GMAB],
+            vec![FEE],
+            flat(0.0),
+            Frequency::Annual,
+        )
+        .unwrap();
+        assert!((mrb.attributed_fraction() - 0.6).abs() < 1e-12);
+        let fair_value = mrb
+            .fair_value(&definition, &cashflows, flat(0.0), Frequency::Annual)
+            .unwrap();
+        assert!(fair_value[0].value().abs() < 1e-12);
+        assert!((fair_value[2].value() - 6.0).abs() < 1e-12);
+        let discounted = mrb
+            .fair_value(&definition, &cashflows, flat(0.05), Frequency::Annual)
+            .unwrap();
+        assert!((discounted[3].value() - 9.0 / 1.05).abs() < 1e-12);
+
+        // Fees that cannot fund the guarantee are attributed in full.
+        *cashflows.amount_mut(0, GMAB.0, 3) = Amount::from_f64(30.0);
+        let mrb = MarketRiskBenefit::at_issue(
+            &definition,
+            &cashflows,
+            vec![GMAB],
+            vec![FEE],
+            flat(0.0),
+            Frequency::Annual,
+        )
+        .unwrap();
+        assert_eq!(mrb.attributed_fraction(), 1.0);
+        let fair_value = mrb
+            .fair_value(&definition, &cashflows, flat(0.0), Frequency::Annual)
+            .unwrap();
+        assert!((fair_value[0].value() - 10.0).abs() < 1e-12);
+
+        assert_eq!(
+            MarketRiskBenefit::at_issue(
+                &definition,
+                &cashflows,
+                Vec::new(),
+                vec![FEE],
+                flat(0.0),
+                Frequency::Annual,
+            )
+            .unwrap_err(),
+            LdtiError::InvalidParameter("market risk benefit kinds")
+        );
+        assert_eq!(
+            mrb.fair_value(
+                &ProductDefinition::new(1, 1, RequiredDataLayout::new(1, 0).unwrap()).unwrap(),
+                &cashflows,
+                flat(0.0),
+                Frequency::Annual,
+            )
+            .unwrap_err(),
+            LdtiError::Valuation(crate::valuation::ValuationError::Shape)
+        );
+    }
+}
@@ -1,10 +1,18 @@
 mod date;
 
+pub mod export;
+pub mod fx;
+pub mod ifrs17;
+pub mod ldti;
+pub mod life;
 pub mod model;
+pub mod portfolio;
 pub mod product;
+pub mod reinsurance;
 pub mod rng;
+pub mod valuation;
 
 pub use date::{
     Date, DateError, Frequency, cashflow_date_at, days_in_month, generate_cashflow_dates,
-    is_leap_year,
+    is_leap_year, period_days, periods_spanning_days,
 };
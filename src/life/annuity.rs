@@ -0,0 +1,642 @@
+use std::marker::PhantomData;
+
+use super::{Expenses, LifeError, MortalityTable, Schedule};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CashflowTiming, FlagField,
+    FlowDirection, KindMetadata, Product, ProductDefinition, ProductState, RateField,
+    RequiredDataBuffer, RequiredDataLayout, ScalarField,
+};
+use crate::rng::RngCore;
+
+/// When each step's annuity payment falls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PaymentTiming {
+    /// At the start of the step, to lives in force then.
+    #[default]
+    Advance,
+    /// At the end of the step, to lives surviving it.
+    Arrears,
+}
+
+impl PaymentTiming {
+    const fn cashflow_timing(self) -> CashflowTiming {
+        match self {
+            Self::Advance => CashflowTiming::StartOfStep,
+            Self::Arrears => CashflowTiming::EndOfStep,
+        }
+    }
+}
+
+/// Marker for [`LifeAnnuity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SingleLife;
+
+/// Marker for [`JointLifeAnnuity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JointLife;
+
+/// Annuity on a single life, with states `alive` and `dead`.
+pub type LifeAnnuity = Annuity<SingleLife>;
+
+/// Joint-and-survivor annuity on a primary and a secondary life, with states
+/// `both_alive`, `primary_alive`, `secondary_alive` and `dead`. Lives are
+/// assumed independent.
+pub type JointLifeAnnuity = Annuity<JointLife>;
+
+#[derive(Debug, Clone)]
+struct Life {
+    mortality: MortalityTable,
+    age: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    primary_q: ScalarField<RateField>,
+    secondary_q: Option<ScalarField<RateField>>,
+    payment: ScalarField<AmountField>,
+    index: ScalarField<RateField>,
+    paying: ScalarField<FlagField>,
+    guaranteed: ScalarField<FlagField>,
+    purchase_price: ScalarField<AmountField>,
+}
+
+/// Life annuity bought with a single purchase price.
+///
+/// Payments of `annual_amount` are spread over the steps of `frequency` and
+/// start after an optional deferral period. During the guarantee period they
+/// are paid whether or not the annuitants survive (certain-and-life). On a
+/// joint annuity the secondary life receives `reversionary` times the payment
+/// after the primary life dies. Payments are indexed by the cumulative
+/// escalation of [`with_indexation`](Self::with_indexation), which is written
+/// to the required data as `index`. The purchase price and initial expenses
+/// fall at the first step; per-policy expenses while either life is alive.
+/// Amounts are per policy and scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct Annuity<L> {
+    definition: ProductDefinition,
+    fields: Fields,
+    primary: Life,
+    secondary: Option<Life>,
+    reversionary: f64,
+    annual_amount: f64,
+    deferral_years: u32,
+    guarantee_years: u32,
+    indexation: Schedule,
+    frequency: Frequency,
+    timing: PaymentTiming,
+    purchase_price: f64,
+    expenses: Expenses,
+    in_force: u64,
+    lives: PhantomData<L>,
+}
+
+impl Annuity<SingleLife> {
+    pub const ALIVE: usize = 0;
+    pub const DEAD: usize = 1;
+
+    /// Immediate annuity of `annual_amount` on a life aged `age`, paid
+    /// annually in advance.
+    pub fn new(mortality: MortalityTable, age: u32, annual_amount: f64) -> Result<Self, LifeError> {
+        Self::build(Life { mortality, age }, None, 0.0, annual_amount)
+    }
+}
+
+impl Annuity<JointLife> {
+    pub const BOTH_ALIVE: usize = 0;
+    pub const PRIMARY_ALIVE: usize = 1;
+    pub const SECONDARY_ALIVE: usize = 2;
+    pub const DEAD: usize = 3;
+
+    /// Immediate joint-and-survivor annuity of `annual_amount`, reducing to
+    /// `reversionary` times the payment while only the secondary life is
+    /// alive, paid annually in advance.
+    pub fn new(
+        primary_mortality: MortalityTable,
+        primary_age: u32,
+        secondary_mortality: MortalityTable,
+        secondary_age: u32,
+        annual_amount: f64,
+        reversionary: f64,
+    ) -> Result<Self, LifeError> {
+        if !(0.0..=1.0).contains(&reversionary) {
+            return Err(LifeError::InvalidParameter("reversionary percentage"));
+        }
+        Self::build(
+            Life {
+                mortality: primary_mortality,
+                age: primary_age,
+            },
+            Some(Life {
+                mortality: secondary_mortality,
+                age: secondary_age,
+            }),
+            reversionary,
+            annual_amount,
+        )
+    }
+
+    pub const fn reversionary(&self) -> f64 {
+        self.reversionary
+    }
+}
+
+impl<L> Annuity<L> {
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const ANNUITY: CashflowKindId = CashflowKindId(1);
+    pub const EXPENSE: CashflowKindId = CashflowKindId(2);
+
+    fn build(
+        primary: Life,
+        secondary: Option<Life>,
+        reversionary: f64,
+        annual_amount: f64,
+    ) -> Result<Self, LifeError> {
+        if !annual_amount.is_finite() {
+            return Err(LifeError::InvalidParameter("annual amount"));
+        }
+        let joint = secondary.is_some();
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            primary_q: builder.policy_scalar("primary_q"),
+            secondary_q: joint.then(|| builder.policy_scalar("secondary_q")),
+            payment: builder.policy_scalar("payment"),
+            index: builder.policy_scalar("index"),
+            paying: builder.policy_scalar("paying"),
+            guaranteed: builder.policy_scalar("guaranteed"),
+            purchase_price: builder.policy_scalar("purchase_price"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let states: &[&str] = if joint {
+            &["both_alive", "primary_alive", "secondary_alive", "dead"]
+        } else {
+            &["alive", "dead"]
+        };
+        let timing = PaymentTiming::default();
+        let definition = ProductDefinition::named(
+            states.iter().map(|s| s.to_string()).collect(),
+            Self::kinds(timing),
+            layout,
+        )?;
+        Ok(Self {
+            definition,
+            fields,
+            primary,
+            secondary,
+            reversionary,
+            annual_amount,
+            deferral_years: 0,
+            guarantee_years: 0,
+            indexation: Schedule::level(0.0),
+            frequency: Frequency::Annual,
+            timing,
+            purchase_price: 0.0,
+            expenses: Expenses::default(),
+            in_force: 1,
+            lives: PhantomData,
+        })
+    }
+
+    fn kinds(timing: PaymentTiming) -> Vec<KindMetadata> {
+        vec![
+            KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+            KindMetadata::new("annuity", FlowDirection::Outflow, CashflowCategory::Benefit)
+                .with_timing(timing.cashflow_timing()),
+            KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+        ]
+    }
+
+    /// Projection step length and payment frequency, reported by
+    /// [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_timing(mut self, timing: PaymentTiming) -> Self {
+        self.timing = timing;
+        self.definition = ProductDefinition::named(
+            self.definition.state_names().to_vec(),
+            Self::kinds(timing),
+            self.definition.required_data.clone(),
+        )
+        .expect("annuity kinds are valid");
+        self
+    }
+
+    /// Defers the first payment by `years` from issue.
+    pub fn with_deferral(mut self, years: u32) -> Self {
+        self.deferral_years = years;
+        self
+    }
+
+    /// Guarantees payments for `years` from the first payment.
+    pub fn with_guarantee(mut self, years: u32) -> Self {
+        self.guarantee_years = years;
+        self
+    }
+
+    /// Annual escalation rates by policy year. Payments in year `k` are
+    /// increased by the rates of years `0..k`.
+    pub fn with_indexation(mut self, rates: Schedule) -> Self {
+        self.indexation = rates;
+        self
+    }
+
+    /// Single premium received at the first step.
+    pub fn with_purchase_price(mut self, price: f64) -> Self {
+        self.purchase_price = price;
+        self
+    }
+
+    /// Expenses; `premium_fraction` applies to the purchase price.
+    pub fn with_expenses(mut self, expenses: Expenses) -> Self {
+        self.expenses = expenses;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    pub const fn deferral_years(&self) -> u32 {
+        self.deferral_years
+    }
+
+    pub const fn guarantee_years(&self) -> u32 {
+        self.guarantee_years
+    }
+
+    pub const fn timing(&self) -> PaymentTiming {
+        self.timing
+    }
+
+    fn periods(&self) -> u32 {
+        self.frequency.periods_per_year()
+    }
+
+    fn dead(&self) -> usize {
+        self.definition.n_states - 1
+    }
+
+    /// Probabilities that the primary and secondary lives survive the step;
+    /// a missing secondary life never survives.
+    fn survival(&self, data: &RequiredDataBuffer) -> (f64, f64) {
+        let primary = 1.0 - data.get(self.fields.primary_q);
+        let secondary = self.fields.secondary_q.map_or(0.0, |q| 1.0 - data.get(q));
+        (primary, secondary)
+    }
+
+    /// Primary and secondary alive at the start of the step, by state.
+    fn alive(&self, state_id: usize) -> (bool, bool) {
+        match (self.secondary.is_some(), state_id) {
+            (false, 0) => (true, false),
+            (true, 0) => (true, true),
+            (true, 1) => (true, false),
+            (true, 2) => (false, true),
+            _ => (false, false),
+        }
+    }
+}
+
+impl<L> Product for Annuity<L> {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(0, self.in_force, Amount::zero())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        _state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.periods();
+        let year = time_index / m as usize;
+        let f = &self.fields;
+        out.set(
+            f.primary_q,
+            self.primary
+                .mortality
+                .q_step(self.primary.age + year as u32, m),
+        );
+        if let (Some(field), Some(life)) = (f.secondary_q, &self.secondary) {
+            out.set(field, life.mortality.q_step(life.age + year as u32, m));
+        }
+        let index: f64 = (0..year).map(|k| 1.0 + self.indexation.at(k)).product();
+        let deferral = self.deferral_years as usize;
+        let paying = year >= deferral;
+        let guaranteed = paying && year < deferral + self.guarantee_years as usize;
+        let payment = if paying {
+            self.annual_amount * index / f64::from(m)
+        } else {
+            0.0
+        };
+        out.set(f.index, index);
+        out.set(f.paying, paying);
+        out.set(f.guaranteed, guaranteed);
+        out.set(f.payment, Amount::from_f64(payment));
+        let price = if time_index == 0 {
+            self.purchase_price
+        } else {
+            0.0
+        };
+        out.set(f.purchase_price, Amount::from_f64(price));
+    }
+
+    fn cashflows(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        let n = state.in_force as f64;
+        let (primary, secondary) = self.alive(state.state_id);
+        let price = data.get(f.purchase_price);
+
+        // Expected share of the full payment, given the lives alive at the
+        // start of the step.
+        let share = if data.get(f.guaranteed) {
+            1.0
+        } else if !data.get(f.paying) {
+            0.0
+        } else {
+            let (p1, p2) = match self.timing {
+                PaymentTiming::Advance => (1.0, 1.0),
+                PaymentTiming::Arrears => self.survival(data),
+            };
+            let p1 = if primary { p1 } else { 0.0 };
+            let p2 = if secondary { p2 } else { 0.0 };
+            p1 + (1.0 - p1) * self.reversionary * p2
+        };
+        out[Self::PREMIUM.0] = price * n;
+        out[Self::ANNUITY.0] = data.get(f.payment) * (share * n);
+
+        if primary || secondary {
+            let initial = if time_index == 0 {
+                self.expenses.initial
+            } else {
+                0.0
+            };
+            let expense = initial
+                + self.expenses.per_policy / f64::from(self.periods())
+                + self.expenses.premium_fraction * price.value();
+            out[Self::EXPENSE.0] = Amount::from_f64(expense * n);
+        }
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = [0.0; 4];
+        let row = &mut row[..self.definition.n_states];
+        self.transition_probabilities(time_index, state, data, row);
+        ProductState {
+            state_id: sample_transition(row, rng),
+            ..*state
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        let (primary, secondary) = self.alive(state.state_id);
+        let (p1, p2) = self.survival(data);
+        let p1 = if primary { p1 } else { 0.0 };
+        let p2 = if secondary { p2 } else { 0.0 };
+        if self.secondary.is_none() {
+            out[0] = p1;
+        } else {
+            out[0] = p1 * p2;
+            out[1] = p1 * (1.0 - p2);
+            out[2] = (1.0 - p1) * p2;
+        }
+        out[self.dead()] = (1.0 - p1) * (1.0 - p2);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::CommutationTable;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    const V: f64 = 1.0 / 1.05;
+
+    fn project<L>(product: &Annuity<L>, steps: usize) -> CashflowBuffer {
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: product.frequency,
+            steps,
+        };
+        let times = generate_cashflow_dates(config.start, steps, config.frequency).unwrap();
+        let definition = product.definition();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        ExpectedValueModel
+            .run(
+                product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        cashflows
+    }
+
+    /// Expected amounts of `kind` summed over states.
+    fn total(cashflows: &CashflowBuffer, kind: CashflowKindId) -> Vec<f64> {
+        (0..cashflows.len_steps())
+            .map(|t| {
+                (0..cashflows.n_states())
+                    .map(|s| cashflows.series(s, kind.0)[t].value())
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Present value at 5% with amounts `delay` steps after the step start.
+    fn present_value(amounts: &[f64], periods: u32, delay: i32) -> f64 {
+        let v = V.powf(1.0 / f64::from(periods));
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(t, a)| v.powi(t as i32 + delay) * a)
+            .sum()
+    }
+
+    const STEPS: usize = 70;
+
+    #[test]
+    fn single_life_annuities_match_commutation_functions() {
+        let table = MortalityTable::standard_ultimate();
+        let commutation = CommutationTable::new(&table, 0.05).unwrap();
+
+        let immediate = LifeAnnuity::new(table.clone(), 65, 1_000.0)
+            .unwrap()
+            .with_purchase_price(15_000.0);
+        let cashflows = project(&immediate, STEPS);
+        let pv = present_value(&total(&cashflows, LifeAnnuity::ANNUITY), 1, 0);
+        assert!((pv / 1_000.0 - commutation.annuity_due(65)).abs() < 1e-9);
+        assert_eq!(total(&cashflows, LifeAnnuity::PREMIUM)[0], 15_000.0);
+        assert_eq!(total(&cashflows, LifeAnnuity::PREMIUM)[1], 0.0);
+
+        let arrears = immediate.clone().with_timing(PaymentTiming::Arrears);
+        assert_eq!(
+            arrears.definition().kind(LifeAnnuity::ANNUITY).timing,
+            CashflowTiming::EndOfStep
+        );
+        let cashflows = project(&arrears, STEPS);
+        let pv = present_value(&total(&cashflows, LifeAnnuity::ANNUITY), 1, 1);
+        assert!((pv / 1_000.0 - (commutation.annuity_due(65) - 1.0)).abs() < 1e-9);
+
+        let deferred = LifeAnnuity::new(table.clone(), 55, 1_000.0)
+            .unwrap()
+            .with_deferral(10);
+        let cashflows = project(&deferred, STEPS + 10);
+        let payments = total(&cashflows, LifeAnnuity::ANNUITY);
+        assert!(payments[..10].iter().all(|p| *p == 0.0));
+        let pv = present_value(&payments, 1, 0);
+        let expected = commutation.n(65) / commutation.d(55);
+        assert!((pv / 1_000.0 - expected).abs() < 1e-9);
+
+        let guaranteed = LifeAnnuity::new(table, 65, 1_000.0)
+            .unwrap()
+            .with_guarantee(10);
+        let cashflows = project(&guaranteed, STEPS);
+        let payments = total(&cashflows, LifeAnnuity::ANNUITY);
+        assert!(payments[..10].iter().all(|p| (*p - 1_000.0).abs() < 1e-9));
+        assert!(cashflows.series(LifeAnnuity::DEAD, LifeAnnuity::ANNUITY.0)[5].value() > 0.0);
+        let certain: f64 = (0..10).map(|t| V.powi(t)).sum();
+        let expected = certain + commutation.n(75) / commutation.d(65);
+        let pv = present_value(&payments, 1, 0);
+        assert!((pv / 1_000.0 - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn indexation_and_monthly_payments() {
+        let table = MortalityTable::standard_ultimate();
+        let product = LifeAnnuity::new(table.clone(), 70, 1_200.0)
+            .unwrap()
+            .with_frequency(Frequency::Monthly)
+            .with_indexation(Schedule::new(vec![0.02, 0.03]).unwrap())
+            .with_expenses(Expenses {
+                initial: 100.0,
+                per_policy: 24.0,
+                premium_fraction: 0.01,
+            })
+            .with_purchase_price(20_000.0)
+            .with_in_force(3);
+        let definition = product.definition();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let index = definition
+            .required_data
+            .scalar_field::<RateField>("index")
+            .unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        let state = product.initial_state();
+        product.generate_required_data(30, &state, &mut rng, &mut data);
+        assert!((data.get(index) - 1.02 * 1.03).abs() < 1e-12);
+        product.generate_required_data(40, &state, &mut rng, &mut data);
+        assert!((data.get(index) - 1.02 * 1.03 * 1.03).abs() < 1e-12);
+
+        let cashflows = project(&product, 36);
+        let payments = total(&cashflows, LifeAnnuity::ANNUITY);
+        assert!((payments[0] - 300.0).abs() < 1e-9);
+        let alive = table.survival(70, 1);
+        assert!((payments[12] - 300.0 * 1.02 * alive).abs() < 1e-9);
+        let expenses = total(&cashflows, LifeAnnuity::EXPENSE);
+        assert!((expenses[0] - 3.0 * (100.0 + 2.0 + 200.0)).abs() < 1e-9);
+        assert!((expenses[12] - 3.0 * 2.0 * alive).abs() < 1e-9);
+    }
+
+    #[test]
+    fn joint_and_survivor_annuities_value_the_reversion() {
+        let table = MortalityTable::standard_ultimate();
+        let product =
+            JointLifeAnnuity::new(table.clone(), 65, table.clone(), 62, 1_000.0, 0.6).unwrap();
+        assert_eq!(product.definition().n_states, 4);
+        assert_eq!(
+            product.definition().state_id("secondary_alive"),
+            Some(JointLifeAnnuity::SECONDARY_ALIVE)
+        );
+
+        // ä_x + r (ä_y - ä_xy) for independent lives.
+        let annuity = |f: &dyn Fn(u32) -> f64| -> f64 {
+            (0..STEPS as u32).map(|t| V.powi(t as i32) * f(t)).sum()
+        };
+        let x = annuity(&|t| table.survival(65, t));
+        let y = annuity(&|t| table.survival(62, t));
+        let xy = annuity(&|t| table.survival(65, t) * table.survival(62, t));
+        for timing in [PaymentTiming::Advance, PaymentTiming::Arrears] {
+            let product = product.clone().with_timing(timing);
+            let cashflows = project(&product, STEPS);
+            let payments = total(&cashflows, JointLifeAnnuity::ANNUITY);
+            let (pv, expected) = match timing {
+                PaymentTiming::Advance => (present_value(&payments, 1, 0), x + 0.6 * (y - xy)),
+                PaymentTiming::Arrears => {
+                    (present_value(&payments, 1, 1), x - 1.0 + 0.6 * (y - xy))
+                }
+            };
+            assert!((pv / 1_000.0 - expected).abs() < 1e-6, "{timing:?}");
+        }
+        assert!(JointLifeAnnuity::new(table.clone(), 65, table, 62, 1_000.0, 1.5).is_err());
+    }
+
+    #[test]
+    fn simulation_moves_through_joint_life_states() {
+        let table = MortalityTable::new(60, vec![0.5]).unwrap();
+        let product = JointLifeAnnuity::new(table.clone(), 60, table, 60, 100.0, 0.5).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(product.definition().required_data.clone(), 4).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(3);
+        let mut counts = [0; 4];
+        for _ in 0..4000 {
+            let state = product.initial_state();
+            product.generate_required_data(0, &state, &mut rng, &mut data);
+            counts[product.next_state(0, &state, &data, &mut rng).state_id] += 1;
+        }
+        assert!(counts.iter().all(|c| (850..1150).contains(c)), "{counts:?}");
+
+        let widow = ProductState::new(JointLifeAnnuity::SECONDARY_ALIVE, 1, Amount::zero());
+        let mut out = [Amount::zero(); 3];
+        product.cashflows(0, &widow, &data, &mut out);
+        assert_eq!(out[JointLifeAnnuity::ANNUITY.0].value(), 50.0);
+        let mut row = [0.0; 4];
+        product.transition_probabilities(0, &widow, &data, &mut row);
+        assert_eq!(row, [0.0, 0.0, 0.5, 0.5]);
+    }
+}
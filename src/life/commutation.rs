@@ -0,0 +1,254 @@
+use super::{LifeError, MortalityTable};
+
+/// Radix `l_x` at the youngest age of the table.
+const RADIX: f64 = 100_000.0;
+
+/// Commutation columns of a mortality table at a fixed annual interest rate.
+///
+/// Columns run from [`MortalityTable::min_age`] to one year past
+/// [`MortalityTable::max_age`], where every remaining life dies, and are zero
+/// beyond. Benefits are paid at the end of the year of death and annuities
+/// annually in advance, per unit sum assured or payment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommutationTable {
+    min_age: u32,
+    interest: f64,
+    d: Vec<f64>,
+    n: Vec<f64>,
+    c: Vec<f64>,
+    m: Vec<f64>,
+    r: Vec<f64>,
+    s: Vec<f64>,
+}
+
+impl CommutationTable {
+    pub fn new(mortality: &MortalityTable, interest: f64) -> Result<Self, LifeError> {
+        if !interest.is_finite() || interest <= -1.0 {
+            return Err(LifeError::InvalidParameter("interest"));
+        }
+        let v = 1.0 / (1.0 + interest);
+        let min_age = mortality.min_age();
+        let ages = (min_age..=mortality.max_age() + 1).collect::<Vec<_>>();
+
+        let mut d = Vec::with_capacity(ages.len());
+        let mut c = Vec::with_capacity(ages.len());
+        let mut l = RADIX;
+        let mut discount = 1.0;
+        for &age in &ages {
+            let deaths = l * mortality.q(age);
+            d.push(discount * l);
+            c.push(discount * v * deaths);
+            l -= deaths;
+            discount *= v;
+        }
+        let n = tail_sums(&d);
+        let m = tail_sums(&c);
+        let r = tail_sums(&m);
+        let s = tail_sums(&n);
+        Ok(Self {
+            min_age,
+            interest,
+            d,
+            n,
+            c,
+            m,
+            r,
+            s,
+        })
+    }
+
+    pub const fn min_age(&self) -> u32 {
+        self.min_age
+    }
+
+    pub const fn interest(&self) -> f64 {
+        self.interest
+    }
+
+    /// `D_x = v^x l_x`, discounting from the youngest age of the table.
+    pub fn d(&self, age: u32) -> f64 {
+        self.column(&self.d, age)
+    }
+
+    /// `N_x`, the sum of `D_y` for `y >= x`.
+    pub fn n(&self, age: u32) -> f64 {
+        self.column(&self.n, age)
+    }
+
+    /// `C_x = v^(x+1) d_x`.
+    pub fn c(&self, age: u32) -> f64 {
+        self.column(&self.c, age)
+    }
+
+    /// `M_x`, the sum of `C_y` for `y >= x`.
+    pub fn m(&self, age: u32) -> f64 {
+        self.column(&self.m, age)
+    }
+
+    /// `R_x`, the sum of `M_y` for `y >= x`.
+    pub fn r(&self, age: u32) -> f64 {
+        self.column(&self.r, age)
+    }
+
+    /// `S_x`, the sum of `N_y` for `y >= x`.
+    pub fn s(&self, age: u32) -> f64 {
+        self.column(&self.s, age)
+    }
+
+    /// `ä_x`.
+    pub fn annuity_due(&self, age: u32) -> f64 {
+        self.n(age) / self.d(age)
+    }
+
+    /// `ä_{x:n}`, payable for at most `years`.
+    pub fn temporary_annuity_due(&self, age: u32, years: u32) -> f64 {
+        (self.n(age) - self.n(age.saturating_add(years))) / self.d(age)
+    }
+
+    /// `A_x`.
+    pub fn whole_life_assurance(&self, age: u32) -> f64 {
+        self.m(age) / self.d(age)
+    }
+
+    /// `A^1_{x:n}`.
+    pub fn term_assurance(&self, age: u32, years: u32) -> f64 {
+        (self.m(age) - self.m(age.saturating_add(years))) / self.d(age)
+    }
+
+    /// `nE_x`.
+    pub fn pure_endowment(&self, age: u32, years: u32) -> f64 {
+        self.d(age.saturating_add(years)) / self.d(age)
+    }
+
+    /// `A_{x:n}`.
+    pub fn endowment_assurance(&self, age: u32, years: u32) -> f64 {
+        self.term_assurance(age, years) + self.pure_endowment(age, years)
+    }
+
+    /// `(IA)_x`, paying `k + 1` on death in year `k`.
+    pub fn increasing_assurance(&self, age: u32) -> f64 {
+        self.r(age) / self.d(age)
+    }
+
+    /// `(Iä)_x`, paying `k + 1` at the start of year `k`.
+    pub fn increasing_annuity_due(&self, age: u32) -> f64 {
+        self.s(age) / self.d(age)
+    }
+
+    /// Annual net premium `P_x` for whole life cover, payable for at most
+    /// `premium_years` (use `u32::MAX` for premiums throughout).
+    pub fn whole_life_premium(&self, age: u32, premium_years: u32) -> f64 {
+        self.whole_life_assurance(age) / self.premium_annuity(age, premium_years)
+    }
+
+    /// Annual net premium `P^1_{x:n}` for term cover.
+    pub fn term_premium(&self, age: u32, years: u32) -> f64 {
+        self.term_assurance(age, years) / self.temporary_annuity_due(age, years)
+    }
+
+    /// Annual net premium `P_{x:n}` for an endowment, payable for at most
+    /// `premium_years`.
+    pub fn endowment_premium(&self, age: u32, years: u32, premium_years: u32) -> f64 {
+        self.endowment_assurance(age, years) / self.premium_annuity(age, premium_years.min(years))
+    }
+
+    fn premium_annuity(&self, age: u32, years: u32) -> f64 {
+        self.temporary_annuity_due(age, years.min(self.d.len() as u32))
+    }
+
+    /// # Panics
+    ///
+    /// If `age` is below the youngest age of the table.
+    fn column(&self, values: &[f64], age: u32) -> f64 {
+        assert!(age >= self.min_age, "age {age} below commutation table");
+        values
+            .get((age - self.min_age) as usize)
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+fn tail_sums(values: &[f64]) -> Vec<f64> {
+    let mut sums = values.to_vec();
+    for i in (0..sums.len().saturating_sub(1)).rev() {
+        sums[i] += sums[i + 1];
+    }
+    sums
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{actual} expected {expected}"
+        );
+    }
+
+    #[test]
+    fn reproduces_amlcr_standard_ultimate_values() {
+        let table = CommutationTable::new(&MortalityTable::standard_ultimate(), 0.05).unwrap();
+        close(table.annuity_due(50), 17.0245, 5e-5);
+        close(table.whole_life_assurance(50), 0.18931, 5e-6);
+        close(table.annuity_due(60), 14.9041, 5e-5);
+        close(table.whole_life_assurance(70), 0.42818, 5e-6);
+        close(table.temporary_annuity_due(50, 20), 12.8428, 5e-5);
+        close(table.term_assurance(50, 20), 0.04020, 5e-6);
+        close(table.pure_endowment(50, 20), 0.34824, 5e-6);
+        close(table.term_premium(50, 20) * 100_000.0, 313.0225, 5e-4);
+    }
+
+    #[test]
+    fn columns_satisfy_the_usual_identities() {
+        let mortality = MortalityTable::standard_ultimate();
+        let table = CommutationTable::new(&mortality, 0.04).unwrap();
+        let d = 0.04 / 1.04;
+        for age in [20, 45, 80, 120] {
+            close(
+                table.whole_life_assurance(age),
+                1.0 - d * table.annuity_due(age),
+                1e-12,
+            );
+            close(
+                table.endowment_assurance(age, 10),
+                1.0 - d * table.temporary_annuity_due(age, 10),
+                1e-12,
+            );
+            close(
+                table.pure_endowment(age, 10),
+                mortality.survival(age, 10) / 1.04f64.powi(10),
+                1e-12,
+            );
+            close(table.r(age), table.m(age) + table.r(age + 1), 1e-9);
+            close(table.s(age), table.n(age) + table.s(age + 1), 1e-9);
+        }
+        assert_eq!(table.d(132), 0.0);
+        assert_eq!(
+            table.whole_life_premium(50, u32::MAX),
+            table.whole_life_assurance(50) / table.annuity_due(50)
+        );
+        close(
+            table.increasing_annuity_due(130),
+            1.0 + 2.0 * mortality.p(130) / 1.04,
+            1e-12,
+        );
+        assert!(CommutationTable::new(&mortality, -1.0).is_err());
+
+        // Terms running past the table cover the whole of life.
+        assert_eq!(
+            table.term_assurance(50, u32::MAX),
+            table.whole_life_assurance(50)
+        );
+        assert_eq!(table.pure_endowment(50, u32::MAX), 0.0);
+        assert_eq!(
+            table.temporary_annuity_due(50, u32::MAX),
+            table.annuity_due(50)
+        );
+        assert_eq!(
+            table.endowment_assurance(50, u32::MAX),
+            table.whole_life_assurance(50)
+        );
+    }
+}
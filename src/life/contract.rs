@@ -0,0 +1,737 @@
+use std::marker::PhantomData;
+
+use super::mortality::step_rate;
+use super::{Expenses, LifeError, MortalityTable, Schedule};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CashflowTiming, FlagField,
+    FlowDirection, KindMetadata, Product, ProductDefinition, ProductState, RateField,
+    RequiredDataBuffer, RequiredDataLayout, ScalarField,
+};
+use crate::rng::RngCore;
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    lapse: ScalarField<RateField>,
+    sum_assured: ScalarField<AmountField>,
+    premium: ScalarField<AmountField>,
+    surrender_value: ScalarField<AmountField>,
+    in_term: ScalarField<FlagField>,
+    maturity: Option<ScalarField<AmountField>>,
+}
+
+/// Marker for [`TermLife`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermCover;
+
+/// Marker for [`WholeLife`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WholeLifeCover;
+
+/// Marker for [`Endowment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndowmentCover;
+
+/// Level or decreasing term assurance on a single life.
+pub type TermLife = LifeContract<TermCover>;
+
+/// Whole life assurance, covering until the first age beyond the mortality
+/// table.
+pub type WholeLife = LifeContract<WholeLifeCover>;
+
+/// Endowment assurance: the sum assured on death within the term, and a
+/// maturity benefit to lives in force at its end.
+pub type Endowment = LifeContract<EndowmentCover>;
+
+/// Conventional single-life contract shared by [`TermLife`], [`WholeLife`]
+/// and [`Endowment`].
+///
+/// Premiums and expenses are paid at the start of each step while the policy
+/// is active; death claims, surrender values and maturity benefits at the end
+/// of the step in which the decrement occurs. Annual rates and amounts are
+/// spread over the steps of `frequency` (constant force for decrements, equal
+/// instalments for premiums and per-policy expenses). Premiums are level by
+/// default; [`with_premium_years`](Self::with_premium_years) limits them and
+/// [`with_single_premium`](Self::with_single_premium) replaces them with one
+/// payment at issue. Amounts are per policy and scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct LifeContract<C> {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    issue_age: u32,
+    term_years: u32,
+    frequency: Frequency,
+    sum_assured: Schedule,
+    premium: Schedule,
+    premium_years: u32,
+    single_premium: bool,
+    maturity_benefit: f64,
+    lapse: Schedule,
+    surrender_value: Schedule,
+    expenses: Expenses,
+    in_force: u64,
+    cover: PhantomData<C>,
+}
+
+impl LifeContract<TermCover> {
+    /// Level cover of `sum_assured` for `term_years` from `issue_age`, with a
+    /// level `annual_premium` payable throughout the term on annual steps.
+    pub fn new(
+        mortality: MortalityTable,
+        issue_age: u32,
+        term_years: u32,
+        sum_assured: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        Self::build(
+            mortality,
+            issue_age,
+            term_years,
+            sum_assured,
+            annual_premium,
+            false,
+        )
+    }
+}
+
+impl LifeContract<WholeLifeCover> {
+    /// Cover of `sum_assured` from `issue_age` for life, with a level
+    /// `annual_premium` payable throughout on annual steps.
+    ///
+    /// The term runs to the first age past [`MortalityTable::max_age`], where
+    /// `q_x = 1`.
+    pub fn new(
+        mortality: MortalityTable,
+        issue_age: u32,
+        sum_assured: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        if issue_age > mortality.max_age() {
+            return Err(LifeError::InvalidParameter("issue age"));
+        }
+        let term_years = mortality.max_age() + 2 - issue_age;
+        Self::build(
+            mortality,
+            issue_age,
+            term_years,
+            sum_assured,
+            annual_premium,
+            false,
+        )
+    }
+}
+
+impl LifeContract<EndowmentCover> {
+    pub const MATURITY: CashflowKindId = CashflowKindId(4);
+
+    /// Endowment of `sum_assured` for `term_years` from `issue_age`, paying
+    /// the same amount on maturity, with a level `annual_premium` payable
+    /// throughout the term on annual steps.
+    pub fn new(
+        mortality: MortalityTable,
+        issue_age: u32,
+        term_years: u32,
+        sum_assured: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        let mut endowment = Self::build(
+            mortality,
+            issue_age,
+            term_years,
+            sum_assured,
+            annual_premium,
+            true,
+        )?;
+        endowment.maturity_benefit = sum_assured;
+        Ok(endowment)
+    }
+
+    /// Amount paid to lives in force at the end of the term.
+    pub fn with_maturity_benefit(mut self, amount: f64) -> Self {
+        self.maturity_benefit = amount;
+        self
+    }
+
+    pub const fn maturity_benefit(&self) -> f64 {
+        self.maturity_benefit
+    }
+}
+
+impl<C> LifeContract<C> {
+    pub const ACTIVE: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const LAPSED: usize = 2;
+    pub const EXPIRED: usize = 3;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const DEATH: CashflowKindId = CashflowKindId(1);
+    pub const LAPSE: CashflowKindId = CashflowKindId(2);
+    pub const EXPENSE: CashflowKindId = CashflowKindId(3);
+
+    fn build(
+        mortality: MortalityTable,
+        issue_age: u32,
+        term_years: u32,
+        sum_assured: f64,
+        annual_premium: f64,
+        maturity: bool,
+    ) -> Result<Self, LifeError> {
+        if term_years == 0 {
+            return Err(LifeError::InvalidParameter("term"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            lapse: builder.policy_scalar("lapse"),
+            sum_assured: builder.policy_scalar("sum_assured"),
+            premium: builder.policy_scalar("premium"),
+            surrender_value: builder.policy_scalar("surrender_value"),
+            in_term: builder.policy_scalar("in_term"),
+            maturity: maturity.then(|| builder.policy_scalar("maturity")),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let mut kinds = vec![
+            KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+            KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit)
+                .with_timing(CashflowTiming::EndOfStep),
+            KindMetadata::new("lapse", FlowDirection::Outflow, CashflowCategory::Benefit)
+                .with_timing(CashflowTiming::EndOfStep),
+            KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+        ];
+        if maturity {
+            kinds.push(
+                KindMetadata::new(
+                    "maturity",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Benefit,
+                )
+                .with_timing(CashflowTiming::EndOfStep),
+            );
+        }
+        let definition = ProductDefinition::named(
+            ["active", "dead", "lapsed", "expired"]
+                .map(String::from)
+                .to_vec(),
+            kinds,
+            layout,
+        )?;
+        Ok(Self {
+            definition,
+            fields,
+            mortality,
+            issue_age,
+            term_years,
+            frequency: Frequency::Annual,
+            sum_assured: Schedule::level(sum_assured),
+            premium: Schedule::level(annual_premium),
+            premium_years: term_years,
+            single_premium: false,
+            maturity_benefit: 0.0,
+            lapse: Schedule::level(0.0),
+            surrender_value: Schedule::level(0.0),
+            expenses: Expenses::default(),
+            in_force: 1,
+            cover: PhantomData,
+        })
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sum assured by policy year, e.g. [`Schedule::linear`] for decreasing term.
+    pub fn with_sum_assured(mut self, sum_assured: Schedule) -> Self {
+        self.sum_assured = sum_assured;
+        self
+    }
+
+    /// Annual premium by policy year, for step premiums.
+    pub fn with_premiums(mut self, premiums: Schedule) -> Self {
+        self.premium = premiums;
+        self.single_premium = false;
+        self
+    }
+
+    /// Limits premiums to the first `years` policy years.
+    pub fn with_premium_years(mut self, years: u32) -> Self {
+        self.premium_years = years;
+        self
+    }
+
+    /// Replaces regular premiums with `amount` paid in full at the first step.
+    pub fn with_single_premium(mut self, amount: f64) -> Self {
+        self.premium = Schedule::level(amount);
+        self.single_premium = true;
+        self
+    }
+
+    /// Annual lapse rates by policy year.
+    pub fn with_lapse(mut self, lapse: Schedule) -> Self {
+        self.lapse = lapse;
+        self
+    }
+
+    /// Amount paid on lapse by policy year.
+    pub fn with_surrender_values(mut self, values: Schedule) -> Self {
+        self.surrender_value = values;
+        self
+    }
+
+    pub fn with_expenses(mut self, expenses: Expenses) -> Self {
+        self.expenses = expenses;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    pub const fn issue_age(&self) -> u32 {
+        self.issue_age
+    }
+
+    pub const fn term_years(&self) -> u32 {
+        self.term_years
+    }
+
+    pub fn mortality(&self) -> &MortalityTable {
+        &self.mortality
+    }
+
+    fn periods(&self) -> u32 {
+        self.frequency.periods_per_year()
+    }
+
+    fn premium_at(&self, time_index: usize, year: usize, in_term: bool) -> f64 {
+        if self.single_premium {
+            if time_index == 0 {
+                self.premium.at(0)
+            } else {
+                0.0
+            }
+        } else if in_term && year < self.premium_years as usize {
+            self.premium.at(year) / f64::from(self.periods())
+        } else {
+            0.0
+        }
+    }
+}
+
+impl<C> Product for LifeContract<C> {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(Self::ACTIVE, self.in_force, Amount::zero())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        _state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.periods();
+        let year = time_index / m as usize;
+        let in_term = year < self.term_years as usize;
+        let f = &self.fields;
+        let (q, w) = if in_term {
+            (
+                self.mortality.q_step(self.issue_age + year as u32, m),
+                step_rate(self.lapse.at(year), m),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        out.set(f.mortality, q);
+        out.set(f.lapse, w);
+        out.set(f.sum_assured, Amount::from_f64(self.sum_assured.at(year)));
+        let premium = self.premium_at(time_index, year, in_term);
+        out.set(f.premium, Amount::from_f64(premium));
+        out.set(
+            f.surrender_value,
+            Amount::from_f64(self.surrender_value.at(year)),
+        );
+        out.set(f.in_term, in_term);
+        if let Some(maturity) = f.maturity {
+            let last = time_index + 1 == self.term_years as usize * m as usize;
+            let amount = if last { self.maturity_benefit } else { 0.0 };
+            out.set(maturity, Amount::from_f64(amount));
+        }
+    }
+
+    fn cashflows(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        if state.state_id != Self::ACTIVE || !data.get(f.in_term) {
+            return;
+        }
+        let n = state.in_force as f64;
+        let q = data.get(f.mortality);
+        let w = data.get(f.lapse);
+        let premium = data.get(f.premium);
+        let initial = if time_index == 0 {
+            self.expenses.initial
+        } else {
+            0.0
+        };
+        let expense = initial
+            + self.expenses.per_policy / f64::from(self.periods())
+            + self.expenses.premium_fraction * premium.value();
+
+        out[Self::PREMIUM.0] = premium * n;
+        out[Self::DEATH.0] = data.get(f.sum_assured) * (q * n);
+        out[Self::LAPSE.0] = data.get(f.surrender_value) * ((1.0 - q) * w * n);
+        out[Self::EXPENSE.0] = Amount::from_f64(expense * n);
+        if let Some(maturity) = f.maturity {
+            out[Endowment::MATURITY.0] = data.get(maturity) * ((1.0 - q) * (1.0 - w) * n);
+        }
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = [0.0; 4];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..*state
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        if state.state_id != Self::ACTIVE {
+            out[state.state_id] = 1.0;
+        } else if data.get(self.fields.in_term) {
+            let q = data.get(self.fields.mortality);
+            let lapsed = (1.0 - q) * data.get(self.fields.lapse);
+            out[Self::ACTIVE] = 1.0 - q - lapsed;
+            out[Self::DEAD] = q;
+            out[Self::LAPSED] = lapsed;
+        } else {
+            out[Self::EXPIRED] = 1.0;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::CommutationTable;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    const V: f64 = 1.0 / 1.05;
+
+    fn project<C>(product: &LifeContract<C>, steps: usize) -> (CashflowBuffer, Vec<f64>) {
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: product.frequency,
+            steps,
+        };
+        let times = generate_cashflow_dates(config.start, steps, config.frequency).unwrap();
+        let definition = product.definition();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        ExpectedValueModel
+            .run(product, &config, &mut rng, &mut cashflows, &mut data)
+            .unwrap();
+        let occupancy = ExpectedValueModel
+            .occupancy(product, steps, &mut rng, &mut data)
+            .unwrap()
+            .chunks(definition.n_states)
+            .map(|row| row[TermLife::ACTIVE])
+            .collect();
+        (cashflows, occupancy)
+    }
+
+    fn present_value(amounts: &[f64], delay: i32) -> f64 {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(t, a)| V.powi(t as i32 + delay) * a)
+            .sum()
+    }
+
+    fn kind(cashflows: &CashflowBuffer, kind: CashflowKindId) -> Vec<f64> {
+        cashflows
+            .series(TermLife::ACTIVE, kind.0)
+            .iter()
+            .map(|a| a.value())
+            .collect()
+    }
+
+    /// Prospective reserve per policy in force at each step, with premiums in
+    /// advance and claims at the end of the year.
+    fn reserves(cashflows: &CashflowBuffer, occupancy: &[f64]) -> Vec<f64> {
+        let premium = kind(cashflows, TermLife::PREMIUM);
+        let death = kind(cashflows, TermLife::DEATH);
+        let maturity = if cashflows.n_kinds() > Endowment::MATURITY.0 {
+            kind(cashflows, Endowment::MATURITY)
+        } else {
+            vec![0.0; premium.len()]
+        };
+        let mut reserves = vec![0.0; premium.len() + 1];
+        for t in (0..premium.len()).rev() {
+            let value = V * (death[t] + maturity[t]) - premium[t];
+            reserves[t] = value + V * reserves[t + 1] * occupancy.get(t + 1).unwrap_or(&0.0);
+            reserves[t] /= occupancy[t];
+        }
+        reserves
+    }
+
+    #[test]
+    fn net_premium_reserves_match_amlcr_susm_values() {
+        // 20-year term on (50), SUSM at 5%: A^1_{50:20} = 0.04020,
+        // ä_{50:20} = 12.8428, so the net premium for 100,000 is 313.02.
+        let table = MortalityTable::standard_ultimate();
+        let premium = 313.022_467;
+        let product = TermLife::new(table, 50, 20, 100_000.0, premium).unwrap();
+        let (cashflows, occupancy) = project(&product, 20);
+
+        let death = kind(&cashflows, TermLife::DEATH);
+        let premiums = kind(&cashflows, TermLife::PREMIUM);
+        let a1: f64 = death
+            .iter()
+            .enumerate()
+            .map(|(t, d)| V.powi(t as i32 + 1) * d)
+            .sum();
+        let annuity: f64 = premiums
+            .iter()
+            .enumerate()
+            .map(|(t, p)| V.powi(t as i32) * p)
+            .sum();
+        assert!((a1 / 100_000.0 - 0.04020).abs() < 5e-6);
+        assert!((annuity / premium - 12.8428).abs() < 5e-5);
+
+        let reserves = reserves(&cashflows, &occupancy);
+        assert!(reserves[0].abs() < 1e-3);
+        for (t, expected) in [(5, 1008.17), (10, 1761.83), (15, 1763.90), (19, 572.11)] {
+            assert!(
+                (reserves[t] - expected).abs() < 0.01,
+                "V_{t} = {} expected {expected}",
+                reserves[t]
+            );
+        }
+        assert_eq!(reserves[20], 0.0);
+    }
+
+    #[test]
+    fn whole_life_premiums_and_reserves_match_commutation_functions() {
+        let table = MortalityTable::standard_ultimate();
+        let commutation = CommutationTable::new(&table, 0.05).unwrap();
+        let premium = 100_000.0 * commutation.whole_life_premium(50, u32::MAX);
+        let product = WholeLife::new(table.clone(), 50, 100_000.0, premium).unwrap();
+        assert_eq!(product.term_years(), 82);
+        let steps = product.term_years() as usize;
+        let (cashflows, occupancy) = project(&product, steps);
+
+        let claims = present_value(&kind(&cashflows, WholeLife::DEATH), 1);
+        let premiums = present_value(&kind(&cashflows, WholeLife::PREMIUM), 0);
+        assert!((claims / 100_000.0 - commutation.whole_life_assurance(50)).abs() < 1e-9);
+        assert!((premiums / premium - commutation.annuity_due(50)).abs() < 1e-9);
+        assert!(occupancy[steps - 1] > 0.0);
+
+        let projected = reserves(&cashflows, &occupancy);
+        for t in [0, 10, 30, 60] {
+            let age = 50 + t as u32;
+            let expected = 100_000.0
+                * (commutation.whole_life_assurance(age)
+                    - commutation.whole_life_premium(50, u32::MAX) * commutation.annuity_due(age));
+            assert!((projected[t] - expected).abs() < 1e-6, "V_{t}");
+        }
+
+        // Limited pay: ten annual premiums fund the same cover.
+        let limited = 100_000.0 * commutation.whole_life_premium(50, 10);
+        let product = WholeLife::new(table.clone(), 50, 100_000.0, limited)
+            .unwrap()
+            .with_premium_years(10);
+        let (cashflows, occupancy) = project(&product, steps);
+        assert_eq!(kind(&cashflows, WholeLife::PREMIUM)[10], 0.0);
+        let limited_reserves = reserves(&cashflows, &occupancy);
+        assert!(limited_reserves[0].abs() < 1e-6);
+        let paid_up = 100_000.0 * commutation.whole_life_assurance(60);
+        assert!((limited_reserves[10] - paid_up).abs() < 1e-6);
+        assert!(WholeLife::new(table, 131, 1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn endowments_pay_on_maturity_and_match_commutation_functions() {
+        let table = MortalityTable::standard_ultimate();
+        let commutation = CommutationTable::new(&table, 0.05).unwrap();
+        let single = 100_000.0 * commutation.endowment_assurance(50, 20);
+        let product = Endowment::new(table.clone(), 50, 20, 100_000.0, 0.0)
+            .unwrap()
+            .with_single_premium(single);
+        assert_eq!(
+            product.definition().kind_id("maturity"),
+            Some(Endowment::MATURITY)
+        );
+        let (cashflows, occupancy) = project(&product, 21);
+
+        let premium = kind(&cashflows, Endowment::PREMIUM);
+        assert_eq!(premium[0], single);
+        assert!(premium[1..].iter().all(|p| *p == 0.0));
+        let maturity = kind(&cashflows, Endowment::MATURITY);
+        assert!(maturity[..19].iter().all(|m| *m == 0.0));
+        let survivors = 100_000.0 * table.survival(50, 20);
+        assert!((maturity[19] - survivors).abs() < 1e-6);
+        assert_eq!(maturity[20], 0.0);
+        assert!((occupancy[20] - table.survival(50, 20)).abs() < 1e-12);
+
+        let projected = reserves(&cashflows, &occupancy);
+        assert!(projected[0].abs() < 1e-6);
+        for t in [1, 5, 19] {
+            let expected = 100_000.0 * commutation.endowment_assurance(50 + t, 20 - t);
+            assert!((projected[t as usize] - expected).abs() < 1e-6, "V_{t}");
+        }
+
+        // Level premiums over the full term, and a reduced maturity benefit.
+        let level = 100_000.0 * commutation.endowment_premium(50, 20, 20);
+        let product = Endowment::new(table.clone(), 50, 20, 100_000.0, level).unwrap();
+        let (cashflows, occupancy) = project(&product, 20);
+        let level_reserves = reserves(&cashflows, &occupancy);
+        assert!(level_reserves[0].abs() < 1e-6);
+        assert_eq!(level_reserves[20], 0.0);
+        let half = Endowment::new(table, 50, 20, 100_000.0, 0.0)
+            .unwrap()
+            .with_maturity_benefit(50_000.0);
+        assert_eq!(half.maturity_benefit(), 50_000.0);
+        let (cashflows, _) = project(&half, 20);
+        assert!((kind(&cashflows, Endowment::MATURITY)[19] - survivors / 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn writes_lapse_expense_and_decreasing_cover() {
+        let table = MortalityTable::standard_ultimate();
+        let product = TermLife::new(table.clone(), 40, 10, 0.0, 0.0)
+            .unwrap()
+            .with_sum_assured(Schedule::linear(10_000.0, 1_000.0, 10).unwrap())
+            .with_premiums(Schedule::new(vec![100.0, 100.0, 150.0]).unwrap())
+            .with_premium_years(5)
+            .with_lapse(Schedule::level(0.1))
+            .with_surrender_values(Schedule::level(50.0))
+            .with_expenses(Expenses {
+                initial: 200.0,
+                per_policy: 12.0,
+                premium_fraction: 0.05,
+            })
+            .with_in_force(2);
+        let (cashflows, occupancy) = project(&product, 12);
+
+        let premium = kind(&cashflows, TermLife::PREMIUM);
+        assert_eq!(premium[0], 200.0);
+        assert!((premium[2] - 300.0 * occupancy[2]).abs() < 1e-9);
+        assert_eq!(premium[5], 0.0);
+
+        let death = kind(&cashflows, TermLife::DEATH);
+        assert!((death[3] - 2.0 * 7_000.0 * table.q(43) * occupancy[3]).abs() < 1e-9);
+        assert_eq!(death[10], 0.0);
+
+        let lapse = kind(&cashflows, TermLife::LAPSE);
+        let lapsed = (1.0 - table.q(40)) * 0.1;
+        assert!((lapse[0] - 2.0 * 50.0 * lapsed).abs() < 1e-9);
+
+        let expense = kind(&cashflows, TermLife::EXPENSE);
+        assert!((expense[0] - 2.0 * (200.0 + 12.0 + 5.0)).abs() < 1e-9);
+        assert!((expense[6] - 2.0 * 12.0 * occupancy[6]).abs() < 1e-9);
+        assert_eq!(occupancy[11], 0.0);
+    }
+
+    #[test]
+    fn monthly_steps_compound_to_annual_decrements() {
+        let table = MortalityTable::standard_ultimate();
+        let product = TermLife::new(table.clone(), 60, 1, 1_000.0, 120.0)
+            .unwrap()
+            .with_frequency(Frequency::Monthly);
+        let (cashflows, occupancy) = project(&product, 12);
+        let premium = kind(&cashflows, TermLife::PREMIUM);
+        assert!((premium[0] - 10.0).abs() < 1e-12);
+        let survived = occupancy[11] * (1.0 - table.q_step(60, 12));
+        assert!((survived - table.p(60)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn models_reject_a_configuration_at_another_frequency() {
+        let table = MortalityTable::standard_ultimate();
+        let product = TermLife::new(table, 60, 1, 1_000.0, 120.0).unwrap();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Monthly,
+            steps: 12,
+        };
+        let definition = product.definition();
+        let times = generate_cashflow_dates(config.start, 12, config.frequency).unwrap();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        assert!(
+            ExpectedValueModel
+                .run(&product, &config, &mut rng, &mut cashflows, &mut data)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn simulation_draws_decrements_from_the_same_probabilities() {
+        let table = MortalityTable::new(30, vec![0.5]).unwrap();
+        let product = TermLife::new(table, 30, 2, 1.0, 0.0).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(product.definition().required_data.clone(), 4).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(7);
+        let mut deaths = 0;
+        for _ in 0..2000 {
+            let state = product.initial_state();
+            product.generate_required_data(0, &state, &mut rng, &mut data);
+            let next = product.next_state(0, &state, &data, &mut rng);
+            assert!(matches!(next.state_id, TermLife::ACTIVE | TermLife::DEAD));
+            deaths += usize::from(next.state_id == TermLife::DEAD);
+        }
+        assert!((900..1100).contains(&deaths));
+
+        let dead = ProductState::new(TermLife::DEAD, 1, Amount::zero());
+        assert_eq!(
+            product.next_state(1, &dead, &data, &mut rng).state_id,
+            TermLife::DEAD
+        );
+    }
+}
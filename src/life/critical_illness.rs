@@ -0,0 +1,408 @@
+use super::mortality::step_rate;
+use super::{LifeError, MortalityTable, RateTable};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CashflowTiming, FlagField,
+    FlowDirection, KindMetadata, Product, ProductDefinition, ProductState, RateField,
+    RequiredDataBuffer, RequiredDataLayout, ScalarField, VectorField,
+};
+use crate::rng::RngCore;
+
+/// How critical illness cover relates to life cover.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CriticalIllnessCover {
+    /// Diagnosis brings forward `fraction` of the death benefit; the rest is
+    /// paid on a later death.
+    Accelerated { fraction: f64 },
+    /// Diagnosis pays the sum assured with no death benefit.
+    #[default]
+    Standalone,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    ill_mortality: ScalarField<RateField>,
+    incidence: ScalarField<RateField>,
+    premium: ScalarField<AmountField>,
+    ci_benefit: ScalarField<AmountField>,
+    in_term: ScalarField<FlagField>,
+    death_benefit: VectorField<AmountField>,
+}
+
+/// Term critical illness cover on a healthy–ill–dead model.
+///
+/// Healthy lives die or are diagnosed during each step; diagnoses pay the
+/// critical illness benefit at the end of the step and move the life to
+/// `ill`, where premiums stop. The death benefit of each state is written to
+/// the `death_benefit` state vector of the required data, so accelerated cover
+/// pays the remaining sum assured on deaths after diagnosis. Mortality after
+/// diagnosis is the table rate times
+/// [`with_ill_mortality_multiplier`](Self::with_ill_mortality_multiplier).
+/// Lives still healthy or ill at the end of the term move to `expired`.
+/// Amounts are per policy and scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct CriticalIllness {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    incidence: RateTable,
+    cover: CriticalIllnessCover,
+    issue_age: u32,
+    term_years: u32,
+    sum_assured: f64,
+    annual_premium: f64,
+    ill_mortality_multiplier: f64,
+    frequency: Frequency,
+    in_force: u64,
+}
+
+impl CriticalIllness {
+    pub const HEALTHY: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const ILL: usize = 2;
+    pub const EXPIRED: usize = 3;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const CRITICAL_ILLNESS: CashflowKindId = CashflowKindId(1);
+    pub const DEATH: CashflowKindId = CashflowKindId(2);
+
+    /// Cover from `issue_age` for `term_years` on annual steps.
+    pub fn new(
+        mortality: MortalityTable,
+        incidence: RateTable,
+        cover: CriticalIllnessCover,
+        issue_age: u32,
+        term_years: u32,
+        sum_assured: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        if term_years == 0 {
+            return Err(LifeError::InvalidParameter("term"));
+        }
+        if let CriticalIllnessCover::Accelerated { fraction } = cover
+            && !(0.0..=1.0).contains(&fraction)
+        {
+            return Err(LifeError::InvalidParameter("acceleration fraction"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            ill_mortality: builder.policy_scalar("ill_q"),
+            incidence: builder.policy_scalar("incidence"),
+            premium: builder.policy_scalar("premium"),
+            ci_benefit: builder.policy_scalar("ci_benefit"),
+            in_term: builder.policy_scalar("in_term"),
+            death_benefit: builder.state_vector("death_benefit"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let definition = ProductDefinition::named(
+            ["healthy", "dead", "ill", "expired"]
+                .map(String::from)
+                .to_vec(),
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new(
+                    "critical_illness",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Benefit,
+                )
+                .with_timing(CashflowTiming::EndOfStep),
+                KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit)
+                    .with_timing(CashflowTiming::EndOfStep),
+            ],
+            layout,
+        )?;
+        Ok(Self {
+            definition,
+            fields,
+            mortality,
+            incidence,
+            cover,
+            issue_age,
+            term_years,
+            sum_assured,
+            annual_premium,
+            ill_mortality_multiplier: 1.0,
+            frequency: Frequency::Annual,
+            in_force: 1,
+        })
+    }
+
+    /// Scales mortality after diagnosis; rates are capped at 1.
+    pub fn with_ill_mortality_multiplier(mut self, multiplier: f64) -> Self {
+        self.ill_mortality_multiplier = multiplier;
+        self
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    /// Benefit paid on diagnosis.
+    pub fn critical_illness_benefit(&self) -> f64 {
+        match self.cover {
+            CriticalIllnessCover::Accelerated { fraction } => fraction * self.sum_assured,
+            CriticalIllnessCover::Standalone => self.sum_assured,
+        }
+    }
+
+    /// Benefit paid on death from `state_id`.
+    pub fn death_benefit(&self, state_id: usize) -> f64 {
+        match (self.cover, state_id) {
+            (CriticalIllnessCover::Standalone, _) => 0.0,
+            (CriticalIllnessCover::Accelerated { .. }, Self::HEALTHY) => self.sum_assured,
+            (CriticalIllnessCover::Accelerated { fraction }, Self::ILL) => {
+                (1.0 - fraction) * self.sum_assured
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl Product for CriticalIllness {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(Self::HEALTHY, self.in_force, Amount::zero())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        _state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.frequency.periods_per_year();
+        let year = time_index / m as usize;
+        let in_term = year < self.term_years as usize;
+        let f = &self.fields;
+        let age = self.issue_age + year as u32;
+        let (q, incidence) = if in_term {
+            (
+                self.mortality.q_step(age, m),
+                step_rate(self.incidence.rate(age), m),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        out.set(f.mortality, q);
+        out.set(
+            f.ill_mortality,
+            (q * self.ill_mortality_multiplier).min(1.0),
+        );
+        out.set(f.incidence, incidence);
+        let premium = if in_term {
+            self.annual_premium / f64::from(m)
+        } else {
+            0.0
+        };
+        out.set(f.premium, Amount::from_f64(premium));
+        out.set(
+            f.ci_benefit,
+            Amount::from_f64(self.critical_illness_benefit()),
+        );
+        out.set(f.in_term, in_term);
+        for state in 0..self.definition.n_states {
+            out.set_state(
+                f.death_benefit,
+                state,
+                Amount::from_f64(self.death_benefit(state)),
+            );
+        }
+    }
+
+    fn cashflows(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        let n = state.in_force as f64;
+        let death = data.get_state(f.death_benefit, state.state_id);
+        match state.state_id {
+            Self::HEALTHY => {
+                let q = data.get(f.mortality);
+                let diagnosed = (1.0 - q) * data.get(f.incidence);
+                out[Self::PREMIUM.0] = data.get(f.premium) * n;
+                out[Self::CRITICAL_ILLNESS.0] = data.get(f.ci_benefit) * (diagnosed * n);
+                out[Self::DEATH.0] = death * (q * n);
+            }
+            Self::ILL => out[Self::DEATH.0] = death * (data.get(f.ill_mortality) * n),
+            _ => {}
+        }
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = [0.0; 4];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..*state
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        let f = &self.fields;
+        match state.state_id {
+            Self::HEALTHY | Self::ILL if !data.get(f.in_term) => out[Self::EXPIRED] = 1.0,
+            Self::HEALTHY => {
+                let q = data.get(f.mortality);
+                let diagnosed = (1.0 - q) * data.get(f.incidence);
+                out[Self::DEAD] = q;
+                out[Self::ILL] = diagnosed;
+                out[Self::HEALTHY] = 1.0 - q - diagnosed;
+            }
+            Self::ILL => {
+                let q = data.get(f.ill_mortality);
+                out[Self::DEAD] = q;
+                out[Self::ILL] = 1.0 - q;
+            }
+            other => out[other] = 1.0,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    fn product(cover: CriticalIllnessCover) -> CriticalIllness {
+        let mortality = MortalityTable::new(50, vec![0.01, 0.02, 0.03]).unwrap();
+        let incidence = RateTable::new(50, vec![0.05, 0.1]).unwrap();
+        CriticalIllness::new(mortality, incidence, cover, 50, 2, 100_000.0, 5_000.0)
+            .unwrap()
+            .with_ill_mortality_multiplier(10.0)
+    }
+
+    fn totals(product: &CriticalIllness, kind: CashflowKindId) -> Vec<f64> {
+        let definition = product.definition();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps: 3,
+        };
+        let times = generate_cashflow_dates(config.start, config.steps, config.frequency).unwrap();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        ExpectedValueModel
+            .run(
+                product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        (0..3)
+            .map(|t| {
+                (0..definition.n_states)
+                    .map(|s| cashflows.series(s, kind.0)[t].value())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accelerated_cover_pays_the_balance_on_later_deaths() {
+        let accelerated = product(CriticalIllnessCover::Accelerated { fraction: 0.6 });
+        let ci = totals(&accelerated, CriticalIllness::CRITICAL_ILLNESS);
+        let death = totals(&accelerated, CriticalIllness::DEATH);
+        let premium = totals(&accelerated, CriticalIllness::PREMIUM);
+
+        let healthy1 = 0.99 * 0.95;
+        let ill1 = 0.99 * 0.05;
+        assert!((ci[0] - 60_000.0 * ill1).abs() < 1e-9);
+        assert!((death[0] - 1_000.0).abs() < 1e-9);
+        assert!((ci[1] - 60_000.0 * healthy1 * 0.98 * 0.1).abs() < 1e-9);
+        // Ill lives die at ten times the table rate and receive the 40%
+        // balance of the sum assured.
+        let expected = 100_000.0 * healthy1 * 0.02 + 40_000.0 * ill1 * 0.2;
+        assert!((death[1] - expected).abs() < 1e-9);
+        assert!((premium[1] - 5_000.0 * healthy1).abs() < 1e-9);
+        assert_eq!(ci[2], 0.0);
+        assert_eq!(death[2], 0.0);
+        assert_eq!(premium[2], 0.0);
+
+        assert!(
+            CriticalIllness::new(
+                MortalityTable::new(50, vec![0.01]).unwrap(),
+                RateTable::level(0.1).unwrap(),
+                CriticalIllnessCover::Accelerated { fraction: 1.5 },
+                50,
+                2,
+                1.0,
+                1.0,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn standalone_cover_pays_only_on_diagnosis() {
+        let standalone = product(CriticalIllnessCover::Standalone);
+        let ci = totals(&standalone, CriticalIllness::CRITICAL_ILLNESS);
+        assert!(
+            totals(&standalone, CriticalIllness::DEATH)
+                .iter()
+                .all(|&d| d == 0.0)
+        );
+        let epv = ci[0] / 1.05 + ci[1] / 1.05f64.powi(2);
+        let hand = 100_000.0 * (0.99 * 0.05 / 1.05 + 0.99 * 0.95 * 0.98 * 0.1 / 1.1025);
+        assert!((epv - hand).abs() < 1e-9);
+
+        // A diagnosed life stays ill until death or the end of the term.
+        let definition = standalone.definition();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(3);
+        let ill = ProductState::new(CriticalIllness::ILL, 1, Amount::zero());
+        let mut row = [0.0; 4];
+        standalone.generate_required_data(2, &ill, &mut rng, &mut data);
+        assert!(standalone.transition_probabilities(2, &ill, &data, &mut row));
+        assert_eq!(row, [0.0, 0.0, 0.0, 1.0]);
+        let next = standalone.next_state(2, &ill, &data, &mut rng);
+        assert_eq!(next.state_id, CriticalIllness::EXPIRED);
+    }
+}
@@ -0,0 +1,580 @@
+use super::mortality::step_rate;
+use super::{LifeError, MortalityTable, RateTable, Schedule};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, FlagField, FlowDirection, KindMetadata,
+    Product, ProductDefinition, ProductState, RateField, RequiredDataBuffer, RequiredDataHistory,
+    RequiredDataLayout, ScalarField, VectorField,
+};
+use crate::rng::RngCore;
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    incidence: ScalarField<RateField>,
+    premium: ScalarField<AmountField>,
+    in_term: ScalarField<FlagField>,
+    recovery: VectorField<RateField>,
+    termination: VectorField<RateField>,
+    benefit: VectorField<AmountField>,
+    claim_reserve: VectorField<AmountField>,
+}
+
+/// Disability income on a healthy–sick–dead model with claim durations.
+///
+/// Sick lives occupy one state per step of claim duration, so recovery and
+/// termination rates can depend on how long a claim has run. They are written
+/// per state to the `recovery` and `termination` state vectors of the required
+/// data, along with the benefit and the claim reserve of each sick state.
+/// Benefits start once a claim has lasted the elimination period and stop at
+/// the end of the benefit period, when open claims move to `expired`;
+/// claims incurred during the term run their full benefit period. Premiums are
+/// waived during a claim.
+///
+/// Premiums and benefits are paid at the start of each step. The claim reserve
+/// is the expected present value of the remaining benefits of a life in each
+/// sick state, at the valuation rate, including the current step's benefit;
+/// it is a balance rather than a flow and is read with
+/// [`claim_reserve`](Self::claim_reserve) for one step, or
+/// [`claim_reserves`](Self::claim_reserves) by state over a projection. Cashflow amounts are per policy and
+/// scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct DisabilityIncome {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    incidence: RateTable,
+    issue_age: u32,
+    term_years: u32,
+    frequency: Frequency,
+    annual_benefit: f64,
+    annual_premium: f64,
+    elimination_months: u32,
+    benefit_years: u32,
+    recovery: Schedule,
+    termination: Schedule,
+    valuation_rate: f64,
+    in_force: u64,
+}
+
+impl DisabilityIncome {
+    pub const HEALTHY: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const EXPIRED: usize = 2;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const BENEFIT: CashflowKindId = CashflowKindId(1);
+
+    /// Cover from `issue_age` for `term_years` on annual steps, paying
+    /// `annual_benefit` while sick for up to `term_years` with no elimination
+    /// period, no recovery and claimant mortality of zero.
+    pub fn new(
+        mortality: MortalityTable,
+        incidence: RateTable,
+        issue_age: u32,
+        term_years: u32,
+        annual_benefit: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        if term_years == 0 {
+            return Err(LifeError::InvalidParameter("term"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            incidence: builder.policy_scalar("incidence"),
+            premium: builder.policy_scalar("premium"),
+            in_term: builder.policy_scalar("in_term"),
+            recovery: builder.state_vector("recovery"),
+            termination: builder.state_vector("termination"),
+            benefit: builder.state_vector("benefit"),
+            claim_reserve: builder.state_vector("claim_reserve"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let mut product = Self {
+            definition: ProductDefinition::new(1, 1, layout)?,
+            fields,
+            mortality,
+            incidence,
+            issue_age,
+            term_years,
+            frequency: Frequency::Annual,
+            annual_benefit,
+            annual_premium,
+            elimination_months: 0,
+            benefit_years: term_years,
+            recovery: Schedule::level(0.0),
+            termination: Schedule::level(0.0),
+            valuation_rate: 0.0,
+            in_force: 1,
+        };
+        product.rebuild()?;
+        Ok(product)
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Result<Self, LifeError> {
+        self.frequency = frequency;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Waiting period before benefits start, rounded up to whole steps.
+    pub fn with_elimination_months(mut self, months: u32) -> Result<Self, LifeError> {
+        self.elimination_months = months;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Maximum benefit duration of a claim.
+    pub fn with_benefit_years(mut self, years: u32) -> Result<Self, LifeError> {
+        if years == 0 {
+            return Err(LifeError::InvalidParameter("benefit period"));
+        }
+        self.benefit_years = years;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Annual recovery rates by claim year.
+    pub fn with_recovery(mut self, rates: Schedule) -> Self {
+        self.recovery = rates;
+        self
+    }
+
+    /// Annual claim termination (death) rates by claim year.
+    pub fn with_termination(mut self, rates: Schedule) -> Self {
+        self.termination = rates;
+        self
+    }
+
+    /// Annual interest rate for claim reserves.
+    pub fn with_valuation_rate(mut self, rate: f64) -> Self {
+        self.valuation_rate = rate;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    /// State of a claim that has lasted `steps` steps.
+    pub const fn sick(steps: usize) -> usize {
+        3 + steps
+    }
+
+    /// Claim duration in steps of a sick state.
+    pub const fn claim_duration(state_id: usize) -> Option<usize> {
+        state_id.checked_sub(3)
+    }
+
+    /// Number of sick states: the elimination and benefit periods in steps.
+    pub fn sick_states(&self) -> usize {
+        self.elimination_steps() + (self.benefit_years * self.periods()) as usize
+    }
+
+    pub fn elimination_steps(&self) -> usize {
+        (self.elimination_months * self.periods()).div_ceil(12) as usize
+    }
+
+    fn periods(&self) -> u32 {
+        self.frequency.periods_per_year()
+    }
+
+    /// Per-policy claim reserve of `state_id` in the required data of a step;
+    /// zero outside the sick states.
+    pub fn claim_reserve(&self, data: &RequiredDataBuffer, state_id: usize) -> Amount {
+        data.get_state(self.fields.claim_reserve, state_id)
+    }
+
+    /// Per-policy claim reserve of every state at each step of a projection's
+    /// required data history, laid out `state * steps + step` like a
+    /// [`CashflowBuffer`](crate::product::CashflowBuffer) series.
+    ///
+    /// Panics if `history` was not recorded for this product's layout.
+    pub fn claim_reserves(&self, history: &RequiredDataHistory) -> Vec<Amount> {
+        (0..history.n_states())
+            .flat_map(|state| history.state_series(self.fields.claim_reserve, state))
+            .map(|&reserve| Amount::from_f64(reserve))
+            .collect()
+    }
+
+    fn rebuild(&mut self) -> Result<(), LifeError> {
+        let mut states = ["healthy", "dead", "expired"].map(String::from).to_vec();
+        states.extend((0..self.sick_states()).map(|d| format!("sick_{d}")));
+        self.definition = ProductDefinition::named(
+            states,
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("benefit", FlowDirection::Outflow, CashflowCategory::Benefit),
+            ],
+            self.definition.required_data.clone(),
+        )?;
+        Ok(())
+    }
+
+    /// Per-step benefit, recovery and termination rates by claim duration.
+    fn claim_rates(&self, duration: usize) -> (f64, f64, f64) {
+        let m = self.periods();
+        let year = duration / m as usize;
+        let benefit = if duration >= self.elimination_steps() {
+            self.annual_benefit / f64::from(m)
+        } else {
+            0.0
+        };
+        (
+            benefit,
+            step_rate(self.recovery.at(year), m),
+            step_rate(self.termination.at(year), m),
+        )
+    }
+}
+
+impl Product for DisabilityIncome {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(Self::HEALTHY, self.in_force, Amount::zero())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        _state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.periods();
+        let year = time_index / m as usize;
+        let in_term = year < self.term_years as usize;
+        let f = &self.fields;
+        let age = self.issue_age + year as u32;
+        let (q, incidence, premium) = if in_term {
+            (
+                self.mortality.q_step(age, m),
+                step_rate(self.incidence.rate(age), m),
+                self.annual_premium / f64::from(m),
+            )
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+        out.set(f.mortality, q);
+        out.set(f.incidence, incidence);
+        out.set(f.premium, Amount::from_f64(premium));
+        out.set(f.in_term, in_term);
+
+        out.vector_mut(f.recovery).fill(0.0);
+        out.vector_mut(f.termination).fill(0.0);
+        out.vector_mut(f.benefit).fill(0.0);
+        out.vector_mut(f.claim_reserve).fill(0.0);
+        let v = (1.0 + self.valuation_rate).powf(-1.0 / f64::from(m));
+        let mut reserve = 0.0;
+        for duration in (0..self.sick_states()).rev() {
+            let state = Self::sick(duration);
+            let (benefit, recovery, termination) = self.claim_rates(duration);
+            reserve = benefit + v * (1.0 - termination) * (1.0 - recovery) * reserve;
+            out.set_state(f.recovery, state, recovery);
+            out.set_state(f.termination, state, termination);
+            out.set_state(f.benefit, state, Amount::from_f64(benefit));
+            out.set_state(f.claim_reserve, state, Amount::from_f64(reserve));
+        }
+    }
+
+    fn cashflows(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        let n = state.in_force as f64;
+        match state.state_id {
+            Self::HEALTHY => out[Self::PREMIUM.0] = data.get(f.premium) * n,
+            Self::DEAD | Self::EXPIRED => {}
+            sick => out[Self::BENEFIT.0] = data.get_state(f.benefit, sick) * n,
+        }
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = vec![0.0; self.definition.n_states];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..*state
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        let f = &self.fields;
+        match state.state_id {
+            Self::HEALTHY if !data.get(f.in_term) => out[Self::EXPIRED] = 1.0,
+            Self::HEALTHY => {
+                let q = data.get(f.mortality);
+                let sick = (1.0 - q) * data.get(f.incidence);
+                out[Self::DEAD] = q;
+                out[Self::sick(0)] = sick;
+                out[Self::HEALTHY] = 1.0 - q - sick;
+            }
+            Self::DEAD | Self::EXPIRED => out[state.state_id] = 1.0,
+            sick => {
+                let termination = data.get_state(f.termination, sick);
+                let recovered = (1.0 - termination) * data.get_state(f.recovery, sick);
+                let next = if sick + 1 < self.definition.n_states {
+                    sick + 1
+                } else {
+                    Self::EXPIRED
+                };
+                out[Self::DEAD] = termination;
+                out[Self::HEALTHY] = recovered;
+                out[next] = 1.0 - termination - recovered;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    fn table() -> MortalityTable {
+        MortalityTable::new(40, vec![0.01; 60]).unwrap()
+    }
+
+    fn data(product: &DisabilityIncome, time_index: usize) -> RequiredDataBuffer {
+        let definition = product.definition();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        product.generate_required_data(
+            time_index,
+            &product.initial_state(),
+            &mut Xoshiro256StarStar::from_seed64(1),
+            &mut data,
+        );
+        data
+    }
+
+    fn run(product: &DisabilityIncome, steps: usize) -> (CashflowBuffer, Vec<f64>) {
+        let definition = product.definition();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: product.frequency,
+            steps,
+        };
+        let times = generate_cashflow_dates(config.start, steps, config.frequency).unwrap();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        ExpectedValueModel
+            .run(product, &config, &mut rng, &mut cashflows, &mut data)
+            .unwrap();
+        let occupancy = ExpectedValueModel
+            .occupancy(product, steps, &mut rng, &mut data)
+            .unwrap();
+        (cashflows, occupancy)
+    }
+
+    #[test]
+    fn claim_reserves_discount_benefits_by_claim_duration() {
+        let product = DisabilityIncome::new(
+            table(),
+            RateTable::level(0.02).unwrap(),
+            40,
+            10,
+            12_000.0,
+            600.0,
+        )
+        .unwrap()
+        .with_benefit_years(3)
+        .unwrap()
+        .with_recovery(Schedule::new(vec![0.3, 0.1]).unwrap())
+        .with_termination(Schedule::level(0.05))
+        .with_valuation_rate(0.04);
+        assert_eq!(product.sick_states(), 3);
+        assert_eq!(product.definition().n_states, 6);
+        assert_eq!(
+            product.definition().state_id("sick_2"),
+            Some(DisabilityIncome::sick(2))
+        );
+
+        let data = data(&product, 0);
+        let v = 1.0 / 1.04;
+        let stay = |r: f64| 0.95 * (1.0 - r);
+        let v2 = 12_000.0;
+        let v1 = 12_000.0 + v * stay(0.1) * v2;
+        let v0 = 12_000.0 + v * stay(0.3) * v1;
+        let reserves: Vec<f64> = (0..3)
+            .map(|d| {
+                product
+                    .claim_reserve(&data, DisabilityIncome::sick(d))
+                    .value()
+            })
+            .collect();
+        for (actual, expected) in reserves.iter().zip([v0, v1, v2]) {
+            assert!((actual - expected).abs() < 1e-9);
+        }
+        assert_eq!(
+            product.claim_reserve(&data, DisabilityIncome::HEALTHY),
+            Amount::zero()
+        );
+        assert_eq!(product.definition().n_kinds, 2);
+
+        let (cashflows, occupancy) = run(&product, 6);
+        let n_states = product.definition().n_states;
+        let sick0 = occupancy[n_states + DisabilityIncome::sick(0)];
+        assert!((sick0 - 0.99 * 0.02).abs() < 1e-12);
+        let benefit = cashflows.series(DisabilityIncome::sick(0), DisabilityIncome::BENEFIT.0);
+        assert!((benefit[1].value() - sick0 * 12_000.0).abs() < 1e-9);
+        let premium = cashflows.series(DisabilityIncome::sick(0), DisabilityIncome::PREMIUM.0);
+        assert!(premium.iter().all(|p| *p == Amount::zero()));
+
+        // Recoveries return to healthy; claims reaching the end of the
+        // benefit period expire.
+        let sick1 = occupancy[2 * n_states + DisabilityIncome::sick(1)];
+        assert!((sick1 - sick0 * stay(0.3)).abs() < 1e-12);
+        let expired = occupancy[4 * n_states + DisabilityIncome::EXPIRED];
+        assert!((expired - sick0 * stay(0.3) * stay(0.1) * stay(0.1)).abs() < 1e-12);
+
+        // The recorded history gives the reserve series of every state.
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps: 6,
+        };
+        let mut cashflows = cashflows.clone();
+        let mut recorded =
+            RequiredDataBuffer::new(product.definition().required_data.clone(), n_states).unwrap();
+        recorded.enable_history(cashflows.times().to_vec()).unwrap();
+        ExpectedValueModel
+            .run(
+                &product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut recorded,
+            )
+            .unwrap();
+        let series = product.claim_reserves(recorded.history().unwrap());
+        assert_eq!(series.len(), n_states * 6);
+        for (d, expected) in reserves.iter().enumerate() {
+            let start = DisabilityIncome::sick(d) * 6;
+            assert!((series[start].value() - expected).abs() < 1e-9);
+        }
+        assert!(series[..6].iter().all(|r| *r == Amount::zero()));
+    }
+
+    #[test]
+    fn elimination_period_delays_monthly_benefits() {
+        let product = DisabilityIncome::new(
+            table(),
+            RateTable::level(0.05).unwrap(),
+            40,
+            1,
+            1_200.0,
+            120.0,
+        )
+        .unwrap()
+        .with_frequency(Frequency::Monthly)
+        .unwrap()
+        .with_elimination_months(3)
+        .unwrap()
+        .with_benefit_years(1)
+        .unwrap();
+        assert_eq!(product.elimination_steps(), 3);
+        assert_eq!(product.sick_states(), 15);
+        let data = data(&product, 0);
+        let benefit = product
+            .definition()
+            .required_data
+            .vector_field::<AmountField>("benefit")
+            .unwrap();
+        assert_eq!(
+            data.get_state(benefit, DisabilityIncome::sick(2)).value(),
+            0.0
+        );
+        assert_eq!(
+            data.get_state(benefit, DisabilityIncome::sick(3)).value(),
+            100.0
+        );
+
+        let (cashflows, occupancy) = run(&product, 14);
+        let n_states = product.definition().n_states;
+        let total = |t: usize| -> f64 {
+            (0..n_states)
+                .map(|s| cashflows.series(s, DisabilityIncome::BENEFIT.0)[t].value())
+                .sum()
+        };
+        assert_eq!(total(3), 0.0);
+        let paying: f64 = occupancy[4 * n_states + DisabilityIncome::sick(3)];
+        assert!(paying > 0.0);
+        assert!((total(4) - 100.0 * paying).abs() < 1e-12);
+        assert_eq!(occupancy[13 * n_states + DisabilityIncome::HEALTHY], 0.0);
+    }
+
+    #[test]
+    fn simulated_claims_advance_one_duration_per_step() {
+        // Without mortality every life falls sick at once and stays sick.
+        let immortal = MortalityTable::new(40, vec![0.0; 60]).unwrap();
+        let product =
+            DisabilityIncome::new(immortal, RateTable::level(1.0).unwrap(), 40, 5, 1.0, 1.0)
+                .unwrap()
+                .with_benefit_years(2)
+                .unwrap();
+        let definition = product.definition();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(9);
+        let mut state = product.initial_state();
+        let mut path = Vec::new();
+        for step in 0..4 {
+            product.generate_required_data(step, &state, &mut rng, &mut data);
+            state = product.next_state(step, &state, &data, &mut rng);
+            path.push(state.state_id);
+        }
+        assert_eq!(
+            &path[..3],
+            &[
+                DisabilityIncome::sick(0),
+                DisabilityIncome::sick(1),
+                DisabilityIncome::EXPIRED
+            ]
+        );
+        assert_eq!(DisabilityIncome::claim_duration(path[1]), Some(1));
+        assert_eq!(
+            DisabilityIncome::claim_duration(DisabilityIncome::HEALTHY),
+            None
+        );
+        assert!(product.clone().with_benefit_years(0).is_err());
+    }
+}
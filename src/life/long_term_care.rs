@@ -0,0 +1,777 @@
+use super::mortality::step_rate;
+use super::{LifeError, MortalityTable, RateTable};
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CountField, FlagField, FlowDirection,
+    KindMetadata, Product, ProductDefinition, ProductState, RateField, RequiredDataBuffer,
+    RequiredDataLayout, ScalarField, VectorField,
+};
+use crate::rng::RngCore;
+use crate::{Date, Frequency, period_days, periods_spanning_days};
+
+/// Care settings, from least to most intensive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CareLevel {
+    HomeCare,
+    AssistedLiving,
+    NursingHome,
+}
+
+impl CareLevel {
+    pub const ALL: [Self; 3] = [Self::HomeCare, Self::AssistedLiving, Self::NursingHome];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::HomeCare => "home_care",
+            Self::AssistedLiving => "assisted_living",
+            Self::NursingHome => "nursing_home",
+        }
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Annual assumptions and benefit limit for one care level.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CareAssumptions {
+    /// Rates of healthy lives entering this level, by age.
+    pub incidence: RateTable,
+    /// Mortality of lives in this level, by age.
+    pub mortality: RateTable,
+    /// Rate of recovering to healthy.
+    pub recovery: f64,
+    /// Rate of moving to the next, more intensive level.
+    pub deterioration: f64,
+    pub daily_maximum: f64,
+}
+
+impl CareAssumptions {
+    /// No incidence, recovery or deterioration, with care mortality `mortality`.
+    pub fn new(mortality: RateTable, daily_maximum: f64) -> Result<Self, LifeError> {
+        Ok(Self {
+            incidence: RateTable::level(0.0)?,
+            mortality,
+            recovery: 0.0,
+            deterioration: 0.0,
+            daily_maximum,
+        })
+    }
+}
+
+/// Escalation of benefit limits by policy year.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InflationProtection {
+    #[default]
+    None,
+    /// Limits grow by `rate` of their issue value each year.
+    Simple(f64),
+    /// Limits grow by `rate` of their previous value each year.
+    Compound(f64),
+}
+
+impl InflationProtection {
+    /// Multiple of the issue limits applying in policy year `year`.
+    pub fn factor(self, year: u32) -> f64 {
+        match self {
+            Self::None => 1.0,
+            Self::Simple(rate) => 1.0 + rate * f64::from(year),
+            Self::Compound(rate) => (1.0 + rate).powf(f64::from(year)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    premium: ScalarField<AmountField>,
+    days: ScalarField<CountField>,
+    inflation: ScalarField<RateField>,
+    pool: ScalarField<AmountField>,
+    incidence: VectorField<RateField>,
+    care_mortality: VectorField<RateField>,
+    recovery: VectorField<RateField>,
+    deterioration: VectorField<RateField>,
+    benefit: VectorField<AmountField>,
+    exhausts: VectorField<FlagField>,
+}
+
+/// Long-term care with home care, assisted living and nursing home states.
+///
+/// Each care level has one state per step of the elimination period, which
+/// counts days in any level and is converted to steps from the projection
+/// start date, followed by a claim state paying the level's daily maximum for
+/// the calendar days of each step. Moving between levels keeps elimination
+/// progress; recovery resets it. Premiums are waived while on claim.
+///
+/// Benefits draw down a lifetime pool tracked in the state space: the pool is
+/// split into equal bands at issue values, and the healthy, elimination and
+/// claim states are repeated for each band of pool used. A claim moves the
+/// life to the band of the pool it has used, split between the two nearest
+/// bands so that the expected amount used is kept, and a claim that uses up
+/// the pool moves to `exhausted`. Both models therefore deplete the pool;
+/// more bands track it more closely at the cost of more states. Inflation
+/// protection scales the daily maximums and the pool alike. Amounts are per
+/// policy and scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct LongTermCare {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    care: [CareAssumptions; 3],
+    issue_age: u32,
+    start: Date,
+    frequency: Frequency,
+    elimination_days: u32,
+    elimination_steps: usize,
+    lifetime_pool: f64,
+    pool_bands: usize,
+    annual_premium: f64,
+    premium_years: u32,
+    inflation: InflationProtection,
+    in_force: u64,
+}
+
+impl LongTermCare {
+    pub const HEALTHY: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const EXHAUSTED: usize = 2;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const BENEFIT: CashflowKindId = CashflowKindId(1);
+
+    /// Lifetime cover from `issue_age` with annual steps from `start`, no
+    /// elimination period, premiums payable for life while not on claim and
+    /// the pool tracked in 20 bands.
+    pub fn new(
+        mortality: MortalityTable,
+        care: [CareAssumptions; 3],
+        issue_age: u32,
+        start: Date,
+        lifetime_pool: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        if !lifetime_pool.is_finite() || lifetime_pool <= 0.0 {
+            return Err(LifeError::InvalidParameter("lifetime pool"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            premium: builder.policy_scalar("premium"),
+            days: builder.policy_scalar("days"),
+            inflation: builder.policy_scalar("inflation"),
+            pool: builder.policy_scalar("pool"),
+            incidence: builder.state_vector("incidence"),
+            care_mortality: builder.state_vector("care_q"),
+            recovery: builder.state_vector("recovery"),
+            deterioration: builder.state_vector("deterioration"),
+            benefit: builder.state_vector("benefit"),
+            exhausts: builder.state_vector("exhausts"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let mut product = Self {
+            definition: ProductDefinition::new(1, 1, layout)?,
+            fields,
+            mortality,
+            care,
+            issue_age,
+            start,
+            frequency: Frequency::Annual,
+            elimination_days: 0,
+            elimination_steps: 0,
+            lifetime_pool,
+            pool_bands: 20,
+            annual_premium,
+            premium_years: u32::MAX,
+            inflation: InflationProtection::None,
+            in_force: 1,
+        };
+        product.rebuild()?;
+        Ok(product)
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Result<Self, LifeError> {
+        self.frequency = frequency;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Days of care before benefits start.
+    pub fn with_elimination_days(mut self, days: u32) -> Result<Self, LifeError> {
+        self.elimination_days = days;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    /// Number of equal bands the lifetime pool is tracked in.
+    pub fn with_pool_bands(mut self, bands: usize) -> Result<Self, LifeError> {
+        if bands == 0 {
+            return Err(LifeError::InvalidParameter("pool bands"));
+        }
+        self.pool_bands = bands;
+        self.rebuild()?;
+        Ok(self)
+    }
+
+    pub fn with_inflation(mut self, inflation: InflationProtection) -> Self {
+        self.inflation = inflation;
+        self
+    }
+
+    pub fn with_premium_years(mut self, years: u32) -> Self {
+        self.premium_years = years;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    /// Steps of care before benefits start.
+    pub fn elimination_steps(&self) -> usize {
+        self.elimination_steps
+    }
+
+    pub fn pool_bands(&self) -> usize {
+        self.pool_bands
+    }
+
+    /// Care states of one band.
+    fn care_width(&self) -> usize {
+        3 * (self.elimination_steps + 1)
+    }
+
+    /// Healthy state with `band` bands of the pool used.
+    pub fn healthy_state(&self, band: usize) -> usize {
+        match band {
+            0 => Self::HEALTHY,
+            _ => 3 + self.care_width() + (band - 1) * (self.care_width() + 1),
+        }
+    }
+
+    /// State of a life in care at `level` after `steps` steps of the
+    /// elimination period with none of the pool used; steps beyond it give
+    /// the claim state.
+    pub fn care_state(&self, level: CareLevel, steps: usize) -> usize {
+        self.banded_care_state(level, steps, 0)
+    }
+
+    /// [`care_state`](Self::care_state) with `band` bands of the pool used.
+    pub fn banded_care_state(&self, level: CareLevel, steps: usize, band: usize) -> usize {
+        let base = match band {
+            0 => 3,
+            _ => self.healthy_state(band) + 1,
+        };
+        base + level.index() * (self.elimination_steps + 1) + steps.min(self.elimination_steps)
+    }
+
+    /// Care level and elimination progress of a care state.
+    pub fn care_level(&self, state_id: usize) -> Option<(CareLevel, usize)> {
+        self.locate(state_id)?.1
+    }
+
+    /// Bands of the pool used in a healthy or care state.
+    pub fn pool_band(&self, state_id: usize) -> Option<usize> {
+        self.locate(state_id).map(|(band, _)| band)
+    }
+
+    /// Pool band and, for care states, care level and elimination progress.
+    fn locate(&self, state_id: usize) -> Option<(usize, Option<(CareLevel, usize)>)> {
+        if state_id == Self::HEALTHY {
+            return Some((0, None));
+        }
+        let offset = state_id.checked_sub(3)?;
+        let width = self.care_width();
+        let (band, position) = match offset.checked_sub(width) {
+            None => (0, Some(offset)),
+            Some(rest) => {
+                let (band, position) = (1 + rest / (width + 1), rest % (width + 1));
+                (band, position.checked_sub(1))
+            }
+        };
+        if band >= self.pool_bands {
+            return None;
+        }
+        let care = position.map(|position| {
+            let span = self.elimination_steps + 1;
+            (CareLevel::ALL[position / span], position % span)
+        });
+        Some((band, care))
+    }
+
+    fn on_claim(&self, state_id: usize) -> bool {
+        self.care_level(state_id)
+            .is_some_and(|(_, steps)| steps == self.elimination_steps)
+    }
+
+    fn rebuild(&mut self) -> Result<(), LifeError> {
+        self.elimination_steps =
+            periods_spanning_days(self.start, self.elimination_days, self.frequency)
+                .map_err(|_| LifeError::InvalidParameter("elimination period"))?;
+        let mut states = ["healthy", "dead", "exhausted"].map(String::from).to_vec();
+        for band in 0..self.pool_bands {
+            let suffix = match band {
+                0 => String::new(),
+                _ => format!("_pool_{band}"),
+            };
+            if band > 0 {
+                states.push(format!("healthy{suffix}"));
+            }
+            for level in CareLevel::ALL {
+                states.extend(
+                    (0..self.elimination_steps)
+                        .map(|steps| format!("{}_waiting_{steps}{suffix}", level.name())),
+                );
+                states.push(format!("{}{suffix}", level.name()));
+            }
+        }
+        self.definition = ProductDefinition::named(
+            states,
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("benefit", FlowDirection::Outflow, CashflowCategory::Benefit),
+            ],
+            self.definition.required_data.clone(),
+        )?;
+        Ok(())
+    }
+
+    /// Adds `weight` to the states `state` gives for the bands either side of
+    /// `band`, a fractional number of bands used; a full pool is exhausted.
+    fn add_banded(&self, out: &mut [f64], band: f64, weight: f64, state: impl Fn(usize) -> usize) {
+        let lower = band.floor();
+        let upper = band - lower;
+        for (band, weight) in [
+            (lower as usize, weight * (1.0 - upper)),
+            (lower as usize + 1, weight * upper),
+        ] {
+            if weight == 0.0 {
+                continue;
+            }
+            let target = if band < self.pool_bands {
+                state(band)
+            } else {
+                Self::EXHAUSTED
+            };
+            out[target] += weight;
+        }
+    }
+}
+
+impl Product for LongTermCare {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(Self::HEALTHY, self.in_force, Amount::zero())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        _state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.frequency.periods_per_year();
+        let year = (time_index / m as usize) as u32;
+        let age = self.issue_age + year;
+        let f = &self.fields;
+        let days = period_days(self.start, time_index, self.frequency).unwrap_or_default();
+        let inflation = self.inflation.factor(year);
+        let pool = self.lifetime_pool * inflation;
+        let premium = if year < self.premium_years {
+            self.annual_premium / f64::from(m)
+        } else {
+            0.0
+        };
+        out.set(f.mortality, self.mortality.q_step(age, m));
+        out.set(f.premium, Amount::from_f64(premium));
+        out.set(f.days, days.max(0) as u64);
+        out.set(f.inflation, inflation);
+        out.set(f.pool, Amount::from_f64(pool));
+
+        for field in [f.incidence, f.care_mortality, f.recovery, f.deterioration] {
+            out.vector_mut(field).fill(0.0);
+        }
+        out.vector_mut(f.benefit).fill(0.0);
+        out.vector_mut(f.exhausts).fill(0.0);
+        let bands = self.pool_bands as f64;
+        for band in 0..self.pool_bands {
+            let remaining = pool * (bands - band as f64) / bands;
+            for level in CareLevel::ALL {
+                let care = &self.care[level.index()];
+                out.set_state(
+                    f.incidence,
+                    self.banded_care_state(level, 0, band),
+                    step_rate(care.incidence.rate(age), m),
+                );
+                let maximum = care.daily_maximum * f64::from(days) * inflation;
+                for steps in 0..=self.elimination_steps {
+                    let state_id = self.banded_care_state(level, steps, band);
+                    out.set_state(
+                        f.care_mortality,
+                        state_id,
+                        step_rate(care.mortality.rate(age), m),
+                    );
+                    out.set_state(f.recovery, state_id, step_rate(care.recovery, m));
+                    out.set_state(f.deterioration, state_id, step_rate(care.deterioration, m));
+                }
+                let claim = self.banded_care_state(level, self.elimination_steps, band);
+                out.set_state(f.benefit, claim, Amount::from_f64(maximum.min(remaining)));
+                out.set_state(f.exhausts, claim, maximum >= remaining);
+            }
+        }
+    }
+
+    fn cashflows(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        let n = state.in_force as f64;
+        match state.state_id {
+            Self::DEAD | Self::EXHAUSTED => {}
+            claim if self.on_claim(claim) => {
+                out[Self::BENEFIT.0] = data.get_state(f.benefit, claim) * n;
+            }
+            _ => out[Self::PREMIUM.0] = data.get(f.premium) * n,
+        }
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = vec![0.0; self.definition.n_states];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..*state
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        let f = &self.fields;
+        if matches!(state.state_id, Self::DEAD | Self::EXHAUSTED) {
+            out[state.state_id] = 1.0;
+            return true;
+        }
+        let Some((band, care)) = self.locate(state.state_id) else {
+            return false;
+        };
+        let Some((level, steps)) = care else {
+            let q = data.get(f.mortality);
+            let healthy = self.healthy_state(band);
+            out[Self::DEAD] = q;
+            out[healthy] = 1.0 - q;
+            for level in CareLevel::ALL {
+                let entry = self.banded_care_state(level, 0, band);
+                let incidence = (1.0 - q) * data.get_state(f.incidence, entry);
+                out[entry] += incidence;
+                out[healthy] -= incidence;
+            }
+            return true;
+        };
+        let care = state.state_id;
+        let q = data.get_state(f.care_mortality, care);
+        out[Self::DEAD] = q;
+        if self.on_claim(care) && data.get_state(f.exhausts, care) {
+            out[Self::EXHAUSTED] = 1.0 - q;
+            return true;
+        }
+        // Bands used after this step's benefit, at issue values.
+        let used = if self.on_claim(care) {
+            let paid = data.get_state(f.benefit, care).value() / data.get(f.inflation);
+            band as f64 + paid * self.pool_bands as f64 / self.lifetime_pool
+        } else {
+            band as f64
+        };
+        let recovery = (1.0 - q) * data.get_state(f.recovery, care);
+        self.add_banded(out, used, recovery, |band| self.healthy_state(band));
+        let deterioration = match CareLevel::ALL.get(level.index() + 1) {
+            Some(&next) => {
+                let rate = (1.0 - q) * data.get_state(f.deterioration, care);
+                self.add_banded(out, used, rate, |band| {
+                    self.banded_care_state(next, steps + 1, band)
+                });
+                rate
+            }
+            None => 0.0,
+        };
+        self.add_banded(out, used, 1.0 - q - recovery - deterioration, |band| {
+            self.banded_care_state(level, steps + 1, band)
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_cashflow_dates;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig, MonteCarloModel};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+
+    fn start() -> Date {
+        Date::constant(2024, 1, 1)
+    }
+
+    fn care(daily_maximum: f64) -> CareAssumptions {
+        CareAssumptions::new(RateTable::level(0.0).unwrap(), daily_maximum).unwrap()
+    }
+
+    fn nursing_home_claim() -> LongTermCare {
+        let mut nursing = care(100.0);
+        nursing.incidence = RateTable::level(1.0).unwrap();
+        let healthy = MortalityTable::new(60, vec![0.0; 40]).unwrap();
+        LongTermCare::new(
+            healthy,
+            [care(50.0), care(75.0), nursing],
+            70,
+            start(),
+            10_000.0,
+            1_200.0,
+        )
+        .unwrap()
+        .with_frequency(Frequency::Monthly)
+        .unwrap()
+        .with_pool_bands(100)
+        .unwrap()
+    }
+
+    fn buffers(
+        product: &LongTermCare,
+        steps: usize,
+    ) -> (ModelConfig, CashflowBuffer, RequiredDataBuffer) {
+        let definition = product.definition();
+        let config = ModelConfig {
+            start: start(),
+            frequency: product.frequency,
+            steps,
+        };
+        let times = generate_cashflow_dates(config.start, steps, config.frequency).unwrap();
+        let cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        (config, cashflows, data)
+    }
+
+    #[test]
+    fn elimination_days_become_care_states() {
+        let product = nursing_home_claim()
+            .with_elimination_days(90)
+            .unwrap()
+            .with_pool_bands(2)
+            .unwrap();
+        assert_eq!(product.elimination_steps(), 3);
+        let definition = product.definition();
+        assert_eq!(definition.n_states, 3 + 3 * 4 + 1 + 3 * 4);
+        assert_eq!(
+            definition.state_id("assisted_living_waiting_2"),
+            Some(product.care_state(CareLevel::AssistedLiving, 2))
+        );
+        assert_eq!(
+            definition.state_id("nursing_home"),
+            Some(product.care_state(CareLevel::NursingHome, 9))
+        );
+        assert_eq!(
+            product.care_level(definition.state_id("home_care_waiting_1").unwrap()),
+            Some((CareLevel::HomeCare, 1))
+        );
+        assert_eq!(product.care_level(LongTermCare::HEALTHY), None);
+        let used = product.banded_care_state(CareLevel::HomeCare, 1, 1);
+        assert_eq!(
+            definition.state_id("home_care_waiting_1_pool_1"),
+            Some(used)
+        );
+        assert_eq!(product.care_level(used), Some((CareLevel::HomeCare, 1)));
+        assert_eq!(product.pool_band(used), Some(1));
+        assert_eq!(
+            definition.state_id("healthy_pool_1"),
+            Some(product.healthy_state(1))
+        );
+        assert_eq!(product.pool_band(definition.n_states), None);
+
+        // Premiums continue through the elimination period and are waived
+        // once the three months covering 90 days have passed.
+        let (config, mut cashflows, mut data) = buffers(&product, 6);
+        ExpectedValueModel
+            .run(
+                &product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        let total = |kind: CashflowKindId, step: usize| -> f64 {
+            (0..definition.n_states)
+                .map(|s| cashflows.series(s, kind.0)[step].value())
+                .sum()
+        };
+        for step in 0..4 {
+            assert_eq!(total(LongTermCare::PREMIUM, step), 100.0);
+            assert_eq!(total(LongTermCare::BENEFIT, step), 0.0);
+        }
+        assert_eq!(total(LongTermCare::PREMIUM, 4), 0.0);
+        assert_eq!(total(LongTermCare::BENEFIT, 4), 100.0 * 31.0);
+    }
+
+    #[test]
+    fn claims_deplete_the_lifetime_pool() {
+        let product = nursing_home_claim();
+        let (config, mut cashflows, mut data) = buffers(&product, 7);
+        MonteCarloModel::new([7; 6], 1)
+            .unwrap()
+            .run(
+                &product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        let claim = product.care_state(CareLevel::NursingHome, 0);
+        let benefits: Vec<f64> = (0..7)
+            .map(|step| {
+                (0..product.definition().n_states)
+                    .map(|s| cashflows.amount(s, LongTermCare::BENEFIT.0, step).value())
+                    .sum()
+            })
+            .collect();
+        // February 2024 has 29 days; the fourth month pays what is left, from
+        // the band of 90% of the pool used.
+        let last = product.banded_care_state(CareLevel::NursingHome, 0, 90);
+        assert_eq!(
+            cashflows.amount(last, LongTermCare::BENEFIT.0, 4),
+            Amount::from_f64(1_000.0)
+        );
+        assert_eq!(
+            benefits,
+            [0.0, 2_900.0, 3_100.0, 3_000.0, 1_000.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            cashflows.series(LongTermCare::HEALTHY, LongTermCare::PREMIUM.0)[0].value(),
+            100.0
+        );
+        assert!(
+            cashflows
+                .series(claim, LongTermCare::PREMIUM.0)
+                .iter()
+                .all(|p| *p == Amount::zero())
+        );
+
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        let mut state = product.initial_state();
+        for step in 0..5 {
+            product.generate_required_data(step, &state, &mut rng, &mut data);
+            state = product.next_state(step, &state, &data, &mut rng);
+        }
+        assert_eq!(state.state_id, LongTermCare::EXHAUSTED);
+    }
+
+    #[test]
+    fn expected_values_exhaust_the_pool() {
+        let benefits = |product: &LongTermCare, steps| -> Vec<f64> {
+            let (config, mut cashflows, mut data) = buffers(product, steps);
+            ExpectedValueModel
+                .run(
+                    product,
+                    &config,
+                    &mut Xoshiro256StarStar::from_seed64(1),
+                    &mut cashflows,
+                    &mut data,
+                )
+                .unwrap();
+            (0..steps)
+                .map(|step| {
+                    (0..product.definition().n_states)
+                        .map(|s| cashflows.amount(s, LongTermCare::BENEFIT.0, step).value())
+                        .sum()
+                })
+                .collect()
+        };
+        // Payments fall on band edges, so the pool is tracked exactly.
+        let product = nursing_home_claim();
+        assert_eq!(
+            benefits(&product, 7),
+            [0.0, 2_900.0, 3_100.0, 3_000.0, 1_000.0, 0.0, 0.0]
+        );
+        let (_, _, mut data) = buffers(&product, 7);
+        let occupancy = ExpectedValueModel
+            .occupancy(
+                &product,
+                7,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut data,
+            )
+            .unwrap();
+        let n_states = product.definition().n_states;
+        assert_eq!(occupancy[6 * n_states + LongTermCare::EXHAUSTED], 1.0);
+
+        // Coarse bands spread exhaustion over more steps but every claim
+        // still pays the pool in total.
+        let coarse = nursing_home_claim().with_pool_bands(3).unwrap();
+        let paid = benefits(&coarse, 12);
+        assert!(paid[5] > 0.0);
+        assert!((paid.iter().sum::<f64>() - 10_000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn inflation_protection_scales_limits() {
+        assert_eq!(InflationProtection::None.factor(5), 1.0);
+        assert!((InflationProtection::Simple(0.05).factor(4) - 1.2).abs() < 1e-12);
+        assert!((InflationProtection::Compound(0.05).factor(2) - 1.1025).abs() < 1e-12);
+
+        let product = nursing_home_claim().with_inflation(InflationProtection::Compound(0.05));
+        let (_, _, mut data) = buffers(&product, 13);
+        let claim = ProductState {
+            state_id: product.care_state(CareLevel::NursingHome, 0),
+            ..product.initial_state()
+        };
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        product.generate_required_data(12, &claim, &mut rng, &mut data);
+        let benefit = product
+            .definition()
+            .required_data
+            .vector_field::<AmountField>("benefit")
+            .unwrap();
+        // January 2025 has 31 days at 105% of the daily maximum; the pool
+        // keeps its issue value.
+        let paid = data.get_state(benefit, claim.state_id).value();
+        assert!((paid - 3_255.0).abs() < 1e-9);
+        let mut row = vec![0.0; product.definition().n_states];
+        assert!(product.transition_probabilities(12, &claim, &data, &mut row));
+        assert!((row[product.banded_care_state(CareLevel::NursingHome, 0, 31)] - 1.0).abs() < 1e-9);
+    }
+}
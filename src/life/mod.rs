@@ -0,0 +1,167 @@
+//! Reference life insurance products and their assumptions.
+
+mod annuity;
+mod commutation;
+mod contract;
+mod critical_illness;
+mod disability;
+mod long_term_care;
+mod mortality;
+mod universal;
+mod variable;
+
+pub use annuity::{Annuity, JointLife, JointLifeAnnuity, LifeAnnuity, PaymentTiming, SingleLife};
+pub use commutation::CommutationTable;
+pub use contract::{
+    Endowment, EndowmentCover, LifeContract, TermCover, TermLife, WholeLife, WholeLifeCover,
+};
+pub use critical_illness::{CriticalIllness, CriticalIllnessCover};
+pub use disability::DisabilityIncome;
+pub use long_term_care::{CareAssumptions, CareLevel, InflationProtection, LongTermCare};
+pub use mortality::MortalityTable;
+pub use universal::{DeathBenefitOption, PolicyCharges, UniversalLife};
+pub use variable::{FundModel, IncomeGuarantee, VariableAnnuity, VariableAnnuityFees};
+
+use std::fmt;
+
+use crate::product::ProductDefinitionError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifeError {
+    /// Mortality rates must be probabilities and the table non-empty.
+    InvalidTable,
+    /// Schedules need at least one finite value.
+    InvalidSchedule,
+    /// Rates, terms or ages are out of range.
+    InvalidParameter(&'static str),
+    Definition(ProductDefinitionError),
+}
+
+impl fmt::Display for LifeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTable => f.write_str("invalid mortality table"),
+            Self::InvalidSchedule => f.write_str("schedules need at least one finite value"),
+            Self::InvalidParameter(name) => write!(f, "invalid {name}"),
+            Self::Definition(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LifeError {}
+
+impl From<ProductDefinitionError> for LifeError {
+    fn from(err: ProductDefinitionError) -> Self {
+        Self::Definition(err)
+    }
+}
+
+/// Values by policy year; the last value applies to all later years.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Schedule {
+    values: Vec<f64>,
+}
+
+impl Schedule {
+    pub fn new(values: Vec<f64>) -> Result<Self, LifeError> {
+        if values.is_empty() || !values.iter().all(|v| v.is_finite()) {
+            return Err(LifeError::InvalidSchedule);
+        }
+        Ok(Self { values })
+    }
+
+    pub fn level(value: f64) -> Self {
+        Self {
+            values: vec![value],
+        }
+    }
+
+    /// Straight line from `first` in year 0 to `last` in year `years - 1`.
+    pub fn linear(first: f64, last: f64, years: usize) -> Result<Self, LifeError> {
+        if years == 0 {
+            return Err(LifeError::InvalidSchedule);
+        }
+        let step = if years > 1 {
+            (last - first) / (years - 1) as f64
+        } else {
+            0.0
+        };
+        Self::new((0..years).map(|year| first + step * year as f64).collect())
+    }
+
+    pub fn at(&self, year: usize) -> f64 {
+        self.values[year.min(self.values.len() - 1)]
+    }
+}
+
+/// Annual rates by integer age, such as incidence rates. Ages outside the
+/// table use the nearest tabulated rate.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateTable {
+    min_age: u32,
+    rates: Vec<f64>,
+}
+
+impl RateTable {
+    pub fn new(min_age: u32, rates: Vec<f64>) -> Result<Self, LifeError> {
+        if rates.is_empty() || !rates.iter().all(|r| (0.0..=1.0).contains(r)) {
+            return Err(LifeError::InvalidTable);
+        }
+        Ok(Self { min_age, rates })
+    }
+
+    pub fn level(rate: f64) -> Result<Self, LifeError> {
+        Self::new(0, vec![rate])
+    }
+
+    pub fn rate(&self, age: u32) -> f64 {
+        let index = age.saturating_sub(self.min_age) as usize;
+        self.rates[index.min(self.rates.len() - 1)]
+    }
+}
+
+/// Expense loadings; per-policy amounts are annual and spread over the steps.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Expenses {
+    /// Paid once at the first step.
+    pub initial: f64,
+    pub per_policy: f64,
+    /// Fraction of each premium.
+    pub premium_fraction: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedules_repeat_their_last_value() {
+        let schedule = Schedule::new(vec![100.0, 150.0]).unwrap();
+        assert_eq!(schedule.at(0), 100.0);
+        assert_eq!(schedule.at(7), 150.0);
+        assert_eq!(Schedule::level(3.0).at(10), 3.0);
+
+        let decreasing = Schedule::linear(1000.0, 100.0, 10).unwrap();
+        assert_eq!(decreasing.at(0), 1000.0);
+        assert_eq!(decreasing.at(9), 100.0);
+        assert_eq!(decreasing.at(3), 700.0);
+
+        assert!(Schedule::new(Vec::new()).is_err());
+        assert!(Schedule::new(vec![f64::NAN]).is_err());
+        assert!(Schedule::linear(1.0, 0.0, 0).is_err());
+    }
+
+    #[test]
+    fn rate_tables_clamp_to_the_tabulated_ages() {
+        let table = RateTable::new(30, vec![0.01, 0.02, 0.03]).unwrap();
+        assert_eq!(table.rate(20), 0.01);
+        assert_eq!(table.rate(31), 0.02);
+        assert_eq!(table.rate(90), 0.03);
+        assert_eq!(RateTable::level(0.2).unwrap().rate(55), 0.2);
+        assert!(RateTable::new(30, vec![1.2]).is_err());
+        assert!(RateTable::level(f64::NAN).is_err());
+    }
+}
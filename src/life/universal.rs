@@ -0,0 +1,663 @@
+use super::mortality::step_rate;
+use super::{Expenses, LifeError, MortalityTable, Schedule};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CashflowTiming, FlagField,
+    FlowDirection, KindMetadata, Product, ProductDefinition, ProductState, RateField,
+    RequiredDataBuffer, RequiredDataLayout, ScalarField,
+};
+use crate::rng::RngCore;
+
+/// How the death benefit relates to the account value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DeathBenefitOption {
+    /// Option A: the face amount, or the account value if larger.
+    #[default]
+    Level,
+    /// Option B: the face amount plus the account value.
+    Increasing,
+}
+
+/// Charges deducted from the account value at the start of each step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolicyCharges {
+    /// Fraction of each premium.
+    pub premium_load: f64,
+    /// Annual administration charge, spread over the steps.
+    pub per_policy: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    lapse: ScalarField<RateField>,
+    credited_rate: ScalarField<RateField>,
+    premium: ScalarField<AmountField>,
+    expense_charge: ScalarField<AmountField>,
+    coi_charge: ScalarField<AmountField>,
+    interest: ScalarField<AmountField>,
+    account_value: ScalarField<AmountField>,
+    death_benefit: ScalarField<AmountField>,
+    surrender_value: ScalarField<AmountField>,
+    surrender_charge: ScalarField<AmountField>,
+    exhausted: ScalarField<FlagField>,
+}
+
+/// Universal life on a single life with a policyholder account value.
+///
+/// The account value is carried in [`ProductState::account_value`] and rolled
+/// forward each step: premium, less expense charges, less the cost of
+/// insurance on the net amount at risk, plus interest at the greater of the
+/// declared and the guaranteed crediting rate. The required data holds the
+/// components and the account value at the end of the step. A policy whose
+/// account value goes negative lapses at the end of the step without value;
+/// otherwise surrenders receive the account value less a surrender charge.
+///
+/// Charge, interest and surrender charge kinds are memo kinds for movements
+/// in the account value: charges are inflows to the insurer out of the
+/// account and credited interest an outflow to it. Death and surrender benefits are paid at the end
+/// of the step, and include the account value. Steps are monthly by default.
+/// Amounts are per policy and scaled by `in_force`.
+#[derive(Debug, Clone)]
+pub struct UniversalLife {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    coi_rates: MortalityTable,
+    issue_age: u32,
+    face_amount: f64,
+    death_benefit_option: DeathBenefitOption,
+    frequency: Frequency,
+    premium: Schedule,
+    premium_years: u32,
+    charges: PolicyCharges,
+    crediting_rate: Schedule,
+    guaranteed_rate: f64,
+    surrender_charge: Schedule,
+    lapse: Schedule,
+    expenses: Expenses,
+    account_value: f64,
+    in_force: u64,
+}
+
+/// Account value movements over one step, per policy.
+struct RollForward {
+    premium: f64,
+    expense_charge: f64,
+    coi_charge: f64,
+    interest: f64,
+    account_value: f64,
+}
+
+impl UniversalLife {
+    pub const ACTIVE: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const SURRENDERED: usize = 2;
+    pub const LAPSED: usize = 3;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const EXPENSE_CHARGE: CashflowKindId = CashflowKindId(1);
+    pub const COI_CHARGE: CashflowKindId = CashflowKindId(2);
+    pub const INTEREST: CashflowKindId = CashflowKindId(3);
+    pub const DEATH: CashflowKindId = CashflowKindId(4);
+    pub const SURRENDER: CashflowKindId = CashflowKindId(5);
+    pub const SURRENDER_CHARGE: CashflowKindId = CashflowKindId(6);
+    pub const EXPENSE: CashflowKindId = CashflowKindId(7);
+
+    /// Level death benefit of `face_amount` from `issue_age` with a planned
+    /// `annual_premium` for life, monthly steps, no charges and no interest.
+    /// Cost of insurance rates default to `mortality`.
+    pub fn new(
+        mortality: MortalityTable,
+        issue_age: u32,
+        face_amount: f64,
+        annual_premium: f64,
+    ) -> Result<Self, LifeError> {
+        if !(face_amount.is_finite() && face_amount >= 0.0) {
+            return Err(LifeError::InvalidParameter("face amount"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            lapse: builder.policy_scalar("lapse"),
+            credited_rate: builder.policy_scalar("credited_rate"),
+            premium: builder.policy_scalar("premium"),
+            expense_charge: builder.policy_scalar("expense_charge"),
+            coi_charge: builder.policy_scalar("coi_charge"),
+            interest: builder.policy_scalar("interest"),
+            account_value: builder.policy_scalar("account_value"),
+            death_benefit: builder.policy_scalar("death_benefit"),
+            surrender_value: builder.policy_scalar("surrender_value"),
+            surrender_charge: builder.policy_scalar("surrender_charge"),
+            exhausted: builder.policy_scalar("exhausted"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let definition = ProductDefinition::named(
+            ["active", "dead", "surrendered", "lapsed"]
+                .map(String::from)
+                .to_vec(),
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new(
+                    "expense_charge",
+                    FlowDirection::Inflow,
+                    CashflowCategory::Other,
+                )
+                .with_memo(true),
+                KindMetadata::new("coi_charge", FlowDirection::Inflow, CashflowCategory::Other)
+                    .with_memo(true),
+                KindMetadata::new(
+                    "interest_credited",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Other,
+                )
+                .with_timing(CashflowTiming::EndOfStep)
+                .with_memo(true),
+                KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit)
+                    .with_timing(CashflowTiming::EndOfStep),
+                KindMetadata::new(
+                    "surrender",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Benefit,
+                )
+                .with_timing(CashflowTiming::EndOfStep),
+                KindMetadata::new(
+                    "surrender_charge",
+                    FlowDirection::Inflow,
+                    CashflowCategory::Other,
+                )
+                .with_timing(CashflowTiming::EndOfStep)
+                .with_memo(true),
+                KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+            ],
+            layout,
+        )?;
+        Ok(Self {
+            definition,
+            fields,
+            coi_rates: mortality.clone(),
+            mortality,
+            issue_age,
+            face_amount,
+            death_benefit_option: DeathBenefitOption::default(),
+            frequency: Frequency::Monthly,
+            premium: Schedule::level(annual_premium),
+            premium_years: u32::MAX,
+            charges: PolicyCharges::default(),
+            crediting_rate: Schedule::level(0.0),
+            guaranteed_rate: 0.0,
+            surrender_charge: Schedule::level(0.0),
+            lapse: Schedule::level(0.0),
+            expenses: Expenses::default(),
+            account_value: 0.0,
+            in_force: 1,
+        })
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_death_benefit_option(mut self, option: DeathBenefitOption) -> Self {
+        self.death_benefit_option = option;
+        self
+    }
+
+    /// Planned annual premium by policy year.
+    pub fn with_premiums(mut self, premiums: Schedule) -> Self {
+        self.premium = premiums;
+        self
+    }
+
+    /// Limits planned premiums to the first `years` policy years.
+    pub fn with_premium_years(mut self, years: u32) -> Self {
+        self.premium_years = years;
+        self
+    }
+
+    pub fn with_charges(mut self, charges: PolicyCharges) -> Self {
+        self.charges = charges;
+        self
+    }
+
+    /// Annual cost of insurance rates applied to the net amount at risk.
+    pub fn with_coi_rates(mut self, rates: MortalityTable) -> Self {
+        self.coi_rates = rates;
+        self
+    }
+
+    /// Declared annual crediting rates by policy year.
+    pub fn with_crediting_rates(mut self, rates: Schedule) -> Self {
+        self.crediting_rate = rates;
+        self
+    }
+
+    /// Minimum annual crediting rate.
+    pub fn with_guaranteed_rate(mut self, rate: f64) -> Self {
+        self.guaranteed_rate = rate;
+        self
+    }
+
+    /// Surrender charges by policy year, as a fraction of the account value.
+    pub fn with_surrender_charges(mut self, charges: Schedule) -> Self {
+        self.surrender_charge = charges;
+        self
+    }
+
+    /// Annual surrender rates by policy year.
+    pub fn with_lapse(mut self, lapse: Schedule) -> Self {
+        self.lapse = lapse;
+        self
+    }
+
+    pub fn with_expenses(mut self, expenses: Expenses) -> Self {
+        self.expenses = expenses;
+        self
+    }
+
+    /// Account value at the start of the projection, e.g. for in-force
+    /// business.
+    pub fn with_account_value(mut self, account_value: f64) -> Self {
+        self.account_value = account_value;
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    pub const fn issue_age(&self) -> u32 {
+        self.issue_age
+    }
+
+    pub const fn face_amount(&self) -> f64 {
+        self.face_amount
+    }
+
+    fn periods(&self) -> u32 {
+        self.frequency.periods_per_year()
+    }
+
+    fn credited_rate(&self, year: usize) -> f64 {
+        self.crediting_rate.at(year).max(self.guaranteed_rate)
+    }
+
+    fn roll_forward(&self, time_index: usize, account_value: f64) -> RollForward {
+        let m = self.periods();
+        let year = time_index / m as usize;
+        let premium = if year < self.premium_years as usize {
+            self.premium.at(year) / f64::from(m)
+        } else {
+            0.0
+        };
+        let expense_charge =
+            self.charges.per_policy / f64::from(m) + self.charges.premium_load * premium;
+        let after_charges = account_value + premium - expense_charge;
+        let net_amount_at_risk = match self.death_benefit_option {
+            DeathBenefitOption::Level => (self.face_amount - after_charges).max(0.0),
+            DeathBenefitOption::Increasing => self.face_amount,
+        };
+        let coi_charge =
+            net_amount_at_risk * self.coi_rates.q_step(self.issue_age + year as u32, m);
+        let before_interest = after_charges - coi_charge;
+        let rate = (1.0 + self.credited_rate(year)).powf(1.0 / f64::from(m)) - 1.0;
+        let interest = before_interest.max(0.0) * rate;
+        RollForward {
+            premium,
+            expense_charge,
+            coi_charge,
+            interest,
+            account_value: before_interest + interest,
+        }
+    }
+}
+
+impl Product for UniversalLife {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        ProductState::new(Self::ACTIVE, self.in_force, Amount::zero())
+            .with_account_value(Amount::from_f64(self.account_value))
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        _rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.periods();
+        let year = time_index / m as usize;
+        let f = &self.fields;
+        let step = self.roll_forward(time_index, state.account_value.value());
+        let value = step.account_value;
+        let exhausted = value < 0.0;
+        let death_benefit = match self.death_benefit_option {
+            DeathBenefitOption::Level => self.face_amount.max(value),
+            DeathBenefitOption::Increasing => self.face_amount + value.max(0.0),
+        };
+        let surrender_charge = value.max(0.0) * self.surrender_charge.at(year);
+
+        out.set(
+            f.mortality,
+            self.mortality.q_step(self.issue_age + year as u32, m),
+        );
+        out.set(f.lapse, step_rate(self.lapse.at(year), m));
+        out.set(f.credited_rate, self.credited_rate(year));
+        out.set(f.premium, Amount::from_f64(step.premium));
+        out.set(f.expense_charge, Amount::from_f64(step.expense_charge));
+        out.set(f.coi_charge, Amount::from_f64(step.coi_charge));
+        out.set(f.interest, Amount::from_f64(step.interest));
+        out.set(f.account_value, Amount::from_f64(value));
+        out.set(f.death_benefit, Amount::from_f64(death_benefit));
+        out.set(
+            f.surrender_value,
+            Amount::from_f64(value.max(0.0) - surrender_charge),
+        );
+        out.set(f.surrender_charge, Amount::from_f64(surrender_charge));
+        out.set(f.exhausted, exhausted);
+    }
+
+    fn cashflows(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        if state.state_id != Self::ACTIVE {
+            return;
+        }
+        let n = state.in_force as f64;
+        let q = data.get(f.mortality);
+        let surrendered = if data.get(f.exhausted) {
+            0.0
+        } else {
+            (1.0 - q) * data.get(f.lapse)
+        };
+        let premium = data.get(f.premium);
+        let initial = if time_index == 0 {
+            self.expenses.initial
+        } else {
+            0.0
+        };
+        let expense = initial
+            + self.expenses.per_policy / f64::from(self.periods())
+            + self.expenses.premium_fraction * premium.value();
+
+        out[Self::PREMIUM.0] = premium * n;
+        out[Self::EXPENSE_CHARGE.0] = data.get(f.expense_charge) * n;
+        out[Self::COI_CHARGE.0] = data.get(f.coi_charge) * n;
+        out[Self::INTEREST.0] = data.get(f.interest) * n;
+        out[Self::DEATH.0] = data.get(f.death_benefit) * (q * n);
+        out[Self::SURRENDER.0] = data.get(f.surrender_value) * (surrendered * n);
+        out[Self::SURRENDER_CHARGE.0] = data.get(f.surrender_charge) * (surrendered * n);
+        out[Self::EXPENSE.0] = Amount::from_f64(expense * n);
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = [0.0; 4];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..self.advance_state(time_index, state, data)
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        if state.state_id != Self::ACTIVE {
+            out[state.state_id] = 1.0;
+            return true;
+        }
+        let q = data.get(self.fields.mortality);
+        out[Self::DEAD] = q;
+        if data.get(self.fields.exhausted) {
+            out[Self::LAPSED] = 1.0 - q;
+        } else {
+            let surrendered = (1.0 - q) * data.get(self.fields.lapse);
+            out[Self::SURRENDERED] = surrendered;
+            out[Self::ACTIVE] = 1.0 - q - surrendered;
+        }
+        true
+    }
+
+    /// Carries the end-of-step account value of an active policy; exits keep
+    /// no account value.
+    fn advance_state(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+    ) -> ProductState {
+        let value = data.get(self.fields.account_value);
+        let account_value = if value.value() > 0.0 {
+            value
+        } else {
+            Amount::zero()
+        };
+        state.with_account_value(account_value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    fn product() -> UniversalLife {
+        UniversalLife::new(MortalityTable::standard_ultimate(), 40, 100_000.0, 1_200.0)
+            .unwrap()
+            .with_charges(PolicyCharges {
+                premium_load: 0.05,
+                per_policy: 60.0,
+            })
+            .with_crediting_rates(Schedule::level(0.04))
+            .with_guaranteed_rate(0.03)
+            .with_account_value(10_000.0)
+    }
+
+    fn buffers(product: &UniversalLife, steps: usize) -> (CashflowBuffer, RequiredDataBuffer) {
+        let definition = product.definition();
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), steps, product.frequency).unwrap();
+        let cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        (cashflows, data)
+    }
+
+    fn run(product: &UniversalLife, steps: usize) -> (CashflowBuffer, RequiredDataBuffer) {
+        let (mut cashflows, mut data) = buffers(product, steps);
+        data.enable_history(cashflows.times().to_vec()).unwrap();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: product.frequency,
+            steps,
+        };
+        ExpectedValueModel
+            .run(
+                product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        (cashflows, data)
+    }
+
+    fn amount(cashflows: &CashflowBuffer, kind: CashflowKindId, step: usize) -> f64 {
+        cashflows.series(UniversalLife::ACTIVE, kind.0)[step].value()
+    }
+
+    #[test]
+    fn rolls_the_account_value_forward_monthly() {
+        let product = product();
+        let q_coi = MortalityTable::standard_ultimate().q_step(40, 12);
+        let i = 1.04f64.powf(1.0 / 12.0) - 1.0;
+
+        // Premium 100, charges 5 + 5, COI on the net amount at risk, then
+        // a month's interest at 4%.
+        let mut expected = 10_000.0;
+        let mut path = Vec::new();
+        for _ in 0..12 {
+            let after_charges = expected + 100.0 - 10.0;
+            let before_interest = after_charges - (100_000.0 - after_charges) * q_coi;
+            expected = before_interest * (1.0 + i);
+            path.push(expected);
+        }
+
+        let (cashflows, data) = run(&product, 13);
+        let history = data.history().unwrap();
+        let layout = &product.definition().required_data;
+        let account_value = layout.scalar_field::<AmountField>("account_value").unwrap();
+        let series = history.series(account_value);
+        for (t, value) in path.iter().enumerate() {
+            assert!((series[t] - value).abs() < 1e-9, "month {t}");
+        }
+
+        let coi = (100_000.0 - 10_090.0) * q_coi;
+        assert!((amount(&cashflows, UniversalLife::COI_CHARGE, 0) - coi).abs() < 1e-9);
+        assert!((amount(&cashflows, UniversalLife::EXPENSE_CHARGE, 0) - 10.0).abs() < 1e-12);
+        assert!((amount(&cashflows, UniversalLife::PREMIUM, 0) - 100.0).abs() < 1e-12);
+        let interest = (10_090.0 - coi) * i;
+        assert!((amount(&cashflows, UniversalLife::INTEREST, 0) - interest).abs() < 1e-9);
+        let q = MortalityTable::standard_ultimate().q_step(40, 12);
+        assert!((amount(&cashflows, UniversalLife::DEATH, 0) - 100_000.0 * q).abs() < 1e-9);
+
+        // Simulation carries the same account value along an active path.
+        let (_, mut data) = buffers(&product, 1);
+        let mut rng = Xoshiro256StarStar::from_seed64(5);
+        let mut state = product.initial_state();
+        product.generate_required_data(0, &state, &mut rng, &mut data);
+        state = ProductState {
+            state_id: UniversalLife::ACTIVE,
+            ..product.next_state(0, &state, &data, &mut rng)
+        };
+        assert!((state.account_value.value() - path[0]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cash_total_leaves_out_account_movements() {
+        let product = product()
+            .with_surrender_charges(Schedule::level(0.05))
+            .with_lapse(Schedule::level(0.1));
+        let (cashflows, _) = run(&product, 24);
+        let definition = product.definition();
+        let net = cashflows.net_over_kinds(definition).unwrap();
+        let total = |kind: CashflowKindId, state: usize, step: usize| {
+            cashflows.amount(state, kind.0, step).value()
+        };
+        for state in 0..definition.n_states {
+            for step in 0..24 {
+                let expected = total(UniversalLife::PREMIUM, state, step)
+                    - total(UniversalLife::DEATH, state, step)
+                    - total(UniversalLife::SURRENDER, state, step)
+                    - total(UniversalLife::EXPENSE, state, step);
+                assert!((net.amount(state, 0, step).value() - expected).abs() < 1e-9);
+            }
+        }
+        assert!(net.amount(UniversalLife::ACTIVE, 0, 0).value() != 0.0);
+    }
+
+    #[test]
+    fn credits_the_guaranteed_rate_and_deducts_surrender_charges() {
+        let product = product()
+            .with_crediting_rates(Schedule::new(vec![0.05, 0.01]).unwrap())
+            .with_surrender_charges(Schedule::new(vec![0.07, 0.05, 0.0]).unwrap())
+            .with_lapse(Schedule::level(0.1))
+            .with_death_benefit_option(DeathBenefitOption::Increasing);
+        let (cashflows, data) = run(&product, 25);
+        let layout = &product.definition().required_data;
+        let history = data.history().unwrap();
+        let rate = history.series(layout.scalar_field::<RateField>("credited_rate").unwrap());
+        assert_eq!(rate[0], 0.05);
+        assert_eq!(rate[12], 0.03);
+
+        let values = history.series(layout.scalar_field::<AmountField>("account_value").unwrap());
+        let q = MortalityTable::standard_ultimate().q_step(40, 12);
+        let w = step_rate(0.1, 12);
+        let surrender = amount(&cashflows, UniversalLife::SURRENDER, 0);
+        assert!((surrender - values[0] * 0.93 * (1.0 - q) * w).abs() < 1e-9);
+        let charge = amount(&cashflows, UniversalLife::SURRENDER_CHARGE, 0);
+        assert!((charge - values[0] * 0.07 * (1.0 - q) * w).abs() < 1e-9);
+        assert_eq!(amount(&cashflows, UniversalLife::SURRENDER_CHARGE, 24), 0.0);
+
+        // Option B pays the account value on top of the face amount and
+        // charges COI on the face amount alone.
+        let coi = 100_000.0 * MortalityTable::standard_ultimate().q_step(40, 12);
+        assert!((amount(&cashflows, UniversalLife::COI_CHARGE, 0) - coi).abs() < 1e-9);
+        let death = amount(&cashflows, UniversalLife::DEATH, 0);
+        assert!((death - (100_000.0 + values[0]) * q).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lapses_when_the_account_value_goes_negative() {
+        let product = UniversalLife::new(MortalityTable::standard_ultimate(), 60, 100_000.0, 0.0)
+            .unwrap()
+            .with_account_value(500.0)
+            .with_charges(PolicyCharges {
+                premium_load: 0.0,
+                per_policy: 120.0,
+            })
+            .with_lapse(Schedule::level(0.05));
+        let (cashflows, data) = run(&product, 24);
+        let layout = &product.definition().required_data;
+        let history = data.history().unwrap();
+        let values = history.series(layout.scalar_field::<AmountField>("account_value").unwrap());
+        let exhausted = values.iter().position(|v| *v < 0.0).unwrap();
+        assert!(exhausted > 0 && exhausted < 23);
+
+        let (_, mut data) = buffers(&product, 24);
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        let occupancy = ExpectedValueModel
+            .occupancy(&product, 24, &mut rng, &mut data)
+            .unwrap();
+        let row = |t: usize| &occupancy[t * 4..(t + 1) * 4];
+        assert_eq!(row(exhausted)[UniversalLife::LAPSED], 0.0);
+        assert_eq!(row(exhausted + 1)[UniversalLife::ACTIVE], 0.0);
+        assert!(row(exhausted + 1)[UniversalLife::LAPSED] > 0.9);
+
+        assert_eq!(amount(&cashflows, UniversalLife::SURRENDER, exhausted), 0.0);
+        assert_eq!(amount(&cashflows, UniversalLife::INTEREST, exhausted), 0.0);
+        assert!(amount(&cashflows, UniversalLife::DEATH, exhausted) > 0.0);
+        assert_eq!(
+            amount(&cashflows, UniversalLife::COI_CHARGE, exhausted + 1),
+            0.0
+        );
+    }
+}
@@ -0,0 +1,764 @@
+use super::mortality::step_rate;
+use super::{LifeError, MortalityTable, Schedule};
+use crate::Frequency;
+use crate::model::sample_transition;
+use crate::product::{
+    Amount, AmountField, CashflowCategory, CashflowKindId, CashflowTiming, FlagField,
+    FlowDirection, KindMetadata, Product, ProductDefinition, ProductState, RateField,
+    RequiredDataBuffer, RequiredDataLayout, ScalarField,
+};
+use crate::rng::{RngCore, next_standard_normal};
+
+/// Lognormal fund returns with annual `drift` and `volatility`.
+///
+/// Each step multiplies the fund by `exp((drift - volatility^2 / 2) dt +
+/// volatility sqrt(dt) Z)` for a standard normal `Z`, so the expected growth
+/// over a year is `exp(drift)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FundModel {
+    pub drift: f64,
+    pub volatility: f64,
+}
+
+impl FundModel {
+    /// Gross return factor over a step of `dt` years.
+    pub fn sample(&self, dt: f64, rng: &mut dyn RngCore) -> f64 {
+        let z = if self.volatility == 0.0 {
+            0.0
+        } else {
+            next_standard_normal(rng)
+        };
+        ((self.drift - 0.5 * self.volatility * self.volatility) * dt
+            + self.volatility * dt.sqrt() * z)
+            .exp()
+    }
+}
+
+/// Annual fee rates deducted from the fund each step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableAnnuityFees {
+    /// Mortality and expense fee on the fund value.
+    pub fund: f64,
+    /// Guarantee fee on the benefit base.
+    pub rider: f64,
+}
+
+/// Guaranteed minimum income benefit: at maturity the benefit base buys
+/// `income_rate` of annual income, valued at `annuity_factor` per unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncomeGuarantee {
+    pub income_rate: f64,
+    pub annuity_factor: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fields {
+    mortality: ScalarField<RateField>,
+    lapse: ScalarField<RateField>,
+    fund_return: ScalarField<RateField>,
+    premium: ScalarField<AmountField>,
+    fund_fee: ScalarField<AmountField>,
+    rider_fee: ScalarField<AmountField>,
+    withdrawal: ScalarField<AmountField>,
+    account_value: ScalarField<AmountField>,
+    benefit_base: ScalarField<AmountField>,
+    gmdb: ScalarField<AmountField>,
+    gmwb: ScalarField<AmountField>,
+    gmab: ScalarField<AmountField>,
+    gmib: ScalarField<AmountField>,
+    maturing: ScalarField<FlagField>,
+}
+
+/// Single premium variable annuity with guaranteed minimum benefits.
+///
+/// The fund value is carried in [`ProductState::account_value`] and the
+/// benefit base in [`ProductState::benefit_base`]; both start at the premium.
+/// Each step the fund earns a return drawn from the [`FundModel`] through the
+/// RNG passed to `generate_required_data`, then pays the fund and rider fees
+/// and the scheduled withdrawal. Withdrawals are a percentage of the benefit
+/// base and do not reduce it. At the end of the step the benefit base rolls up
+/// and, on policy anniversaries, ratchets to the fund value.
+///
+/// Guarantee claims are the shortfall of the fund against each benefit:
+/// - GMDB: benefit base over the fund on death;
+/// - GMWB: withdrawals the fund can no longer pay, while the policy is active;
+/// - GMAB: benefit base over the fund at maturity;
+/// - GMIB: the value of the guaranteed income over the fund at maturity, when
+///   the policy annuitises. An income guarantee replaces the GMAB.
+///
+/// Fees are income taken out of the fund, recorded as memo kinds since the
+/// payments out of the fund are already net of them. Withdrawals, death,
+/// surrender and maturity payments out of the fund are separate kinds from
+/// the guarantee claims. All flows but
+/// the premium fall at the end of the step. Amounts are per policy and scaled
+/// by `in_force`.
+#[derive(Debug, Clone)]
+pub struct VariableAnnuity {
+    definition: ProductDefinition,
+    fields: Fields,
+    mortality: MortalityTable,
+    issue_age: u32,
+    term_years: u32,
+    premium: f64,
+    frequency: Frequency,
+    fund: FundModel,
+    fees: VariableAnnuityFees,
+    roll_up: f64,
+    roll_up_years: u32,
+    ratchet: bool,
+    withdrawals: Schedule,
+    lapse: Schedule,
+    gmdb: bool,
+    gmwb: bool,
+    gmab: bool,
+    gmib: Option<IncomeGuarantee>,
+    in_force: u64,
+}
+
+impl VariableAnnuity {
+    pub const ACTIVE: usize = 0;
+    pub const DEAD: usize = 1;
+    pub const LAPSED: usize = 2;
+    pub const MATURED: usize = 3;
+    pub const ANNUITISED: usize = 4;
+
+    pub const PREMIUM: CashflowKindId = CashflowKindId(0);
+    pub const FUND_FEE: CashflowKindId = CashflowKindId(1);
+    pub const RIDER_FEE: CashflowKindId = CashflowKindId(2);
+    pub const WITHDRAWAL: CashflowKindId = CashflowKindId(3);
+    pub const DEATH: CashflowKindId = CashflowKindId(4);
+    pub const SURRENDER: CashflowKindId = CashflowKindId(5);
+    pub const MATURITY: CashflowKindId = CashflowKindId(6);
+    pub const GMDB: CashflowKindId = CashflowKindId(7);
+    pub const GMWB: CashflowKindId = CashflowKindId(8);
+    pub const GMAB: CashflowKindId = CashflowKindId(9);
+    pub const GMIB: CashflowKindId = CashflowKindId(10);
+
+    /// Contract of `premium` from `issue_age` maturing after `term_years`, on
+    /// annual steps, with no guarantees, fees, withdrawals or fund growth.
+    pub fn new(
+        mortality: MortalityTable,
+        issue_age: u32,
+        term_years: u32,
+        premium: f64,
+    ) -> Result<Self, LifeError> {
+        if term_years == 0 {
+            return Err(LifeError::InvalidParameter("term"));
+        }
+        if !(premium.is_finite() && premium >= 0.0) {
+            return Err(LifeError::InvalidParameter("premium"));
+        }
+        let mut builder = RequiredDataLayout::builder();
+        let fields = Fields {
+            mortality: builder.policy_scalar("q"),
+            lapse: builder.policy_scalar("lapse"),
+            fund_return: builder.policy_scalar("fund_return"),
+            premium: builder.policy_scalar("premium"),
+            fund_fee: builder.policy_scalar("fund_fee"),
+            rider_fee: builder.policy_scalar("rider_fee"),
+            withdrawal: builder.policy_scalar("withdrawal"),
+            account_value: builder.policy_scalar("account_value"),
+            benefit_base: builder.policy_scalar("benefit_base"),
+            gmdb: builder.policy_scalar("gmdb"),
+            gmwb: builder.policy_scalar("gmwb"),
+            gmab: builder.policy_scalar("gmab"),
+            gmib: builder.policy_scalar("gmib"),
+            maturing: builder.policy_scalar("maturing"),
+        };
+        let layout = builder
+            .build()
+            .map_err(|_| LifeError::InvalidParameter("layout"))?;
+        let end = |name: &str, direction, category| {
+            KindMetadata::new(name, direction, category).with_timing(CashflowTiming::EndOfStep)
+        };
+        use CashflowCategory::{Benefit, Other};
+        use FlowDirection::{Inflow, Outflow};
+        let definition = ProductDefinition::named(
+            ["active", "dead", "lapsed", "matured", "annuitised"]
+                .map(String::from)
+                .to_vec(),
+            vec![
+                KindMetadata::new("premium", Inflow, CashflowCategory::Premium),
+                end("fund_fee", Inflow, Other).with_memo(true),
+                end("rider_fee", Inflow, Other).with_memo(true),
+                end("withdrawal", Outflow, Benefit),
+                end("death", Outflow, Benefit),
+                end("surrender", Outflow, Benefit),
+                end("maturity", Outflow, Benefit),
+                end("gmdb", Outflow, Benefit),
+                end("gmwb", Outflow, Benefit),
+                end("gmab", Outflow, Benefit),
+                end("gmib", Outflow, Benefit),
+            ],
+            layout,
+        )?;
+        Ok(Self {
+            definition,
+            fields,
+            mortality,
+            issue_age,
+            term_years,
+            premium,
+            frequency: Frequency::Annual,
+            fund: FundModel::default(),
+            fees: VariableAnnuityFees::default(),
+            roll_up: 0.0,
+            roll_up_years: 0,
+            ratchet: false,
+            withdrawals: Schedule::level(0.0),
+            lapse: Schedule::level(0.0),
+            gmdb: false,
+            gmwb: false,
+            gmab: false,
+            gmib: None,
+            in_force: 1,
+        })
+    }
+
+    /// Projection step length, reported by [`Product::frequency`].
+    pub fn with_frequency(mut self, frequency: Frequency) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn with_fund_model(mut self, fund: FundModel) -> Self {
+        self.fund = fund;
+        self
+    }
+
+    pub fn with_fees(mut self, fees: VariableAnnuityFees) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    /// Grows the benefit base at `rate` a year for the first `years`.
+    pub fn with_roll_up(mut self, rate: f64, years: u32) -> Self {
+        self.roll_up = rate;
+        self.roll_up_years = years;
+        self
+    }
+
+    /// Resets the benefit base to the fund value on each anniversary when
+    /// higher.
+    pub fn with_ratchet(mut self) -> Self {
+        self.ratchet = true;
+        self
+    }
+
+    /// Annual withdrawals by policy year, as a fraction of the benefit base.
+    pub fn with_withdrawals(mut self, rates: Schedule) -> Self {
+        self.withdrawals = rates;
+        self
+    }
+
+    /// Annual lapse rates by policy year.
+    pub fn with_lapse(mut self, lapse: Schedule) -> Self {
+        self.lapse = lapse;
+        self
+    }
+
+    pub fn with_gmdb(mut self) -> Self {
+        self.gmdb = true;
+        self
+    }
+
+    pub fn with_gmwb(mut self) -> Self {
+        self.gmwb = true;
+        self
+    }
+
+    pub fn with_gmab(mut self) -> Self {
+        self.gmab = true;
+        self
+    }
+
+    pub fn with_gmib(mut self, guarantee: IncomeGuarantee) -> Self {
+        self.gmib = Some(guarantee);
+        self
+    }
+
+    pub fn with_in_force(mut self, in_force: u64) -> Self {
+        self.in_force = in_force;
+        self
+    }
+
+    pub const fn term_years(&self) -> u32 {
+        self.term_years
+    }
+
+    fn periods(&self) -> u32 {
+        self.frequency.periods_per_year()
+    }
+}
+
+impl Product for VariableAnnuity {
+    fn definition(&self) -> &ProductDefinition {
+        &self.definition
+    }
+
+    fn frequency(&self) -> Option<Frequency> {
+        Some(self.frequency)
+    }
+
+    fn initial_state(&self) -> ProductState {
+        let premium = Amount::from_f64(self.premium);
+        ProductState::new(Self::ACTIVE, self.in_force, Amount::zero())
+            .with_account_value(premium)
+            .with_benefit_base(premium)
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        let m = self.periods();
+        let dt = 1.0 / f64::from(m);
+        let year = time_index / m as usize;
+        let in_term = year < self.term_years as usize;
+        let maturing = time_index + 1 == self.term_years as usize * m as usize;
+        let f = &self.fields;
+
+        let growth = self.fund.sample(dt, rng);
+        let base = state.benefit_base.value();
+        let grown = state.account_value.value() * growth;
+        let fund_fee = grown * self.fees.fund * dt;
+        let rider_fee = (base * self.fees.rider * dt).min(grown - fund_fee).max(0.0);
+        let after_fees = grown - fund_fee - rider_fee;
+        let scheduled = if in_term {
+            base * self.withdrawals.at(year) * dt
+        } else {
+            0.0
+        };
+        let withdrawal = scheduled.min(after_fees);
+        let value = after_fees - withdrawal;
+
+        let gmwb = if self.gmwb {
+            scheduled - withdrawal
+        } else {
+            0.0
+        };
+        let shortfall = (base - value).max(0.0);
+        let gmdb = if self.gmdb { shortfall } else { 0.0 };
+        let (gmab, gmib) = match self.gmib {
+            Some(income) if maturing => (
+                0.0,
+                (base * income.income_rate * income.annuity_factor - value).max(0.0),
+            ),
+            None if maturing && self.gmab => (shortfall, 0.0),
+            _ => (0.0, 0.0),
+        };
+
+        let mut next_base = base;
+        if year < self.roll_up_years as usize {
+            next_base *= (1.0 + self.roll_up).powf(dt);
+        }
+        if self.ratchet && (time_index + 1).is_multiple_of(m as usize) {
+            next_base = next_base.max(value);
+        }
+
+        let (q, w) = if in_term {
+            (
+                self.mortality.q_step(self.issue_age + year as u32, m),
+                step_rate(self.lapse.at(year), m),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        let premium = if time_index == 0 { self.premium } else { 0.0 };
+        out.set(f.mortality, q);
+        out.set(f.lapse, w);
+        out.set(f.fund_return, growth - 1.0);
+        out.set(f.premium, Amount::from_f64(premium));
+        out.set(f.fund_fee, Amount::from_f64(fund_fee));
+        out.set(f.rider_fee, Amount::from_f64(rider_fee));
+        out.set(f.withdrawal, Amount::from_f64(withdrawal));
+        out.set(f.account_value, Amount::from_f64(value));
+        out.set(f.benefit_base, Amount::from_f64(next_base));
+        out.set(f.gmdb, Amount::from_f64(gmdb));
+        out.set(f.gmwb, Amount::from_f64(gmwb));
+        out.set(f.gmab, Amount::from_f64(gmab));
+        out.set(f.gmib, Amount::from_f64(gmib));
+        out.set(f.maturing, maturing);
+    }
+
+    fn cashflows(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        out.fill(Amount::zero());
+        let f = &self.fields;
+        if state.state_id != Self::ACTIVE {
+            return;
+        }
+        let n = state.in_force as f64;
+        let q = data.get(f.mortality);
+        let lapsed = (1.0 - q) * data.get(f.lapse);
+        let survived = if data.get(f.maturing) {
+            1.0 - q - lapsed
+        } else {
+            0.0
+        };
+        let value = data.get(f.account_value);
+
+        out[Self::PREMIUM.0] = data.get(f.premium) * n;
+        out[Self::FUND_FEE.0] = data.get(f.fund_fee) * n;
+        out[Self::RIDER_FEE.0] = data.get(f.rider_fee) * n;
+        out[Self::WITHDRAWAL.0] = data.get(f.withdrawal) * n;
+        out[Self::GMWB.0] = data.get(f.gmwb) * n;
+        out[Self::DEATH.0] = value * (q * n);
+        out[Self::GMDB.0] = data.get(f.gmdb) * (q * n);
+        out[Self::SURRENDER.0] = value * (lapsed * n);
+        out[Self::MATURITY.0] = value * (survived * n);
+        out[Self::GMAB.0] = data.get(f.gmab) * (survived * n);
+        out[Self::GMIB.0] = data.get(f.gmib) * (survived * n);
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let mut row = [0.0; 5];
+        self.transition_probabilities(time_index, state, data, &mut row);
+        ProductState {
+            state_id: sample_transition(&row, rng),
+            ..self.advance_state(time_index, state, data)
+        }
+    }
+
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        out.fill(0.0);
+        if state.state_id != Self::ACTIVE {
+            out[state.state_id] = 1.0;
+            return true;
+        }
+        let q = data.get(self.fields.mortality);
+        let lapsed = (1.0 - q) * data.get(self.fields.lapse);
+        let survivor = match (data.get(self.fields.maturing), self.gmib) {
+            (false, _) => Self::ACTIVE,
+            (true, Some(_)) => Self::ANNUITISED,
+            (true, None) => Self::MATURED,
+        };
+        out[Self::DEAD] = q;
+        out[Self::LAPSED] = lapsed;
+        out[survivor] = 1.0 - q - lapsed;
+        true
+    }
+
+    fn advance_state(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+    ) -> ProductState {
+        state
+            .with_account_value(data.get(self.fields.account_value))
+            .with_benefit_base(data.get(self.fields.benefit_base))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Decrements, ExpectedValueModel, Model, ModelConfig, MonteCarloModel};
+    use crate::product::CashflowBuffer;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    fn buffers(product: &VariableAnnuity, steps: usize) -> (CashflowBuffer, RequiredDataBuffer) {
+        let definition = product.definition();
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), steps, product.frequency).unwrap();
+        let cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        (cashflows, data)
+    }
+
+    fn config(product: &VariableAnnuity, steps: usize) -> ModelConfig {
+        ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: product.frequency,
+            steps,
+        }
+    }
+
+    fn project(product: &VariableAnnuity, steps: usize) -> (CashflowBuffer, RequiredDataBuffer) {
+        let (mut cashflows, mut data) = buffers(product, steps);
+        data.enable_history(cashflows.times().to_vec()).unwrap();
+        ExpectedValueModel
+            .run(
+                product,
+                &config(product, steps),
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+        (cashflows, data)
+    }
+
+    fn series(cashflows: &CashflowBuffer, kind: CashflowKindId) -> Vec<f64> {
+        cashflows
+            .series(VariableAnnuity::ACTIVE, kind.0)
+            .iter()
+            .map(|a| a.value())
+            .collect()
+    }
+
+    fn history(product: &VariableAnnuity, data: &RequiredDataBuffer, name: &str) -> Vec<f64> {
+        let field = product
+            .definition()
+            .required_data
+            .scalar_field::<AmountField>(name)
+            .unwrap();
+        data.history().unwrap().series(field).to_vec()
+    }
+
+    #[test]
+    fn deterministic_fund_pays_fees_and_grows_the_benefit_base() {
+        let table = MortalityTable::standard_ultimate();
+        let product = VariableAnnuity::new(table.clone(), 60, 10, 100_000.0)
+            .unwrap()
+            .with_fund_model(FundModel {
+                drift: 0.06,
+                volatility: 0.0,
+            })
+            .with_fees(VariableAnnuityFees {
+                fund: 0.015,
+                rider: 0.005,
+            })
+            .with_roll_up(0.05, 5)
+            .with_gmdb()
+            .with_gmab();
+        let (cashflows, data) = project(&product, 10);
+
+        let grown = 100_000.0 * 0.06f64.exp();
+        let fund_fee = series(&cashflows, VariableAnnuity::FUND_FEE);
+        assert!((fund_fee[0] - grown * 0.015).abs() < 1e-9);
+        let rider_fee = series(&cashflows, VariableAnnuity::RIDER_FEE);
+        assert!((rider_fee[0] - 500.0).abs() < 1e-9);
+        let values = history(&product, &data, "account_value");
+        assert!((values[0] - (grown * 0.985 - 500.0)).abs() < 1e-9);
+
+        let bases = history(&product, &data, "benefit_base");
+        assert!((bases[0] - 105_000.0).abs() < 1e-6);
+        assert!((bases[4] - 100_000.0 * 1.05f64.powi(5)).abs() < 1e-6);
+        assert_eq!(bases[9], bases[4]);
+
+        // GMDB claims are the shortfall against the base at the start of the
+        // step; by maturity the fund has outgrown the roll-up.
+        assert!(values[9] > bases[9]);
+        let gmdb = series(&cashflows, VariableAnnuity::GMDB);
+        let death = series(&cashflows, VariableAnnuity::DEATH);
+        for t in 0..10 {
+            let base = if t == 0 { 100_000.0 } else { bases[t - 1] };
+            let shortfall = (base - values[t]).max(0.0);
+            assert!((gmdb[t] - shortfall * death[t] / values[t]).abs() < 1e-9);
+        }
+        assert!(
+            series(&cashflows, VariableAnnuity::GMAB)
+                .iter()
+                .all(|c| *c == 0.0)
+        );
+        let maturity = series(&cashflows, VariableAnnuity::MATURITY);
+        assert!(maturity[..9].iter().all(|c| *c == 0.0));
+        assert!((maturity[9] - values[9] * table.survival(60, 10)).abs() < 1e-6);
+        assert!((series(&cashflows, VariableAnnuity::PREMIUM)[0] - 100_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn falling_fund_triggers_death_and_accumulation_claims() {
+        let table = MortalityTable::standard_ultimate();
+        let product = VariableAnnuity::new(table.clone(), 70, 5, 100_000.0)
+            .unwrap()
+            .with_fund_model(FundModel {
+                drift: -0.1,
+                volatility: 0.0,
+            })
+            .with_ratchet()
+            .with_gmdb()
+            .with_gmab();
+        let (cashflows, data) = project(&product, 5);
+        let values = history(&product, &data, "account_value");
+        let bases = history(&product, &data, "benefit_base");
+        assert!(bases.iter().all(|b| (*b - 100_000.0).abs() < 1e-9));
+
+        let gmdb = series(&cashflows, VariableAnnuity::GMDB);
+        let alive = table.survival(70, 2);
+        let expected = (100_000.0 - values[2]) * table.q(72) * alive;
+        assert!((gmdb[2] - expected).abs() < 1e-6);
+        let gmab = series(&cashflows, VariableAnnuity::GMAB);
+        let survivors = table.survival(70, 5);
+        assert!((gmab[4] - (100_000.0 - values[4]) * survivors).abs() < 1e-6);
+        let death = series(&cashflows, VariableAnnuity::DEATH);
+        assert!((death[2] - values[2] * table.q(72) * alive).abs() < 1e-6);
+
+        // A rising fund ratchets the base on anniversaries.
+        let rising = product.with_fund_model(FundModel {
+            drift: 0.1,
+            volatility: 0.0,
+        });
+        let (_, data) = project(&rising, 5);
+        let values = history(&rising, &data, "account_value");
+        let bases = history(&rising, &data, "benefit_base");
+        assert_eq!(bases, values);
+    }
+
+    #[test]
+    fn withdrawals_beyond_the_fund_become_gmwb_claims() {
+        let table = MortalityTable::standard_ultimate();
+        let product = VariableAnnuity::new(table, 65, 30, 100_000.0)
+            .unwrap()
+            .with_fund_model(FundModel {
+                drift: -0.05,
+                volatility: 0.0,
+            })
+            .with_withdrawals(Schedule::level(0.1))
+            .with_gmwb();
+        let (cashflows, data) = project(&product, 30);
+        let values = history(&product, &data, "account_value");
+        let exhausted = values.iter().position(|v| *v == 0.0).unwrap();
+        assert!(exhausted > 3 && exhausted < 15);
+
+        let (_, mut scratch) = buffers(&product, 30);
+        let occupancy = ExpectedValueModel
+            .occupancy(
+                &product,
+                30,
+                &mut Xoshiro256StarStar::from_seed64(1),
+                &mut scratch,
+            )
+            .unwrap();
+        let withdrawal = series(&cashflows, VariableAnnuity::WITHDRAWAL);
+        let gmwb = series(&cashflows, VariableAnnuity::GMWB);
+        for t in 0..30 {
+            let active = occupancy[t * 5 + VariableAnnuity::ACTIVE];
+            let paid = withdrawal[t] + gmwb[t];
+            assert!((paid - 10_000.0 * active).abs() < 1e-6, "year {t}");
+        }
+        assert_eq!(gmwb[0], 0.0);
+        assert!(gmwb[exhausted] > 0.0);
+        assert_eq!(withdrawal[exhausted + 1], 0.0);
+    }
+
+    #[test]
+    fn income_guarantee_annuitises_at_maturity() {
+        let table = MortalityTable::standard_ultimate();
+        let product = VariableAnnuity::new(table.clone(), 55, 10, 100_000.0)
+            .unwrap()
+            .with_roll_up(0.05, 10)
+            .with_gmab()
+            .with_gmib(IncomeGuarantee {
+                income_rate: 0.06,
+                annuity_factor: 15.0,
+            });
+        let (cashflows, data) = project(&product, 11);
+        let base = 100_000.0 * 1.05f64.powi(9);
+        let survivors = table.survival(55, 10);
+        let gmib = series(&cashflows, VariableAnnuity::GMIB);
+        let expected = (base * 0.06 * 15.0 - 100_000.0) * survivors;
+        assert!((gmib[9] - expected).abs() < 1e-6);
+        assert!(
+            series(&cashflows, VariableAnnuity::GMAB)
+                .iter()
+                .all(|c| *c == 0.0)
+        );
+        assert!(history(&product, &data, "gmib")[9] > 0.0);
+
+        let mut rng = Xoshiro256StarStar::from_seed64(2);
+        let mut occupancy = [0.0; 5];
+        let (_, mut data) = buffers(&product, 11);
+        let rows = ExpectedValueModel
+            .occupancy(&product, 11, &mut rng, &mut data)
+            .unwrap();
+        occupancy.copy_from_slice(&rows[50..55]);
+        assert_eq!(occupancy[VariableAnnuity::ACTIVE], 0.0);
+        assert!((occupancy[VariableAnnuity::ANNUITISED] - survivors).abs() < 1e-12);
+    }
+
+    #[test]
+    fn monte_carlo_fund_paths_are_reproducible_and_unbiased() {
+        let table = MortalityTable::standard_ultimate();
+        let product = VariableAnnuity::new(table.clone(), 60, 10, 100.0)
+            .unwrap()
+            .with_frequency(Frequency::Quarterly)
+            .with_fund_model(FundModel {
+                drift: 0.04,
+                volatility: 0.2,
+            })
+            .with_gmdb()
+            .with_gmab();
+        let steps = 40;
+        let config = config(&product, steps);
+        let model = MonteCarloModel::new([42; 6], 2000)
+            .unwrap()
+            .with_decrements(Decrements::Expected);
+        let (mut cashflows, mut data) = buffers(&product, steps);
+        model
+            .run(
+                &product,
+                &config,
+                &mut Xoshiro256StarStar::from_seed64(0),
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+
+        // E[F_T] = 100 e^{0.4}, paid to survivors at maturity.
+        let maturity = series(&cashflows, VariableAnnuity::MATURITY)[steps - 1];
+        let expected = 100.0 * 0.4f64.exp() * table.survival(60, 10);
+        assert!(
+            (maturity / expected - 1.0).abs() < 0.05,
+            "{maturity} vs {expected}"
+        );
+        let gmab = series(&cashflows, VariableAnnuity::GMAB)[steps - 1];
+        assert!(gmab > 0.0);
+
+        let (mut first, mut data) = buffers(&product, steps);
+        let (mut second, _) = buffers(&product, steps);
+        model
+            .run_scenario(&product, &config, 7, &mut first, &mut data)
+            .unwrap();
+        model
+            .run_scenario(&product, &config, 7, &mut second, &mut data)
+            .unwrap();
+        assert_eq!(
+            series(&first, VariableAnnuity::MATURITY),
+            series(&second, VariableAnnuity::MATURITY)
+        );
+
+        // Simulated decrements follow one life through the states.
+        let simulated = MonteCarloModel::new([42; 6], 1).unwrap();
+        let (mut path, mut data) = buffers(&product, steps);
+        simulated
+            .run_scenario(&product, &config, 0, &mut path, &mut data)
+            .unwrap();
+        let n_states = product.definition().n_states;
+        let n_kinds = product.definition().n_kinds;
+        for t in 0..steps {
+            let paying = (0..n_states)
+                .filter(|&s| (0..n_kinds).any(|k| path.series(s, k)[t] != Amount::zero()))
+                .count();
+            assert!(paying <= 1, "step {t}");
+        }
+    }
+}
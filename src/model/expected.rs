@@ -0,0 +1,316 @@
+use super::{Model, ModelConfig, ModelError, validate_buffers};
+use crate::product::{Amount, CashflowBuffer, Product, ProductState, RequiredDataBuffer};
+use crate::rng::RngCore;
+
+/// Expected-value projection over a Markov chain of product states.
+///
+/// Each step the model generates required data once, from the carried state
+/// with `state_id` set to the most likely current state, and records it. It
+/// then asks the product for the cashflows and transition probabilities of
+/// every occupied state, and rolls the carried state forward with
+/// [`Product::advance_state`]; it starts as the initial state. Cashflows are
+/// weighted by state occupancy and written to that state's slice of the
+/// buffer, so `cashflows` holds expected amounts for the initial in-force.
+///
+/// Products must implement [`Product::transition_probabilities`]; the RNG is
+/// passed through to `generate_required_data` only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExpectedValueModel;
+
+impl ExpectedValueModel {
+    /// Probability of occupying each state at the start of every step,
+    /// as a `steps * n_states` row-major matrix.
+    ///
+    /// Runs the same recursion as [`Model::run`] without writing cashflows.
+    pub fn occupancy(
+        &self,
+        product: &dyn Product,
+        steps: usize,
+        rng: &mut dyn RngCore,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<Vec<f64>, ModelError> {
+        let mut rows = Vec::with_capacity(steps * product.definition().n_states);
+        self.project(
+            product,
+            steps,
+            rng,
+            data,
+            |_, occupancy| rows.extend_from_slice(occupancy),
+            |_, _, _, _| {},
+        )?;
+        Ok(rows)
+    }
+
+    /// [`Model::run`] returning the [`occupancy`](Self::occupancy) of the
+    /// same projection, so that both see one draw of the required data.
+    pub fn run_with_occupancy(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        rng: &mut dyn RngCore,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<Vec<f64>, ModelError> {
+        let mut rows = Vec::with_capacity(config.steps * product.definition().n_states);
+        self.run_observed(product, config, rng, cashflows, data, |_, occupancy| {
+            rows.extend_from_slice(occupancy)
+        })?;
+        Ok(rows)
+    }
+
+    fn run_observed(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        rng: &mut dyn RngCore,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+        on_step: impl FnMut(usize, &[f64]),
+    ) -> Result<(), ModelError> {
+        validate_buffers(product, config, cashflows, data)?;
+        for state in 0..cashflows.n_states() {
+            for kind in 0..cashflows.n_kinds() {
+                cashflows.series_mut(state, kind).fill(Amount::zero());
+            }
+        }
+        self.project(
+            product,
+            config.steps,
+            rng,
+            data,
+            on_step,
+            |step, state, weight, out| {
+                for (kind, &amount) in out.iter().enumerate() {
+                    *cashflows.amount_mut(state, kind, step) += amount * weight;
+                }
+            },
+        )
+    }
+
+    fn project(
+        &self,
+        product: &dyn Product,
+        steps: usize,
+        rng: &mut dyn RngCore,
+        data: &mut RequiredDataBuffer,
+        mut on_step: impl FnMut(usize, &[f64]),
+        mut on_cashflows: impl FnMut(usize, usize, f64, &[Amount]),
+    ) -> Result<(), ModelError> {
+        let definition = product.definition();
+        let n_states = definition.n_states;
+        let initial = product.initial_state();
+        if initial.state_id >= n_states {
+            return Err(ModelError);
+        }
+
+        let mut carried = initial;
+        let mut occupancy = vec![0.0; n_states];
+        occupancy[initial.state_id] = 1.0;
+        let mut next = vec![0.0; n_states];
+        let mut row = vec![0.0; n_states];
+        let mut out = vec![Amount::zero(); definition.n_kinds];
+
+        for step in 0..steps {
+            on_step(step, &occupancy);
+            let mut lead = 0;
+            for (s, &p) in occupancy.iter().enumerate() {
+                if p > occupancy[lead] {
+                    lead = s;
+                }
+            }
+            let lead_state = ProductState {
+                state_id: lead,
+                ..carried
+            };
+            product.generate_required_data(step, &lead_state, rng, data);
+            data.record(step);
+
+            next.fill(0.0);
+            for (state_id, &p) in occupancy.iter().enumerate() {
+                if p == 0.0 {
+                    continue;
+                }
+                let state = ProductState {
+                    state_id,
+                    ..carried
+                };
+                product.cashflows(step, &state, data, &mut out);
+                on_cashflows(step, state_id, p, &out);
+
+                row.fill(0.0);
+                if !product.transition_probabilities(step, &state, data, &mut row) {
+                    return Err(ModelError);
+                }
+                for (target, &q) in next.iter_mut().zip(&row) {
+                    *target += p * q;
+                }
+            }
+            std::mem::swap(&mut occupancy, &mut next);
+            carried = product.advance_state(step, &lead_state, data);
+        }
+        Ok(())
+    }
+}
+
+impl Model for ExpectedValueModel {
+    fn run(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        rng: &mut dyn RngCore,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<(), ModelError> {
+        self.run_observed(product, config, rng, cashflows, data, |_, _| {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Date;
+    use crate::Frequency;
+    use crate::product::{ProductDefinition, RequiredDataLayout};
+
+    /// Two-state chain: alive pays 10 per step and dies with probability 0.1.
+    /// The step count is carried in `reserves` and written to the data.
+    struct Decrement {
+        definition: ProductDefinition,
+        markov: bool,
+    }
+
+    impl Product for Decrement {
+        fn definition(&self) -> &ProductDefinition {
+            &self.definition
+        }
+
+        fn initial_state(&self) -> ProductState {
+            ProductState::new(0, 2, Amount::zero())
+        }
+
+        fn generate_required_data(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _rng: &mut dyn RngCore,
+            out: &mut RequiredDataBuffer,
+        ) {
+            out.set_policy_scalar(0, state.reserves.value());
+        }
+
+        fn cashflows(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [Amount],
+        ) {
+            let paying = if state.state_id == 0 { 10.0 } else { 0.0 };
+            out[0] = Amount::from_f64(paying * state.in_force as f64);
+        }
+
+        fn next_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            _rng: &mut dyn RngCore,
+        ) -> ProductState {
+            *state
+        }
+
+        fn transition_probabilities(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [f64],
+        ) -> bool {
+            if state.state_id == 0 {
+                out.copy_from_slice(&[0.9, 0.1]);
+            } else {
+                out.copy_from_slice(&[0.0, 1.0]);
+            }
+            self.markov
+        }
+
+        fn advance_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+        ) -> ProductState {
+            ProductState {
+                reserves: state.reserves + Amount::from_f64(1.0),
+                ..*state
+            }
+        }
+    }
+
+    struct ZeroRng;
+
+    impl RngCore for ZeroRng {
+        fn next_u32(&mut self) -> u32 {
+            0
+        }
+    }
+
+    fn setup(markov: bool) -> (Decrement, ModelConfig, CashflowBuffer, RequiredDataBuffer) {
+        let layout = RequiredDataLayout::new(1, 0).unwrap();
+        let definition = ProductDefinition::new(2, 1, layout.clone()).unwrap();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps: 3,
+        };
+        let times =
+            crate::generate_cashflow_dates(config.start, config.steps, config.frequency).unwrap();
+        let cashflows = CashflowBuffer::new(2, 1, times).unwrap();
+        let data = RequiredDataBuffer::new(layout, 2).unwrap();
+        (Decrement { definition, markov }, config, cashflows, data)
+    }
+
+    #[test]
+    fn weights_cashflows_by_state_occupancy() {
+        let (product, config, mut cashflows, mut data) = setup(true);
+        data.enable_history(cashflows.times().to_vec()).unwrap();
+        ExpectedValueModel
+            .run(&product, &config, &mut ZeroRng, &mut cashflows, &mut data)
+            .unwrap();
+
+        let alive: Vec<f64> = cashflows.series(0, 0).iter().map(|a| a.value()).collect();
+        assert_eq!(alive.len(), 3);
+        assert!((alive[0] - 20.0).abs() < 1e-12);
+        assert!((alive[1] - 18.0).abs() < 1e-12);
+        assert!((alive[2] - 16.2).abs() < 1e-12);
+        assert!(cashflows.series(1, 0).iter().all(|a| *a == Amount::zero()));
+        assert_eq!(
+            data.history().unwrap().policy_scalar_series(0),
+            &[0.0, 1.0, 2.0]
+        );
+
+        let occupancy = ExpectedValueModel
+            .occupancy(&product, 2, &mut ZeroRng, &mut data)
+            .unwrap();
+        assert_eq!(occupancy.len(), 4);
+        assert!((occupancy[2] - 0.9).abs() < 1e-12);
+        assert!((occupancy[3] - 0.1).abs() < 1e-12);
+
+        let expected = cashflows.clone();
+        let rows = ExpectedValueModel
+            .run_with_occupancy(&product, &config, &mut ZeroRng, &mut cashflows, &mut data)
+            .unwrap();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[..4], occupancy[..]);
+        assert_eq!(cashflows.series(0, 0), expected.series(0, 0));
+    }
+
+    #[test]
+    fn rejects_products_without_transition_probabilities() {
+        let (product, config, mut cashflows, mut data) = setup(false);
+        assert_eq!(
+            ExpectedValueModel.run(&product, &config, &mut ZeroRng, &mut cashflows, &mut data),
+            Err(ModelError)
+        );
+    }
+}
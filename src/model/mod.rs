@@ -1,10 +1,18 @@
-use crate::product::{CashflowBuffer, Product, ProductDefinition, RequiredDataBuffer};
+mod expected;
+mod monte_carlo;
+
+pub use expected::ExpectedValueModel;
+pub use monte_carlo::{Decrements, MonteCarloModel};
+
+use crate::product::{CashflowBuffer, Product, RequiredDataBuffer};
+use crate::rng::{RngCore, next_uniform};
 use crate::{Date, Frequency};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ModelError;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModelConfig {
     pub start: Date,
     pub frequency: Frequency,
@@ -16,6 +24,10 @@ pub struct ModelConfig {
 /// Determinism: with identical inputs and an RNG stream in the same state,
 /// implementations must produce identical outputs in the provided buffers.
 ///
+/// Implementations call [`RequiredDataBuffer::record`] after generating each
+/// step's required data so that buffers with history enabled keep an audit
+/// trail.
+///
 /// # Examples
 ///
 /// ```rust
@@ -82,8 +94,9 @@ pub struct ModelConfig {
 ///         cashflows: &mut CashflowBuffer,
 ///         data: &mut RequiredDataBuffer,
 ///     ) -> Result<(), ModelError> {
-///         validate_buffers(product.definition(), config.steps, cashflows, data)?;
+///         validate_buffers(product, config, cashflows, data)?;
 ///         product.generate_required_data(0, &product.initial_state(), rng, data);
+///         data.record(0);
 ///         Ok(())
 ///     }
 /// }
@@ -97,7 +110,7 @@ pub struct ModelConfig {
 /// }
 ///
 /// let layout = RequiredDataLayout::new(1, 0).unwrap();
-/// let definition = ProductDefinition::new(1, 1, layout).unwrap();
+/// let definition = ProductDefinition::new(1, 1, layout.clone()).unwrap();
 /// let product = DemoProduct { definition };
 /// let config = ModelConfig {
 ///     start: Date::new(2024, 1, 1).unwrap(),
@@ -117,25 +130,55 @@ pub trait Model {
         &self,
         product: &dyn Product,
         config: &ModelConfig,
-        rng: &mut dyn crate::rng::RngCore,
+        rng: &mut dyn RngCore,
         cashflows: &mut CashflowBuffer,
         data: &mut RequiredDataBuffer,
     ) -> Result<(), ModelError>;
 }
 
+/// Draws the index of the next state from transition probabilities.
+///
+/// Probability mass missing from `probabilities` (rounding) falls to the last
+/// state with a non-zero probability.
+pub fn sample_transition(probabilities: &[f64], rng: &mut dyn RngCore) -> usize {
+    let u = next_uniform(rng);
+    let mut cumulative = 0.0;
+    let mut last = 0;
+    for (state, &p) in probabilities.iter().enumerate() {
+        if p <= 0.0 {
+            continue;
+        }
+        cumulative += p;
+        last = state;
+        if u < cumulative {
+            return state;
+        }
+    }
+    last
+}
+
+/// Checks that the buffers fit the product's definition and `config`, and
+/// that `config` steps at the product's [`frequency`](Product::frequency).
 pub fn validate_buffers(
-    definition: &ProductDefinition,
-    steps: usize,
+    product: &dyn Product,
+    config: &ModelConfig,
     cashflows: &CashflowBuffer,
     data: &RequiredDataBuffer,
 ) -> Result<(), ModelError> {
+    if product
+        .frequency()
+        .is_some_and(|frequency| frequency != config.frequency)
+    {
+        return Err(ModelError);
+    }
+    let definition = product.definition();
     if cashflows.n_states() != definition.n_states
         || cashflows.n_kinds() != definition.n_kinds
-        || cashflows.len_steps() != steps
+        || cashflows.len_steps() != config.steps
     {
         return Err(ModelError);
     }
-    if data.n_states() != definition.n_states || data.layout() != definition.required_data {
+    if data.n_states() != definition.n_states || data.layout() != &definition.required_data {
         return Err(ModelError);
     }
     Ok(())
@@ -148,18 +191,31 @@ mod tests {
     use crate::product::{ProductDefinition, RequiredDataLayout};
     use crate::rng::RngCore;
 
+    fn config(frequency: Frequency, steps: usize) -> ModelConfig {
+        ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency,
+            steps,
+        }
+    }
+
     #[test]
     fn validate_buffers_accepts_matching_layouts() {
         let layout = RequiredDataLayout::new(1, 2).unwrap();
-        let definition = ProductDefinition::new(2, 3, layout).unwrap();
+        let definition = ProductDefinition::new(2, 3, layout.clone()).unwrap();
         let data = RequiredDataBuffer::new(layout, 2).unwrap();
         let times = vec![
             Date::new(2024, 1, 1).unwrap(),
             Date::new(2024, 2, 1).unwrap(),
         ];
         let cashflows = CashflowBuffer::new(2, 3, times).unwrap();
+        let product = TestProduct {
+            definition,
+            frequency: None,
+        };
 
-        assert!(validate_buffers(&definition, 2, &cashflows, &data).is_ok());
+        let monthly = config(Frequency::Monthly, 2);
+        assert!(validate_buffers(&product, &monthly, &cashflows, &data).is_ok());
     }
 
     #[test]
@@ -170,12 +226,38 @@ mod tests {
         let data = RequiredDataBuffer::new(wrong_layout, 2).unwrap();
         let times = vec![Date::new(2024, 1, 1).unwrap()];
         let cashflows = CashflowBuffer::new(2, 3, times).unwrap();
+        let product = TestProduct {
+            definition,
+            frequency: None,
+        };
 
-        assert!(validate_buffers(&definition, 1, &cashflows, &data).is_err());
+        let annual = config(Frequency::Annual, 1);
+        assert!(validate_buffers(&product, &annual, &cashflows, &data).is_err());
+    }
+
+    #[test]
+    fn validate_buffers_rejects_another_frequency() {
+        let layout = RequiredDataLayout::new(1, 2).unwrap();
+        let definition = ProductDefinition::new(2, 3, layout.clone()).unwrap();
+        let data = RequiredDataBuffer::new(layout, 2).unwrap();
+        let cashflows = CashflowBuffer::new(2, 3, vec![Date::constant(2024, 1, 1)]).unwrap();
+        let product = TestProduct {
+            definition,
+            frequency: Some(Frequency::Annual),
+        };
+
+        let annual = config(Frequency::Annual, 1);
+        assert!(validate_buffers(&product, &annual, &cashflows, &data).is_ok());
+        let monthly = config(Frequency::Monthly, 1);
+        assert_eq!(
+            validate_buffers(&product, &monthly, &cashflows, &data),
+            Err(ModelError)
+        );
     }
 
     struct TestProduct {
         definition: ProductDefinition,
+        frequency: Option<Frequency>,
     }
 
     impl Product for TestProduct {
@@ -187,6 +269,10 @@ mod tests {
             ProductState::new(0, 1, crate::product::Amount::zero())
         }
 
+        fn frequency(&self) -> Option<Frequency> {
+            self.frequency
+        }
+
         fn generate_required_data(
             &self,
             _time_index: usize,
@@ -230,7 +316,7 @@ mod tests {
             cashflows: &mut CashflowBuffer,
             data: &mut RequiredDataBuffer,
         ) -> Result<(), ModelError> {
-            validate_buffers(product.definition(), config.steps, cashflows, data)?;
+            validate_buffers(product, config, cashflows, data)?;
             product.generate_required_data(0, &product.initial_state(), rng, data);
             Ok(())
         }
@@ -248,7 +334,10 @@ mod tests {
     fn model_run_rejects_required_data_layout_mismatch() {
         let layout = RequiredDataLayout::new(1, 1).unwrap();
         let definition = ProductDefinition::new(2, 1, layout).unwrap();
-        let product = TestProduct { definition };
+        let product = TestProduct {
+            definition,
+            frequency: None,
+        };
         let config = ModelConfig {
             start: Date::new(2024, 1, 1).unwrap(),
             frequency: Frequency::Monthly,
@@ -270,8 +359,11 @@ mod tests {
     #[test]
     fn model_run_populates_required_data_from_product() {
         let layout = RequiredDataLayout::new(1, 1).unwrap();
-        let definition = ProductDefinition::new(1, 1, layout).unwrap();
-        let product = TestProduct { definition };
+        let definition = ProductDefinition::new(1, 1, layout.clone()).unwrap();
+        let product = TestProduct {
+            definition,
+            frequency: None,
+        };
         let config = ModelConfig {
             start: Date::new(2024, 1, 1).unwrap(),
             frequency: Frequency::Monthly,
@@ -289,4 +381,25 @@ mod tests {
         assert_eq!(data.policy_scalar(0), 3.5);
         assert_eq!(data.state_vector(0)[0], 1.25);
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn model_config_round_trips_through_serde() {
+        let config = ModelConfig {
+            start: Date::new(2024, 1, 31).unwrap(),
+            frequency: Frequency::Quarterly,
+            steps: 40,
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"start":"2024-01-31","frequency":"Quarterly","steps":40}"#
+        );
+        assert_eq!(serde_json::from_str::<ModelConfig>(&json).unwrap(), config);
+
+        let state = ProductState::new(1, 10, crate::product::Amount::from_f64(5.0));
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(json, r#"{"state_id":1,"in_force":10,"reserves":5.0}"#);
+        assert_eq!(serde_json::from_str::<ProductState>(&json).unwrap(), state);
+    }
 }
@@ -0,0 +1,317 @@
+use super::{ExpectedValueModel, Model, ModelConfig, ModelError, validate_buffers};
+use crate::product::{Amount, CashflowBuffer, Product, RequiredDataBuffer};
+use crate::rng::RngCore;
+use crate::rng::mgk32a::{Mgk32a, Mgk32aJump, Mgk32aStream, Mgk32aStreams, SeedError};
+
+/// How a scenario treats the product's decrements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Decrements {
+    /// Follow one path of states drawn through [`Product::next_state`].
+    #[default]
+    Simulated,
+    /// Weight states by occupancy as in [`ExpectedValueModel`], so only the
+    /// product's own draws (e.g. fund returns) vary between scenarios.
+    Expected,
+}
+
+/// Monte Carlo projection averaging cashflows over scenarios.
+///
+/// Scenario `i` draws from stream `i` of an MRG32k3a stream factory seeded
+/// with `seed`, so any scenario can be reproduced on its own with
+/// [`MonteCarloModel::run_scenario`] and results do not depend on the order in
+/// which scenarios are run. The `rng` passed to [`Model::run`] is not used.
+/// With required data history enabled, `data` holds the last scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonteCarloModel {
+    seed: [u64; 6],
+    scenarios: usize,
+    decrements: Decrements,
+}
+
+impl MonteCarloModel {
+    pub fn new(seed: [u64; 6], scenarios: usize) -> Result<Self, SeedError> {
+        Mgk32a::new(seed)?;
+        Ok(Self {
+            seed,
+            scenarios,
+            decrements: Decrements::default(),
+        })
+    }
+
+    pub fn with_decrements(mut self, decrements: Decrements) -> Self {
+        self.decrements = decrements;
+        self
+    }
+
+    pub const fn scenarios(&self) -> usize {
+        self.scenarios
+    }
+
+    /// Random stream of scenario `scenario`.
+    pub fn stream(&self, scenario: usize) -> Mgk32aStream {
+        let mut rng = Mgk32a::new(self.seed).expect("seed validated on construction");
+        Mgk32aJump::stream()
+            .repeat(scenario as u128)
+            .apply(&mut rng);
+        Mgk32aStream::new(rng.state()).expect("jumped states are valid seeds")
+    }
+
+    /// Projects a single scenario into `cashflows`, overwriting it.
+    pub fn run_scenario(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        scenario: usize,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<(), ModelError> {
+        self.project(product, config, &mut self.stream(scenario), cashflows, data)
+    }
+
+    fn project(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        rng: &mut dyn RngCore,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<(), ModelError> {
+        if self.decrements == Decrements::Expected {
+            return ExpectedValueModel.run(product, config, rng, cashflows, data);
+        }
+        validate_buffers(product, config, cashflows, data)?;
+        clear(cashflows);
+        let mut out = vec![Amount::zero(); product.definition().n_kinds];
+        let mut state = product.initial_state();
+        for step in 0..config.steps {
+            if state.state_id >= cashflows.n_states() {
+                return Err(ModelError);
+            }
+            product.generate_required_data(step, &state, rng, data);
+            data.record(step);
+            product.cashflows(step, &state, data, &mut out);
+            for (kind, &amount) in out.iter().enumerate() {
+                *cashflows.amount_mut(state.state_id, kind, step) = amount;
+            }
+            state = product.next_state(step, &state, data, rng);
+        }
+        Ok(())
+    }
+}
+
+impl Model for MonteCarloModel {
+    fn run(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        _rng: &mut dyn RngCore,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<(), ModelError> {
+        validate_buffers(product, config, cashflows, data)?;
+        if self.scenarios == 0 {
+            return Err(ModelError);
+        }
+        clear(cashflows);
+        let mut scenario = CashflowBuffer::new(
+            cashflows.n_states(),
+            cashflows.n_kinds(),
+            cashflows.times().to_vec(),
+        )
+        .map_err(|_| ModelError)?;
+        let weight = 1.0 / self.scenarios as f64;
+        let streams = Mgk32aStreams::new(self.seed).map_err(|_| ModelError)?;
+        for mut stream in streams.take(self.scenarios) {
+            self.project(product, config, &mut stream, &mut scenario, data)?;
+            for state in 0..cashflows.n_states() {
+                for kind in 0..cashflows.n_kinds() {
+                    let total = cashflows.series_mut(state, kind);
+                    for (sum, &amount) in total.iter_mut().zip(scenario.series(state, kind)) {
+                        *sum += amount * weight;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn clear(cashflows: &mut CashflowBuffer) {
+    for state in 0..cashflows.n_states() {
+        for kind in 0..cashflows.n_kinds() {
+            cashflows.series_mut(state, kind).fill(Amount::zero());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{ProductDefinition, ProductState, RequiredDataLayout};
+    use crate::rng::next_uniform;
+    use crate::{Date, Frequency};
+
+    const SEED: [u64; 6] = [12345; 6];
+
+    /// Alive pays a uniform draw each step and dies with probability 0.5.
+    struct Coin {
+        definition: ProductDefinition,
+    }
+
+    impl Product for Coin {
+        fn definition(&self) -> &ProductDefinition {
+            &self.definition
+        }
+
+        fn initial_state(&self) -> ProductState {
+            ProductState::new(0, 1, Amount::zero())
+        }
+
+        fn generate_required_data(
+            &self,
+            _time_index: usize,
+            _state: &ProductState,
+            rng: &mut dyn RngCore,
+            out: &mut RequiredDataBuffer,
+        ) {
+            out.set_policy_scalar(0, next_uniform(rng));
+        }
+
+        fn cashflows(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            data: &RequiredDataBuffer,
+            out: &mut [Amount],
+        ) {
+            let alive = if state.state_id == 0 { 1.0 } else { 0.0 };
+            out[0] = Amount::from_f64(alive * data.policy_scalar(0));
+        }
+
+        fn next_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            rng: &mut dyn RngCore,
+        ) -> ProductState {
+            let dies = state.state_id == 1 || next_uniform(rng) < 0.5;
+            ProductState {
+                state_id: usize::from(dies),
+                ..*state
+            }
+        }
+
+        fn transition_probabilities(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [f64],
+        ) -> bool {
+            if state.state_id == 0 {
+                out.copy_from_slice(&[0.5, 0.5]);
+            } else {
+                out.copy_from_slice(&[0.0, 1.0]);
+            }
+            true
+        }
+    }
+
+    fn setup() -> (Coin, ModelConfig, CashflowBuffer, RequiredDataBuffer) {
+        let layout = RequiredDataLayout::new(1, 0).unwrap();
+        let definition = ProductDefinition::new(2, 1, layout.clone()).unwrap();
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps: 2,
+        };
+        let times =
+            crate::generate_cashflow_dates(config.start, config.steps, config.frequency).unwrap();
+        let cashflows = CashflowBuffer::new(2, 1, times).unwrap();
+        let data = RequiredDataBuffer::new(layout, 2).unwrap();
+        (Coin { definition }, config, cashflows, data)
+    }
+
+    fn alive(cashflows: &CashflowBuffer) -> Vec<f64> {
+        cashflows.series(0, 0).iter().map(|a| a.value()).collect()
+    }
+
+    #[test]
+    fn averages_reproducible_scenarios() {
+        let (product, config, mut cashflows, mut data) = setup();
+        let model = MonteCarloModel::new(SEED, 4000).unwrap();
+        model
+            .run(&product, &config, &mut NoRng, &mut cashflows, &mut data)
+            .unwrap();
+        let mean = alive(&cashflows);
+        // E[U] = 0.5 while alive, with survival 1 then 0.5.
+        assert!((mean[0] - 0.5).abs() < 0.02);
+        assert!((mean[1] - 0.25).abs() < 0.02);
+
+        let mut again = cashflows.clone();
+        model
+            .run(&product, &config, &mut NoRng, &mut again, &mut data)
+            .unwrap();
+        assert_eq!(alive(&again), alive(&cashflows));
+
+        // Averaging individually reproduced scenarios gives the same result.
+        let small = MonteCarloModel::new(SEED, 3).unwrap();
+        small
+            .run(&product, &config, &mut NoRng, &mut cashflows, &mut data)
+            .unwrap();
+        let mut sum = [0.0; 2];
+        for scenario in 0..3 {
+            small
+                .run_scenario(&product, &config, scenario, &mut again, &mut data)
+                .unwrap();
+            for (total, value) in sum.iter_mut().zip(alive(&again)) {
+                *total += value / 3.0;
+            }
+        }
+        for (total, value) in sum.iter().zip(alive(&cashflows)) {
+            assert!((total - value).abs() < 1e-12);
+        }
+        let mut first = small.stream(0);
+        let mut streams = Mgk32aStreams::new(SEED).unwrap();
+        assert_eq!(first.next_u32(), streams.next_stream().next_u32());
+        assert_eq!(small.stream(2).state(), streams.nth(1).unwrap().state());
+    }
+
+    #[test]
+    fn expected_decrements_only_vary_product_draws() {
+        let (product, config, mut cashflows, mut data) = setup();
+        MonteCarloModel::new(SEED, 1)
+            .unwrap()
+            .with_decrements(Decrements::Expected)
+            .run(&product, &config, &mut NoRng, &mut cashflows, &mut data)
+            .unwrap();
+        let mut stream = MonteCarloModel::new(SEED, 1).unwrap().stream(0);
+        let first = next_uniform(&mut stream);
+        let second = next_uniform(&mut stream);
+        let mean = alive(&cashflows);
+        assert!((mean[0] - first).abs() < 1e-12);
+        assert!((mean[1] - 0.5 * second).abs() < 1e-12);
+
+        assert!(MonteCarloModel::new([0; 6], 1).is_err());
+        assert_eq!(
+            MonteCarloModel::new(SEED, 0).unwrap().run(
+                &product,
+                &config,
+                &mut NoRng,
+                &mut cashflows,
+                &mut data
+            ),
+            Err(ModelError)
+        );
+    }
+
+    struct NoRng;
+
+    impl RngCore for NoRng {
+        fn next_u32(&mut self) -> u32 {
+            unreachable!("Monte Carlo scenarios draw from their own streams")
+        }
+    }
+}
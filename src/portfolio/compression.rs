@@ -0,0 +1,679 @@
+//! Model-point compression of seriatim policies.
+//!
+//! Policies are grouped by product code and the configured keys, then each
+//! group is replaced by one representative record carrying the group's total
+//! in-force. Monetary fields are in-force-weighted means, so group totals are
+//! preserved exactly; ages and durations are in-force-weighted means in days
+//! from the valuation date.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use jiff::ToSpan;
+
+use super::{PolicyRecord, ProductRegistry, RegistryError, Sex};
+use crate::model::{Model, ModelConfig, ModelError};
+use crate::product::{CashflowBuffer, CashflowBufferError, RequiredDataBuffer};
+use crate::rng::RngCore;
+use crate::{Date, generate_cashflow_dates};
+
+/// Banding of an integer attribute such as an age or duration in years.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Bins {
+    /// Bands `[k * width, (k + 1) * width)`.
+    Width(i32),
+    /// Bands starting at each ascending edge; values below the first edge
+    /// share one band.
+    Edges(Vec<i32>),
+}
+
+impl Bins {
+    fn band(&self, value: i32) -> KeyPart {
+        match self {
+            Self::Width(width) => {
+                let width = (*width).max(1);
+                let lower = value.div_euclid(width) * width;
+                KeyPart::Band {
+                    lower,
+                    upper: Some(lower + width),
+                }
+            }
+            Self::Edges(edges) => match edges.iter().rposition(|&edge| edge <= value) {
+                Some(i) => KeyPart::Band {
+                    lower: edges[i],
+                    upper: edges.get(i + 1).copied(),
+                },
+                None => KeyPart::Band {
+                    lower: i32::MIN,
+                    upper: edges.first().copied(),
+                },
+            },
+        }
+    }
+}
+
+/// Attribute that separates policies into different model points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupingKey {
+    Sex,
+    IssueAge(Bins),
+    /// Age at the valuation date.
+    AttainedAge(Bins),
+    /// Completed policy years at the valuation date.
+    Duration(Bins),
+    /// Raw value of an unmapped input column.
+    Extra(String),
+}
+
+/// Quantity whose total and spread are kept for each model point.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Moment {
+    SumAssured,
+    Premium,
+    /// Numeric unmapped input column, such as a reserve.
+    Extra(String),
+}
+
+impl Moment {
+    fn value(&self, record: &PolicyRecord) -> Result<f64, CompressionError> {
+        match self {
+            Self::SumAssured => Ok(record.sum_assured),
+            Self::Premium => Ok(record.premium),
+            Self::Extra(column) => record
+                .extra(column)
+                .and_then(|value| value.trim().parse().ok())
+                .ok_or_else(|| CompressionError::NonNumeric {
+                    policy_id: record.policy_id.clone(),
+                    column: column.clone(),
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionConfig {
+    pub valuation_date: Date,
+    pub keys: Vec<GroupingKey>,
+    pub moments: Vec<Moment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressionError {
+    /// A moment column is missing or not a number.
+    NonNumeric { policy_id: String, column: String },
+    /// A representative date falls outside the supported range.
+    Date,
+}
+
+impl fmt::Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NonNumeric { policy_id, column } => {
+                write!(f, "policy `{policy_id}`: column `{column}` is not numeric")
+            }
+            Self::Date => f.write_str("representative date out of range"),
+        }
+    }
+}
+
+impl std::error::Error for CompressionError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyPart {
+    Text(String),
+    /// Half-open band `[lower, upper)`; `i32::MIN` marks "below the first edge".
+    Band {
+        lower: i32,
+        upper: Option<i32>,
+    },
+}
+
+impl fmt::Display for KeyPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Band {
+                lower: i32::MIN,
+                upper: Some(upper),
+            } => write!(f, "<{upper}"),
+            Self::Band {
+                lower,
+                upper: Some(upper),
+            } => write!(f, "{lower}-{}", upper - 1),
+            Self::Band { lower, upper: None } => write!(f, "{lower}+"),
+        }
+    }
+}
+
+/// In-force-weighted total, mean and standard deviation of a [`Moment`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MomentSummary {
+    pub moment: Moment,
+    pub total: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+/// One compressed group and its representative record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPoint {
+    /// Product code followed by one label per grouping key.
+    pub key: Vec<String>,
+    pub record: PolicyRecord,
+    /// Indices of the seriatim records in the group.
+    pub members: Vec<usize>,
+    pub moments: Vec<MomentSummary>,
+}
+
+/// Groups `records` into model points, ordered by key.
+///
+/// Policies with no in-force are dropped.
+pub fn compress(
+    records: &[PolicyRecord],
+    config: &CompressionConfig,
+) -> Result<Vec<ModelPoint>, CompressionError> {
+    let mut groups: BTreeMap<Vec<KeyPart>, Vec<usize>> = BTreeMap::new();
+    for (index, record) in records.iter().enumerate() {
+        if record.in_force == 0 {
+            continue;
+        }
+        groups
+            .entry(group_key(record, config))
+            .or_default()
+            .push(index);
+    }
+
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(n, (key, members))| {
+            let record = representative(n, records, &members, config)?;
+            let moments = config
+                .moments
+                .iter()
+                .map(|moment| summarize(moment, records, &members))
+                .collect::<Result<_, _>>()?;
+            Ok(ModelPoint {
+                key: key.iter().map(ToString::to_string).collect(),
+                record,
+                members,
+                moments,
+            })
+        })
+        .collect()
+}
+
+fn group_key(record: &PolicyRecord, config: &CompressionConfig) -> Vec<KeyPart> {
+    let mut key = vec![KeyPart::Text(record.product_code.clone())];
+    for grouping in &config.keys {
+        key.push(match grouping {
+            GroupingKey::Sex => KeyPart::Text(format!("{:?}", record.sex)),
+            GroupingKey::IssueAge(bins) => bins.band(record.issue_age()),
+            GroupingKey::AttainedAge(bins) => bins.band(record.age_at(config.valuation_date)),
+            GroupingKey::Duration(bins) => bins.band(record.duration_at(config.valuation_date)),
+            GroupingKey::Extra(column) => {
+                KeyPart::Text(record.extra(column).unwrap_or_default().to_owned())
+            }
+        });
+    }
+    key
+}
+
+fn representative(
+    n: usize,
+    records: &[PolicyRecord],
+    members: &[usize],
+    config: &CompressionConfig,
+) -> Result<PolicyRecord, CompressionError> {
+    let first = &records[members[0]];
+    let in_force: u64 = members.iter().map(|&i| records[i].in_force).sum();
+    let weight = in_force as f64;
+    let mean = |value: &dyn Fn(&PolicyRecord) -> f64| {
+        members
+            .iter()
+            .map(|&i| records[i].in_force as f64 * value(&records[i]))
+            .sum::<f64>()
+            / weight
+    };
+    let days_before = |date: Date| (config.valuation_date - date).get_days() as f64;
+    let mean_date = |days: f64| {
+        config
+            .valuation_date
+            .checked_sub((days.round() as i64).days())
+            .map_err(|_| CompressionError::Date)
+    };
+
+    let male: u64 = members
+        .iter()
+        .filter(|&&i| records[i].sex == Sex::Male)
+        .map(|&i| records[i].in_force)
+        .sum();
+    let sex = if 2 * male >= in_force {
+        Sex::Male
+    } else {
+        Sex::Female
+    };
+
+    let mut extra: BTreeMap<String, String> = first
+        .extra
+        .iter()
+        .filter(|(column, value)| {
+            members
+                .iter()
+                .all(|&i| records[i].extra(column) == Some(value.as_str()))
+        })
+        .map(|(column, value)| (column.clone(), value.clone()))
+        .collect();
+    for moment in &config.moments {
+        if let Moment::Extra(column) = moment {
+            let summary = summarize(moment, records, members)?;
+            extra.insert(column.clone(), summary.mean.to_string());
+        }
+    }
+
+    Ok(PolicyRecord {
+        policy_id: format!("MP{n}"),
+        product_code: first.product_code.clone(),
+        issue_date: mean_date(mean(&|r| days_before(r.issue_date)))?,
+        birth_date: mean_date(mean(&|r| days_before(r.birth_date)))?,
+        sex,
+        sum_assured: mean(&|r| r.sum_assured),
+        premium: mean(&|r| r.premium),
+        in_force,
+        extra,
+    })
+}
+
+fn summarize(
+    moment: &Moment,
+    records: &[PolicyRecord],
+    members: &[usize],
+) -> Result<MomentSummary, CompressionError> {
+    let mut weight = 0.0;
+    let mut total = 0.0;
+    let mut squares = 0.0;
+    for &i in members {
+        let w = records[i].in_force as f64;
+        let x = moment.value(&records[i])?;
+        weight += w;
+        total += w * x;
+        squares += w * x * x;
+    }
+    let mean = total / weight;
+    Ok(MomentSummary {
+        moment: moment.clone(),
+        total,
+        mean,
+        std_dev: (squares / weight - mean * mean).max(0.0).sqrt(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectionError {
+    Registry(RegistryError),
+    Model(ModelError),
+    /// Products produced buffers of different shapes or dates.
+    Shape(CashflowBufferError),
+    /// The projection dates could not be generated.
+    Dates,
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Registry(err) => err.fmt(f),
+            Self::Model(_) => f.write_str("model run failed"),
+            Self::Shape(err) => err.fmt(f),
+            Self::Dates => f.write_str("cannot generate projection dates"),
+        }
+    }
+}
+
+impl std::error::Error for ProjectionError {}
+
+/// Runs every record through `model` and sums the cashflows.
+///
+/// All products must share state and kind dimensions. Records are projected in
+/// order from the one `rng` stream.
+pub fn project_records(
+    model: &dyn Model,
+    registry: &ProductRegistry,
+    records: &[PolicyRecord],
+    config: &ModelConfig,
+    rng: &mut dyn RngCore,
+) -> Result<Option<CashflowBuffer>, ProjectionError> {
+    let times = generate_cashflow_dates(config.start, config.steps, config.frequency)
+        .map_err(|_| ProjectionError::Dates)?;
+    let mut total: Option<CashflowBuffer> = None;
+    for record in records {
+        let product = registry.build(record).map_err(ProjectionError::Registry)?;
+        let definition = product.definition();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times.clone())
+                .map_err(ProjectionError::Shape)?;
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states)
+                .map_err(|_| ProjectionError::Shape(CashflowBufferError))?;
+        model
+            .run(product.as_ref(), config, rng, &mut cashflows, &mut data)
+            .map_err(ProjectionError::Model)?;
+        match &mut total {
+            Some(total) => total
+                .add_buffer(&cashflows)
+                .map_err(ProjectionError::Shape)?,
+            None => total = Some(cashflows),
+        }
+    }
+    Ok(total)
+}
+
+/// Compressed-versus-seriatim error for one cashflow kind, summed over states.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KindError {
+    pub kind: usize,
+    pub seriatim_total: f64,
+    pub compressed_total: f64,
+    /// Largest absolute difference at any single step.
+    pub max_step_error: f64,
+}
+
+impl KindError {
+    pub fn total_error(&self) -> f64 {
+        self.compressed_total - self.seriatim_total
+    }
+
+    /// Total error relative to the seriatim total; zero when both are zero.
+    pub fn relative_error(&self) -> f64 {
+        if self.seriatim_total == 0.0 {
+            if self.compressed_total == 0.0 {
+                0.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            self.total_error() / self.seriatim_total.abs()
+        }
+    }
+}
+
+/// Accuracy of a compressed portfolio against its seriatim projection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionReport {
+    pub policies: usize,
+    pub model_points: usize,
+    pub kinds: Vec<KindError>,
+}
+
+impl CompressionReport {
+    pub fn new(
+        policies: usize,
+        model_points: usize,
+        seriatim: &CashflowBuffer,
+        compressed: &CashflowBuffer,
+    ) -> Result<Self, CashflowBufferError> {
+        if !seriatim.is_compatible(compressed) {
+            return Err(CashflowBufferError);
+        }
+        let seriatim = seriatim.sum_over_states();
+        let compressed = compressed.sum_over_states();
+        let kinds = (0..seriatim.n_kinds())
+            .map(|kind| {
+                let s = seriatim.series(0, kind);
+                let c = compressed.series(0, kind);
+                KindError {
+                    kind,
+                    seriatim_total: s.iter().map(|a| a.value()).sum(),
+                    compressed_total: c.iter().map(|a| a.value()).sum(),
+                    max_step_error: s
+                        .iter()
+                        .zip(c)
+                        .map(|(s, c)| (c.value() - s.value()).abs())
+                        .fold(0.0, f64::max),
+                }
+            })
+            .collect();
+        Ok(Self {
+            policies,
+            model_points,
+            kinds,
+        })
+    }
+
+    /// Policies per model point.
+    pub fn compression_ratio(&self) -> f64 {
+        self.policies as f64 / self.model_points as f64
+    }
+
+    /// Largest absolute relative error across kinds.
+    pub fn max_relative_error(&self) -> f64 {
+        self.kinds
+            .iter()
+            .map(|k| k.relative_error().abs())
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Frequency;
+    use crate::model::validate_buffers;
+    use crate::product::{Amount, Product, ProductDefinition, ProductState, RequiredDataLayout};
+
+    /// Pays `in_force * sum_assured * age / 1000` each step, ageing one year per step.
+    struct AgeScaled {
+        definition: ProductDefinition,
+        record: PolicyRecord,
+        valuation: Date,
+    }
+
+    impl Product for AgeScaled {
+        fn definition(&self) -> &ProductDefinition {
+            &self.definition
+        }
+
+        fn initial_state(&self) -> ProductState {
+            self.record.initial_state(0)
+        }
+
+        fn generate_required_data(
+            &self,
+            time_index: usize,
+            _state: &ProductState,
+            _rng: &mut dyn RngCore,
+            out: &mut RequiredDataBuffer,
+        ) {
+            let days = (self.valuation - self.record.birth_date).get_days() as f64;
+            out.set_policy_scalar(0, days / 365.25 + time_index as f64);
+        }
+
+        fn cashflows(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            data: &RequiredDataBuffer,
+            out: &mut [Amount],
+        ) {
+            let rate = data.policy_scalar(0) / 1000.0;
+            out[0] = Amount::from_f64(state.in_force as f64 * self.record.sum_assured * rate);
+        }
+
+        fn next_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            _rng: &mut dyn RngCore,
+        ) -> ProductState {
+            *state
+        }
+    }
+
+    struct StepModel;
+
+    impl Model for StepModel {
+        fn run(
+            &self,
+            product: &dyn Product,
+            config: &ModelConfig,
+            rng: &mut dyn RngCore,
+            cashflows: &mut CashflowBuffer,
+            data: &mut RequiredDataBuffer,
+        ) -> Result<(), ModelError> {
+            validate_buffers(product, config, cashflows, data)?;
+            let mut state = product.initial_state();
+            let mut out = vec![Amount::zero(); product.definition().n_kinds];
+            for step in 0..config.steps {
+                product.generate_required_data(step, &state, rng, data);
+                data.record(step);
+                product.cashflows(step, &state, data, &mut out);
+                for (kind, amount) in out.iter().enumerate() {
+                    *cashflows.amount_mut(state.state_id, kind, step) += *amount;
+                }
+                state = product.next_state(step, &state, data, rng);
+            }
+            Ok(())
+        }
+    }
+
+    struct ZeroRng;
+
+    impl RngCore for ZeroRng {
+        fn next_u32(&mut self) -> u32 {
+            0
+        }
+    }
+
+    fn record(id: &str, birth: Date, sex: Sex, sum_assured: f64, in_force: u64) -> PolicyRecord {
+        let mut extra = BTreeMap::new();
+        extra.insert("reserve".to_string(), (sum_assured / 10.0).to_string());
+        extra.insert("channel".to_string(), "agency".to_string());
+        PolicyRecord {
+            policy_id: id.into(),
+            product_code: "AGE".into(),
+            issue_date: Date::constant(2020, 1, 1),
+            birth_date: birth,
+            sex,
+            sum_assured,
+            premium: sum_assured / 100.0,
+            in_force,
+            extra,
+        }
+    }
+
+    fn seriatim() -> Vec<PolicyRecord> {
+        vec![
+            record("A", Date::constant(1980, 1, 1), Sex::Male, 1000.0, 1),
+            record("B", Date::constant(1982, 1, 1), Sex::Female, 3000.0, 3),
+            record("C", Date::constant(1960, 6, 1), Sex::Male, 500.0, 2),
+            record("D", Date::constant(1961, 6, 1), Sex::Male, 700.0, 0),
+        ]
+    }
+
+    fn config() -> CompressionConfig {
+        CompressionConfig {
+            valuation_date: Date::constant(2025, 1, 1),
+            keys: vec![GroupingKey::AttainedAge(Bins::Width(10))],
+            moments: vec![Moment::SumAssured, Moment::Extra("reserve".into())],
+        }
+    }
+
+    #[test]
+    fn groups_by_band_and_preserves_totals() {
+        let records = seriatim();
+        let points = compress(&records, &config()).unwrap();
+        assert_eq!(points.len(), 2);
+
+        let young = &points[0];
+        assert_eq!(young.key, vec!["AGE", "40-49"]);
+        assert_eq!(young.members, vec![0, 1]);
+        assert_eq!(young.record.in_force, 4);
+        assert_eq!(young.record.sex, Sex::Female);
+        assert_eq!(young.record.sum_assured * 4.0, 10_000.0);
+        assert_eq!(young.moments[0].total, 10_000.0);
+        assert_eq!(young.moments[0].mean, 2500.0);
+        assert!((young.moments[0].std_dev - 866.025_403_784_438_6).abs() < 1e-9);
+        assert_eq!(young.moments[1].total, 1000.0);
+        assert_eq!(young.record.extra("reserve"), Some("250"));
+        assert_eq!(young.record.extra("channel"), Some("agency"));
+        assert_eq!(young.record.age_at(Date::constant(2025, 1, 1)), 43);
+
+        assert_eq!(points[1].key, vec!["AGE", "60-69"]);
+        assert_eq!(points[1].members, vec![2]);
+        assert_eq!(points[1].record.birth_date, Date::constant(1960, 6, 1));
+    }
+
+    #[test]
+    fn bins_support_explicit_edges() {
+        let bins = Bins::Edges(vec![18, 40, 65]);
+        assert_eq!(bins.band(10).to_string(), "<18");
+        assert_eq!(bins.band(40).to_string(), "40-64");
+        assert_eq!(bins.band(80).to_string(), "65+");
+        assert_eq!(Bins::Width(5).band(-3).to_string(), "-5--1");
+    }
+
+    #[test]
+    fn non_numeric_moments_are_reported() {
+        let mut config = config();
+        config.moments = vec![Moment::Extra("channel".into())];
+        assert_eq!(
+            compress(&seriatim(), &config),
+            Err(CompressionError::NonNumeric {
+                policy_id: "A".into(),
+                column: "channel".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn reports_projection_error_against_seriatim() {
+        let valuation = Date::constant(2025, 1, 1);
+        let mut registry = ProductRegistry::new();
+        registry.register("AGE", move |record: &PolicyRecord| {
+            let layout = RequiredDataLayout::new(1, 0).map_err(|e| e.to_string())?;
+            Ok(Box::new(AgeScaled {
+                definition: ProductDefinition::new(1, 1, layout).map_err(|e| e.to_string())?,
+                record: record.clone(),
+                valuation,
+            }) as Box<dyn Product>)
+        });
+        let model_config = ModelConfig {
+            start: valuation,
+            frequency: Frequency::Annual,
+            steps: 3,
+        };
+
+        let records = seriatim();
+        let points = compress(&records, &config()).unwrap();
+        let compressed: Vec<_> = points.iter().map(|p| p.record.clone()).collect();
+        let full = project_records(&StepModel, &registry, &records, &model_config, &mut ZeroRng)
+            .unwrap()
+            .unwrap();
+        let grouped = project_records(
+            &StepModel,
+            &registry,
+            &compressed,
+            &model_config,
+            &mut ZeroRng,
+        )
+        .unwrap()
+        .unwrap();
+
+        let report = CompressionReport::new(records.len(), points.len(), &full, &grouped).unwrap();
+        assert_eq!(report.compression_ratio(), 2.0);
+        let error = &report.kinds[0];
+        assert!(error.seriatim_total > 0.0);
+        assert!(error.relative_error().abs() < 0.02);
+        assert!(error.max_step_error > 0.0);
+        assert_eq!(report.max_relative_error(), error.relative_error().abs());
+
+        let unknown = vec![PolicyRecord {
+            product_code: "NONE".into(),
+            ..records[0].clone()
+        }];
+        assert!(matches!(
+            project_records(&StepModel, &registry, &unknown, &model_config, &mut ZeroRng),
+            Err(ProjectionError::Registry(_))
+        ));
+    }
+}
@@ -0,0 +1,591 @@
+//! In-force policy data: records, loaders and product construction.
+
+pub mod compression;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+mod registry;
+
+pub use registry::{ProductFactory, ProductRegistry, RegistryError};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Read};
+
+use crate::Date;
+use crate::product::{Amount, ProductState};
+
+#[derive(Debug)]
+pub enum PortfolioError {
+    Io(io::Error),
+    /// A required column is absent from the header.
+    MissingColumn(String),
+    /// The input has no header row.
+    Empty,
+    #[cfg(feature = "parquet")]
+    Parquet(::parquet::errors::ParquetError),
+}
+
+impl fmt::Display for PortfolioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::MissingColumn(name) => write!(f, "missing column `{name}`"),
+            Self::Empty => f.write_str("input has no header row"),
+            #[cfg(feature = "parquet")]
+            Self::Parquet(err) => write!(f, "parquet error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for PortfolioError {}
+
+impl From<io::Error> for PortfolioError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    /// Accepts `M`/`F`/`Male`/`Female`, ignoring case.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "m" | "male" => Some(Self::Male),
+            "f" | "female" => Some(Self::Female),
+            _ => None,
+        }
+    }
+}
+
+/// One in-force policy.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolicyRecord {
+    pub policy_id: String,
+    pub product_code: String,
+    pub issue_date: Date,
+    pub birth_date: Date,
+    pub sex: Sex,
+    pub sum_assured: f64,
+    pub premium: f64,
+    pub in_force: u64,
+    /// Unmapped columns, keyed by header.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl PolicyRecord {
+    /// Age in completed years on `date`.
+    pub fn age_at(&self, date: Date) -> i32 {
+        completed_years(self.birth_date, date)
+    }
+
+    pub fn issue_age(&self) -> i32 {
+        self.age_at(self.issue_date)
+    }
+
+    /// Completed policy years on `date`.
+    pub fn duration_at(&self, date: Date) -> i32 {
+        completed_years(self.issue_date, date)
+    }
+
+    pub fn extra(&self, column: &str) -> Option<&str> {
+        self.extra.get(column).map(String::as_str)
+    }
+
+    /// Starting state for a projection: the record's in-force count, no reserve.
+    pub fn initial_state(&self, state_id: usize) -> ProductState {
+        ProductState::new(state_id, self.in_force, Amount::zero())
+    }
+}
+
+fn completed_years(from: Date, to: Date) -> i32 {
+    let years = i32::from(to.year()) - i32::from(from.year());
+    if (to.month(), to.day()) < (from.month(), from.day()) {
+        years - 1
+    } else {
+        years
+    }
+}
+
+/// Standard record fields that can be mapped to input columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Column {
+    PolicyId,
+    ProductCode,
+    IssueDate,
+    BirthDate,
+    Sex,
+    SumAssured,
+    Premium,
+    /// Optional; defaults to 1 when unmapped.
+    InForce,
+}
+
+impl Column {
+    pub const ALL: [Self; 8] = [
+        Self::PolicyId,
+        Self::ProductCode,
+        Self::IssueDate,
+        Self::BirthDate,
+        Self::Sex,
+        Self::SumAssured,
+        Self::Premium,
+        Self::InForce,
+    ];
+
+    /// Header used when no mapping overrides it.
+    pub const fn default_header(self) -> &'static str {
+        match self {
+            Self::PolicyId => "policy_id",
+            Self::ProductCode => "product_code",
+            Self::IssueDate => "issue_date",
+            Self::BirthDate => "birth_date",
+            Self::Sex => "sex",
+            Self::SumAssured => "sum_assured",
+            Self::Premium => "premium",
+            Self::InForce => "in_force",
+        }
+    }
+
+    pub const fn is_required(self) -> bool {
+        !matches!(self, Self::InForce)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowErrorKind {
+    /// The row has a different number of cells than the header.
+    Malformed,
+    /// A required cell is empty.
+    Missing,
+    /// A cell could not be parsed as its column's type.
+    Parse(String),
+    /// The record failed a validation rule.
+    Invalid(String),
+}
+
+/// A rejected input row; other rows are still loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// 1-based data row number, not counting the header; skipped blank rows
+    /// are counted.
+    pub row: usize,
+    pub column: Option<String>,
+    pub kind: RowErrorKind,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(column) = &self.column {
+            write!(f, ", column `{column}`")?;
+        }
+        match &self.kind {
+            RowErrorKind::Malformed => f.write_str(": wrong number of cells"),
+            RowErrorKind::Missing => f.write_str(": missing value"),
+            RowErrorKind::Parse(value) => write!(f, ": cannot parse `{value}`"),
+            RowErrorKind::Invalid(reason) => write!(f, ": {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RowError {}
+
+/// Records that loaded, plus the rows that were rejected.
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub records: Vec<PolicyRecord>,
+    pub errors: Vec<RowError>,
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub type Validator = Box<dyn Fn(&PolicyRecord) -> Result<(), String> + Send + Sync>;
+
+/// Reads policy records with a configurable column mapping and validation.
+///
+/// Every record is checked for a birth date on or before the issue date and
+/// for finite, non-negative sum assured and premium. Further rules are added
+/// with [`PolicyLoader::with_validator`].
+pub struct PolicyLoader {
+    headers: BTreeMap<Column, String>,
+    issue_ages: Option<(i32, i32)>,
+    validators: Vec<Validator>,
+}
+
+impl Default for PolicyLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PolicyLoader {
+    pub fn new() -> Self {
+        Self {
+            headers: Column::ALL
+                .iter()
+                .map(|&c| (c, c.default_header().to_owned()))
+                .collect(),
+            issue_ages: None,
+            validators: Vec::new(),
+        }
+    }
+
+    /// Reads `column` from the input column named `header`.
+    pub fn with_column(mut self, column: Column, header: impl Into<String>) -> Self {
+        self.headers.insert(column, header.into());
+        self
+    }
+
+    /// Rejects records whose issue age falls outside `min..=max`.
+    pub fn with_issue_ages(mut self, min: i32, max: i32) -> Self {
+        self.issue_ages = Some((min, max));
+        self
+    }
+
+    pub fn with_validator(
+        mut self,
+        validator: impl Fn(&PolicyRecord) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validators.push(Box::new(validator));
+        self
+    }
+
+    pub fn header(&self, column: Column) -> &str {
+        &self.headers[&column]
+    }
+
+    /// Reads comma-separated records with a header row.
+    ///
+    /// Fields may be double-quoted, with `""` for a literal quote.
+    pub fn read_csv<R: Read>(&self, mut reader: R) -> Result<LoadReport, PortfolioError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let mut rows = parse_csv(&text).into_iter();
+        let header = rows.next().ok_or(PortfolioError::Empty)?;
+        let rows: Vec<_> = rows
+            .enumerate()
+            .map(|(i, cells)| (i + 1, cells))
+            .filter(|(_, cells)| !(cells.len() == 1 && cells[0].is_empty()))
+            .collect();
+        self.load_rows(&header, rows)
+    }
+
+    /// Parses rows of string cells, each with its data row number, against
+    /// `header`.
+    pub(crate) fn load_rows(
+        &self,
+        header: &[String],
+        rows: impl IntoIterator<Item = (usize, Vec<String>)>,
+    ) -> Result<LoadReport, PortfolioError> {
+        let mut positions = BTreeMap::new();
+        for (&column, name) in &self.headers {
+            match header.iter().position(|h| h == name) {
+                Some(index) => {
+                    positions.insert(column, index);
+                }
+                None if column.is_required() => {
+                    return Err(PortfolioError::MissingColumn(name.clone()));
+                }
+                None => {}
+            }
+        }
+
+        let mut report = LoadReport::default();
+        for (row, cells) in rows {
+            match self.parse_row(row, header, &positions, &cells) {
+                Ok(record) => report.records.push(record),
+                Err(err) => report.errors.push(err),
+            }
+        }
+        Ok(report)
+    }
+
+    fn parse_row(
+        &self,
+        row: usize,
+        header: &[String],
+        positions: &BTreeMap<Column, usize>,
+        cells: &[String],
+    ) -> Result<PolicyRecord, RowError> {
+        if cells.len() != header.len() {
+            return Err(RowError {
+                row,
+                column: None,
+                kind: RowErrorKind::Malformed,
+            });
+        }
+        let cell = |column: Column| -> Result<Option<&str>, RowError> {
+            let Some(&index) = positions.get(&column) else {
+                return Ok(None);
+            };
+            let value = cells[index].trim();
+            if value.is_empty() {
+                if column.is_required() {
+                    return Err(self.row_error(row, column, RowErrorKind::Missing));
+                }
+                return Ok(None);
+            }
+            Ok(Some(value))
+        };
+        let required = |column| cell(column).map(|value| value.unwrap_or_default());
+        let parse_error = |column, value: &str| {
+            self.row_error(row, column, RowErrorKind::Parse(value.to_owned()))
+        };
+        let date = |column| {
+            let value = required(column)?;
+            value
+                .parse::<Date>()
+                .map_err(|_| parse_error(column, value))
+        };
+        let number = |column| {
+            let value = required(column)?;
+            value.parse::<f64>().map_err(|_| parse_error(column, value))
+        };
+
+        let sex_value = required(Column::Sex)?;
+        let in_force = match cell(Column::InForce)? {
+            Some(value) => value
+                .parse::<u64>()
+                .map_err(|_| parse_error(Column::InForce, value))?,
+            None => 1,
+        };
+        let extra = header
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !positions.values().any(|p| p == index))
+            .map(|(index, name)| (name.clone(), cells[index].clone()))
+            .collect();
+
+        let record = PolicyRecord {
+            policy_id: required(Column::PolicyId)?.to_owned(),
+            product_code: required(Column::ProductCode)?.to_owned(),
+            issue_date: date(Column::IssueDate)?,
+            birth_date: date(Column::BirthDate)?,
+            sex: Sex::parse(sex_value).ok_or_else(|| parse_error(Column::Sex, sex_value))?,
+            sum_assured: number(Column::SumAssured)?,
+            premium: number(Column::Premium)?,
+            in_force,
+            extra,
+        };
+        self.validate(&record)
+            .map_err(|(column, reason)| RowError {
+                row,
+                column: column.map(|c| self.header(c).to_owned()),
+                kind: RowErrorKind::Invalid(reason),
+            })?;
+        Ok(record)
+    }
+
+    fn validate(&self, record: &PolicyRecord) -> Result<(), (Option<Column>, String)> {
+        if record.birth_date > record.issue_date {
+            return Err((
+                Some(Column::BirthDate),
+                "birth date is after issue date".into(),
+            ));
+        }
+        for (column, value) in [
+            (Column::SumAssured, record.sum_assured),
+            (Column::Premium, record.premium),
+        ] {
+            if !(value.is_finite() && value >= 0.0) {
+                return Err((Some(column), "must be finite and non-negative".into()));
+            }
+        }
+        if let Some((min, max)) = self.issue_ages {
+            let age = record.issue_age();
+            if !(min..=max).contains(&age) {
+                return Err((
+                    Some(Column::BirthDate),
+                    format!("issue age {age} outside {min}..={max}"),
+                ));
+            }
+        }
+        for validator in &self.validators {
+            validator(record).map_err(|reason| (None, reason))?;
+        }
+        Ok(())
+    }
+
+    fn row_error(&self, row: usize, column: Column, kind: RowErrorKind) -> RowError {
+        RowError {
+            row,
+            column: Some(self.header(column).to_owned()),
+            kind,
+        }
+    }
+}
+
+/// Splits CSV text into rows of unescaped cells.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DateError;
+
+    const SAMPLE: &str = "\
+policy_id,product_code,issue_date,birth_date,sex,sum_assured,premium,smoker
+P1,TERM10,2020-03-01,1980-06-15,M,100000,450.5,N
+P2,TERM10,2021-07-01,1975-01-01,female,250000,1200,Y
+P3,TERM10,2021-07-01,1975-01-01,X,250000,1200,Y
+P4,TERM10,not-a-date,1975-01-01,F,250000,1200,Y
+P5,TERM10,2021-07-01,2022-01-01,F,250000,1200,Y
+P6,TERM10,2021-07-01
+";
+
+    #[test]
+    fn loads_records_and_reports_row_errors() -> Result<(), DateError> {
+        let report = PolicyLoader::new().read_csv(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(report.records.len(), 2);
+        let first = &report.records[0];
+        assert_eq!(first.policy_id, "P1");
+        assert_eq!(first.issue_date, Date::new(2020, 3, 1)?);
+        assert_eq!(first.sex, Sex::Male);
+        assert_eq!(first.premium, 450.5);
+        assert_eq!(first.in_force, 1);
+        assert_eq!(first.extra("smoker"), Some("N"));
+        assert_eq!(first.issue_age(), 39);
+        assert_eq!(first.duration_at(Date::new(2024, 3, 1)?), 4);
+        assert_eq!(report.records[1].sex, Sex::Female);
+
+        let errors: Vec<_> = report
+            .errors
+            .iter()
+            .map(|e| (e.row, e.column.as_deref(), e.kind.clone()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (3, Some("sex"), RowErrorKind::Parse("X".into())),
+                (
+                    4,
+                    Some("issue_date"),
+                    RowErrorKind::Parse("not-a-date".into())
+                ),
+                (
+                    5,
+                    Some("birth_date"),
+                    RowErrorKind::Invalid("birth date is after issue date".into())
+                ),
+                (6, None, RowErrorKind::Malformed),
+            ]
+        );
+        assert_eq!(
+            report.errors[0].to_string(),
+            "row 3, column `sex`: cannot parse `X`"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn column_mapping_and_validators_are_configurable() {
+        let input = "ID,Plan,Issue,DOB,Gender,SA,Prem,Count\n\
+            \"A,1\",WL,2020-01-01,1960-01-01,F,50000,0,3\n\
+            B,WL,2020-01-01,1990-01-01,M,50000,0,1\n\
+            \n\
+            C,WL,2020-01-01,1970-01-01,M,,0,1\n";
+        let loader = PolicyLoader::new()
+            .with_column(Column::PolicyId, "ID")
+            .with_column(Column::ProductCode, "Plan")
+            .with_column(Column::IssueDate, "Issue")
+            .with_column(Column::BirthDate, "DOB")
+            .with_column(Column::Sex, "Gender")
+            .with_column(Column::SumAssured, "SA")
+            .with_column(Column::Premium, "Prem")
+            .with_column(Column::InForce, "Count")
+            .with_issue_ages(40, 70)
+            .with_validator(|record| {
+                if record.product_code == "WL" {
+                    Ok(())
+                } else {
+                    Err("unknown plan".into())
+                }
+            });
+        let report = loader.read_csv(input.as_bytes()).unwrap();
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].policy_id, "A,1");
+        assert_eq!(report.records[0].in_force, 3);
+        assert!(report.records[0].extra.is_empty());
+        assert_eq!(report.errors[0].row, 2);
+        assert_eq!(
+            report.errors[0].kind,
+            RowErrorKind::Invalid("issue age 30 outside 40..=70".into())
+        );
+        // The blank row is skipped but still counted.
+        assert_eq!(report.errors[1].row, 4);
+        assert_eq!(report.errors[1].kind, RowErrorKind::Missing);
+        assert_eq!(report.errors[1].column.as_deref(), Some("SA"));
+
+        let loader = PolicyLoader::new().with_validator(|_| Err("rejected".into()));
+        let report = loader.read_csv(SAMPLE.as_bytes()).unwrap();
+        assert!(report.records.is_empty());
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn missing_required_column_fails_the_load() {
+        let input = "policy_id,product_code\nP1,TERM10\n";
+        assert!(matches!(
+            PolicyLoader::new().read_csv(input.as_bytes()),
+            Err(PortfolioError::MissingColumn(name)) if name == "issue_date"
+        ));
+        assert!(matches!(
+            PolicyLoader::new().read_csv("".as_bytes()),
+            Err(PortfolioError::Empty)
+        ));
+    }
+
+    #[test]
+    fn csv_parser_handles_quotes_and_line_endings() {
+        let rows = parse_csv("a,\"b \"\"c\"\"\",\"d\ne\"\r\n1,,2");
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_string(), "b \"c\"".into(), "d\ne".into()],
+                vec!["1".into(), "".into(), "2".into()],
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::PolicyRecord;
+use crate::product::Product;
+
+/// Builds a product for one policy, with its initial state taken from the record.
+pub type ProductFactory =
+    Box<dyn Fn(&PolicyRecord) -> Result<Box<dyn Product>, String> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    /// No factory is registered for the record's product code.
+    UnknownProduct(String),
+    /// The factory rejected the record.
+    Factory { policy_id: String, message: String },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownProduct(code) => write!(f, "unknown product code `{code}`"),
+            Self::Factory { policy_id, message } => {
+                write!(f, "policy `{policy_id}`: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// Maps product codes to the factories that build them.
+#[derive(Default)]
+pub struct ProductRegistry {
+    factories: HashMap<String, ProductFactory>,
+}
+
+impl ProductRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `factory` for `code`, replacing any previous registration.
+    pub fn register(
+        &mut self,
+        code: impl Into<String>,
+        factory: impl Fn(&PolicyRecord) -> Result<Box<dyn Product>, String> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(code.into(), Box::new(factory));
+    }
+
+    pub fn contains(&self, code: &str) -> bool {
+        self.factories.contains_key(code)
+    }
+
+    pub fn build(&self, record: &PolicyRecord) -> Result<Box<dyn Product>, RegistryError> {
+        let factory = self
+            .factories
+            .get(&record.product_code)
+            .ok_or_else(|| RegistryError::UnknownProduct(record.product_code.clone()))?;
+        factory(record).map_err(|message| RegistryError::Factory {
+            policy_id: record.policy_id.clone(),
+            message,
+        })
+    }
+
+    /// Builds every record, keeping per-record failures in place.
+    pub fn build_all(
+        &self,
+        records: &[PolicyRecord],
+    ) -> Vec<Result<Box<dyn Product>, RegistryError>> {
+        records.iter().map(|record| self.build(record)).collect()
+    }
+}
+
+impl fmt::Debug for ProductRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut codes: Vec<_> = self.factories.keys().collect();
+        codes.sort();
+        f.debug_struct("ProductRegistry")
+            .field("codes", &codes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::portfolio::PolicyLoader;
+    use crate::product::{
+        Amount, ProductDefinition, ProductState, RequiredDataBuffer, RequiredDataLayout,
+    };
+    use crate::rng::RngCore;
+
+    struct FlatProduct {
+        definition: ProductDefinition,
+        initial: ProductState,
+    }
+
+    impl Product for FlatProduct {
+        fn definition(&self) -> &ProductDefinition {
+            &self.definition
+        }
+
+        fn initial_state(&self) -> ProductState {
+            self.initial
+        }
+
+        fn generate_required_data(
+            &self,
+            _time_index: usize,
+            _state: &ProductState,
+            _rng: &mut dyn RngCore,
+            _out: &mut RequiredDataBuffer,
+        ) {
+        }
+
+        fn cashflows(
+            &self,
+            _time_index: usize,
+            _state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [Amount],
+        ) {
+            out.fill(Amount::zero());
+        }
+
+        fn next_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            _rng: &mut dyn RngCore,
+        ) -> ProductState {
+            *state
+        }
+    }
+
+    #[test]
+    fn registry_builds_products_by_code() {
+        let input = "policy_id,product_code,issue_date,birth_date,sex,sum_assured,premium,in_force\n\
+            P1,FLAT,2020-01-01,1980-01-01,M,1000,10,4\n\
+            P2,OTHER,2020-01-01,1980-01-01,M,1000,10,1\n\
+            P3,FLAT,2020-01-01,1980-01-01,M,0,10,1\n";
+        let report = PolicyLoader::new().read_csv(input.as_bytes()).unwrap();
+
+        let mut registry = ProductRegistry::new();
+        registry.register("FLAT", |record: &PolicyRecord| {
+            if record.sum_assured == 0.0 {
+                return Err("zero sum assured".into());
+            }
+            let layout = RequiredDataLayout::new(1, 0).map_err(|e| e.to_string())?;
+            let definition = ProductDefinition::new(1, 1, layout).map_err(|e| e.to_string())?;
+            Ok(Box::new(FlatProduct {
+                definition,
+                initial: record.initial_state(0),
+            }) as Box<dyn Product>)
+        });
+        assert!(registry.contains("FLAT"));
+
+        let built = registry.build_all(&report.records);
+        assert_eq!(built.len(), 3);
+        let first = built[0].as_ref().unwrap();
+        assert_eq!(first.initial_state().in_force, 4);
+        assert_eq!(
+            built[1].as_ref().err(),
+            Some(&RegistryError::UnknownProduct("OTHER".into()))
+        );
+        assert_eq!(
+            built[2].as_ref().err().map(ToString::to_string),
+            Some("policy `P3`: zero sum assured".into())
+        );
+    }
+}
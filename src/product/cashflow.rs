@@ -1,8 +1,13 @@
-use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
+use super::ProductDefinition;
 use crate::{Date, DateError, Frequency};
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Amount(f64);
 
 impl Amount {
@@ -59,10 +64,61 @@ impl Neg for Amount {
     }
 }
 
+impl Mul<f64> for Amount {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self(self.0 * rhs)
+    }
+}
+
+impl Mul<Amount> for f64 {
+    type Output = Amount;
+
+    fn mul(self, rhs: Amount) -> Self::Output {
+        Amount(self * rhs.0)
+    }
+}
+
+impl Div<f64> for Amount {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self(self.0 / rhs)
+    }
+}
+
+impl MulAssign<f64> for Amount {
+    fn mul_assign(&mut self, rhs: f64) {
+        self.0 *= rhs;
+    }
+}
+
+impl DivAssign<f64> for Amount {
+    fn div_assign(&mut self, rhs: f64) {
+        self.0 /= rhs;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, v| acc + v)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct CashflowKindId(pub usize);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cashflow {
     pub time: Date,
     pub amount: Amount,
@@ -94,8 +150,18 @@ impl Cashflow {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CashflowBufferError;
 
+impl fmt::Display for CashflowBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid cashflow buffer dimensions")
+    }
+}
+
+impl std::error::Error for CashflowBufferError {}
+
 /// SoA cashflow storage with fixed dimensions per state/kind/step.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CashflowBufferParts"))]
 pub struct CashflowBuffer {
     times: Vec<Date>,
     amounts: Vec<Amount>,
@@ -164,6 +230,23 @@ impl CashflowBuffer {
         self.times.len()
     }
 
+    /// Raw amounts in `(state, kind, step)` order, steps fastest.
+    pub fn amounts(&self) -> &[Amount] {
+        &self.amounts
+    }
+
+    /// Amounts for one state and kind across all steps.
+    pub fn series(&self, state: usize, kind: usize) -> &[Amount] {
+        let start = self.offset(state, kind, 0);
+        &self.amounts[start..start + self.times.len()]
+    }
+
+    pub fn series_mut(&mut self, state: usize, kind: usize) -> &mut [Amount] {
+        let start = self.offset(state, kind, 0);
+        let len = self.times.len();
+        &mut self.amounts[start..start + len]
+    }
+
     pub fn amount(&self, state: usize, kind: usize, step: usize) -> Amount {
         let idx = self.offset(state, kind, step);
         self.amounts[idx]
@@ -174,6 +257,119 @@ impl CashflowBuffer {
         &mut self.amounts[idx]
     }
 
+    /// Returns `true` when both buffers share dimensions and time grid.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.n_states == other.n_states
+            && self.n_kinds == other.n_kinds
+            && self.times == other.times
+    }
+
+    /// Adds `other` element-wise; dimensions and time grids must match.
+    pub fn add_buffer(&mut self, other: &Self) -> Result<(), CashflowBufferError> {
+        self.zip_apply(other, |a, b| *a += b)
+    }
+
+    /// Subtracts `other` element-wise; dimensions and time grids must match.
+    pub fn sub_buffer(&mut self, other: &Self) -> Result<(), CashflowBufferError> {
+        self.zip_apply(other, |a, b| *a -= b)
+    }
+
+    /// Returns `self - other` element-wise.
+    pub fn difference(&self, other: &Self) -> Result<Self, CashflowBufferError> {
+        let mut out = self.clone();
+        out.sub_buffer(other)?;
+        Ok(out)
+    }
+
+    pub fn scale(&mut self, factor: f64) {
+        for amount in &mut self.amounts {
+            *amount *= factor;
+        }
+    }
+
+    /// Collapses all states into a single state.
+    pub fn sum_over_states(&self) -> Self {
+        let block = self.n_kinds * self.times.len();
+        let mut amounts = vec![Amount::zero(); block];
+        for chunk in self.amounts.chunks_exact(block) {
+            for (acc, &v) in amounts.iter_mut().zip(chunk) {
+                *acc += v;
+            }
+        }
+        self.with_shape(1, self.n_kinds, amounts)
+    }
+
+    /// Collapses all cashflow kinds into a single kind per state.
+    pub fn sum_over_kinds(&self) -> Self {
+        let steps = self.times.len();
+        let mut amounts = vec![Amount::zero(); self.n_states * steps];
+        for (state, out) in amounts.chunks_exact_mut(steps).enumerate() {
+            for kind in 0..self.n_kinds {
+                for (acc, &v) in out.iter_mut().zip(self.series(state, kind)) {
+                    *acc += v;
+                }
+            }
+        }
+        self.with_shape(self.n_states, 1, amounts)
+    }
+
+    /// Collapses all cashflow kinds into their signed net per state, from the
+    /// insurer's point of view, leaving out memo kinds.
+    pub fn net_over_kinds(
+        &self,
+        definition: &ProductDefinition,
+    ) -> Result<Self, CashflowBufferError> {
+        if definition.n_kinds != self.n_kinds || definition.n_states != self.n_states {
+            return Err(CashflowBufferError);
+        }
+        let steps = self.times.len();
+        let mut amounts = vec![Amount::zero(); self.n_states * steps];
+        for (state, out) in amounts.chunks_exact_mut(steps).enumerate() {
+            for (kind, metadata) in definition.kinds().iter().enumerate() {
+                for (acc, &v) in out.iter_mut().zip(self.series(state, kind)) {
+                    *acc += metadata.cash(v);
+                }
+            }
+        }
+        Ok(self.with_shape(self.n_states, 1, amounts))
+    }
+
+    /// Running totals over steps for every state and kind.
+    pub fn cumulative_over_steps(&self) -> Self {
+        let mut out = self.clone();
+        for series in out.amounts.chunks_exact_mut(self.times.len()) {
+            let mut running = Amount::zero();
+            for v in series {
+                running += *v;
+                *v = running;
+            }
+        }
+        out
+    }
+
+    fn with_shape(&self, n_states: usize, n_kinds: usize, amounts: Vec<Amount>) -> Self {
+        Self {
+            times: self.times.clone(),
+            amounts,
+            n_states,
+            n_kinds,
+        }
+    }
+
+    fn zip_apply(
+        &mut self,
+        other: &Self,
+        f: impl Fn(&mut Amount, Amount),
+    ) -> Result<(), CashflowBufferError> {
+        if !self.is_compatible(other) {
+            return Err(CashflowBufferError);
+        }
+        for (a, &b) in self.amounts.iter_mut().zip(&other.amounts) {
+            f(a, b);
+        }
+        Ok(())
+    }
+
     fn offset(&self, state: usize, kind: usize, step: usize) -> usize {
         debug_assert!(state < self.n_states);
         debug_assert!(kind < self.n_kinds);
@@ -182,6 +378,26 @@ impl CashflowBuffer {
     }
 }
 
+/// Unvalidated wire form of [`CashflowBuffer`]; deserialization goes through
+/// [`CashflowBuffer::from_parts`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CashflowBufferParts {
+    times: Vec<Date>,
+    amounts: Vec<Amount>,
+    n_states: usize,
+    n_kinds: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CashflowBufferParts> for CashflowBuffer {
+    type Error = CashflowBufferError;
+
+    fn try_from(parts: CashflowBufferParts) -> Result<Self, Self::Error> {
+        Self::from_parts(parts.n_states, parts.n_kinds, parts.times, parts.amounts)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +455,114 @@ mod tests {
         assert_eq!(buffer.amount(0, 1, 2), Amount::from_f64(-7.5));
         Ok(())
     }
+
+    #[test]
+    fn amount_scaling_and_sum() {
+        let amount = Amount::from_f64(200.0);
+        assert_eq!(amount * 0.25, Amount::from_f64(50.0));
+        assert_eq!(0.5 * amount, Amount::from_f64(100.0));
+        assert_eq!(amount / 4.0, Amount::from_f64(50.0));
+
+        let mut scaled = amount;
+        scaled *= 1.5;
+        assert_eq!(scaled, Amount::from_f64(300.0));
+        scaled /= 3.0;
+        assert_eq!(scaled, Amount::from_f64(100.0));
+
+        let values = [Amount::from_f64(1.0), Amount::from_f64(2.5)];
+        assert_eq!(values.iter().sum::<Amount>(), Amount::from_f64(3.5));
+        assert_eq!(values.into_iter().sum::<Amount>(), Amount::from_f64(3.5));
+        assert_eq!(std::iter::empty::<Amount>().sum::<Amount>(), Amount::zero());
+    }
+
+    fn sample_buffer(times: Vec<Date>) -> CashflowBuffer {
+        let n = 2 * 2 * times.len();
+        let amounts = (0..n).map(|i| Amount::from_f64(i as f64)).collect();
+        CashflowBuffer::from_parts(2, 2, times, amounts).unwrap()
+    }
+
+    #[test]
+    fn cashflow_buffer_elementwise_ops_check_shapes() -> Result<(), DateError> {
+        let times = vec![Date::new(2024, 1, 1)?, Date::new(2024, 2, 1)?];
+        let a = sample_buffer(times.clone());
+        let mut b = a.clone();
+        b.scale(2.0);
+        assert_eq!(b.amount(1, 1, 1), Amount::from_f64(14.0));
+
+        let diff = b.difference(&a).unwrap();
+        assert_eq!(diff.amounts(), a.amounts());
+
+        let mut sum = a.clone();
+        sum.add_buffer(&a).unwrap();
+        assert_eq!(sum.amounts(), b.amounts());
+        sum.sub_buffer(&b).unwrap();
+        assert!(sum.amounts().iter().all(|&v| v == Amount::zero()));
+
+        let other_grid = sample_buffer(vec![Date::new(2024, 1, 1)?, Date::new(2024, 3, 1)?]);
+        assert!(!a.is_compatible(&other_grid));
+        assert!(sum.add_buffer(&other_grid).is_err());
+        assert!(a.difference(&a.sum_over_states()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn cashflow_buffer_aggregates_over_states_kinds_and_steps() -> Result<(), DateError> {
+        let times = vec![Date::new(2024, 1, 1)?, Date::new(2024, 2, 1)?];
+        // Layout (state, kind, step): s0k0 = [0, 1], s0k1 = [2, 3], s1k0 = [4, 5], s1k1 = [6, 7].
+        let buffer = sample_buffer(times);
+        assert_eq!(
+            buffer.series(1, 0),
+            &[Amount::from_f64(4.0), Amount::from_f64(5.0)]
+        );
+
+        let by_kind = buffer.sum_over_states();
+        assert_eq!(by_kind.n_states(), 1);
+        assert_eq!(by_kind.n_kinds(), 2);
+        assert_eq!(by_kind.amount(0, 1, 0), Amount::from_f64(8.0));
+
+        let by_state = buffer.sum_over_kinds();
+        assert_eq!(by_state.n_states(), 2);
+        assert_eq!(by_state.n_kinds(), 1);
+        assert_eq!(by_state.amount(1, 0, 1), Amount::from_f64(12.0));
+
+        let cumulative = buffer.cumulative_over_steps();
+        assert_eq!(cumulative.amount(0, 1, 1), Amount::from_f64(5.0));
+        assert_eq!(cumulative.amount(1, 0, 0), Amount::from_f64(4.0));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn cashflow_types_round_trip_through_serde() -> Result<(), DateError> {
+        let times = vec![Date::new(2024, 1, 1)?, Date::new(2024, 2, 1)?];
+        let mut buffer = CashflowBuffer::new(1, 1, times).unwrap();
+        *buffer.amount_mut(0, 0, 1) = Amount::from_f64(12.5);
+        let json = serde_json::to_string(&buffer).unwrap();
+        assert_eq!(
+            json,
+            r#"{"times":["2024-01-01","2024-02-01"],"amounts":[0.0,12.5],"n_states":1,"n_kinds":1}"#
+        );
+        let back: CashflowBuffer = serde_json::from_str(&json).unwrap();
+        assert!(back.is_compatible(&buffer));
+        assert_eq!(back.amounts(), buffer.amounts());
+
+        let flow = Cashflow::new(
+            Date::new(2024, 3, 1)?,
+            Amount::from_f64(1.0),
+            CashflowKindId(2),
+        );
+        let json = serde_json::to_string(&flow).unwrap();
+        assert_eq!(json, r#"{"time":"2024-03-01","amount":1.0,"kind":2}"#);
+        assert_eq!(serde_json::from_str::<Cashflow>(&json).unwrap(), flow);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn cashflow_buffer_deserialization_validates_sizes() {
+        let json = r#"{"times":["2024-01-01"],"amounts":[1.0],"n_states":1,"n_kinds":2}"#;
+        assert!(serde_json::from_str::<CashflowBuffer>(json).is_err());
+        let json = r#"{"times":[],"amounts":[],"n_states":1,"n_kinds":1}"#;
+        assert!(serde_json::from_str::<CashflowBuffer>(json).is_err());
+    }
 }
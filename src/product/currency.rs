@@ -0,0 +1,287 @@
+use std::fmt;
+use std::ops::Neg;
+
+use super::{Amount, CashflowBuffer, CashflowBufferError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyError;
+
+impl fmt::Display for CurrencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("currency codes must be three upper-case ASCII letters")
+    }
+}
+
+impl std::error::Error for CurrencyError {}
+
+/// ISO 4217 alphabetic currency code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const USD: Self = Self(*b"USD");
+    pub const EUR: Self = Self(*b"EUR");
+    pub const GBP: Self = Self(*b"GBP");
+    pub const JPY: Self = Self(*b"JPY");
+    pub const CHF: Self = Self(*b"CHF");
+    pub const CAD: Self = Self(*b"CAD");
+
+    /// Parses a three-letter upper-case code such as `"USD"`.
+    pub fn new(code: &str) -> Result<Self, CurrencyError> {
+        let bytes: [u8; 3] = code.as_bytes().try_into().map_err(|_| CurrencyError)?;
+        if !bytes.iter().all(u8::is_ascii_uppercase) {
+            return Err(CurrencyError);
+        }
+        Ok(Self(bytes))
+    }
+
+    pub fn code(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("currency codes are ASCII")
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl From<Currency> for String {
+    fn from(currency: Currency) -> Self {
+        currency.code().to_owned()
+    }
+}
+
+impl TryFrom<String> for Currency {
+    type Error = CurrencyError;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Self::new(&code)
+    }
+}
+
+/// Arithmetic was attempted between amounts in different currencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurrencyMismatch {
+    pub left: Currency,
+    pub right: Currency,
+}
+
+/// Amount tagged with its currency.
+///
+/// There are no `Add`/`Sub` operators: combining amounts goes through the
+/// `checked_*` methods, which reject mixed currencies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Money {
+    amount: Amount,
+    currency: Currency,
+}
+
+impl Money {
+    pub const fn new(amount: Amount, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub const fn zero(currency: Currency) -> Self {
+        Self::new(Amount::zero(), currency)
+    }
+
+    pub const fn amount(self) -> Amount {
+        self.amount
+    }
+
+    pub const fn currency(self) -> Currency {
+        self.currency
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Result<Self, CurrencyMismatch> {
+        self.ensure_same_currency(rhs)?;
+        Ok(Self::new(self.amount + rhs.amount, self.currency))
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, CurrencyMismatch> {
+        self.ensure_same_currency(rhs)?;
+        Ok(Self::new(self.amount - rhs.amount, self.currency))
+    }
+
+    fn ensure_same_currency(self, rhs: Self) -> Result<(), CurrencyMismatch> {
+        if self.currency != rhs.currency {
+            return Err(CurrencyMismatch {
+                left: self.currency,
+                right: rhs.currency,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Neg for Money {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.amount, self.currency)
+    }
+}
+
+/// Cashflow buffer recording the currency of each cashflow kind.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "CurrencyCashflowBufferParts"))]
+pub struct CurrencyCashflowBuffer {
+    buffer: CashflowBuffer,
+    currencies: Vec<Currency>,
+}
+
+impl CurrencyCashflowBuffer {
+    /// Wraps `buffer`; `currencies` must hold one entry per cashflow kind.
+    pub fn new(
+        buffer: CashflowBuffer,
+        currencies: Vec<Currency>,
+    ) -> Result<Self, CashflowBufferError> {
+        if currencies.len() != buffer.n_kinds() {
+            return Err(CashflowBufferError);
+        }
+        Ok(Self { buffer, currencies })
+    }
+
+    pub fn buffer(&self) -> &CashflowBuffer {
+        &self.buffer
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut CashflowBuffer {
+        &mut self.buffer
+    }
+
+    pub fn currencies(&self) -> &[Currency] {
+        &self.currencies
+    }
+
+    pub fn currency(&self, kind: usize) -> Currency {
+        self.currencies[kind]
+    }
+
+    pub fn money(&self, state: usize, kind: usize, step: usize) -> Money {
+        Money::new(self.buffer.amount(state, kind, step), self.currencies[kind])
+    }
+
+    /// Adds `money` to a cell, rejecting it if the kind uses another currency.
+    pub fn add_money(
+        &mut self,
+        state: usize,
+        kind: usize,
+        step: usize,
+        money: Money,
+    ) -> Result<(), CurrencyMismatch> {
+        let current = self.money(state, kind, step).checked_add(money)?;
+        *self.buffer.amount_mut(state, kind, step) = current.amount();
+        Ok(())
+    }
+
+    pub fn into_parts(self) -> (CashflowBuffer, Vec<Currency>) {
+        (self.buffer, self.currencies)
+    }
+}
+
+/// Unvalidated wire form of [`CurrencyCashflowBuffer`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CurrencyCashflowBufferParts {
+    buffer: CashflowBuffer,
+    currencies: Vec<Currency>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CurrencyCashflowBufferParts> for CurrencyCashflowBuffer {
+    type Error = CashflowBufferError;
+
+    fn try_from(parts: CurrencyCashflowBufferParts) -> Result<Self, Self::Error> {
+        Self::new(parts.buffer, parts.currencies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Date, DateError};
+
+    #[test]
+    fn currency_codes_are_validated() {
+        assert_eq!(Currency::new("EUR"), Ok(Currency::EUR));
+        assert_eq!(Currency::USD.code(), "USD");
+        assert_eq!(Currency::GBP.to_string(), "GBP");
+        assert!(Currency::new("usd").is_err());
+        assert!(Currency::new("USDX").is_err());
+        assert!(Currency::new("U$D").is_err());
+    }
+
+    #[test]
+    fn money_rejects_mixed_currency_arithmetic() {
+        let usd = Money::new(Amount::from_f64(10.0), Currency::USD);
+        let eur = Money::new(Amount::from_f64(5.0), Currency::EUR);
+
+        assert_eq!(
+            usd.checked_add(usd),
+            Ok(Money::new(Amount::from_f64(20.0), Currency::USD))
+        );
+        assert_eq!(usd.checked_sub(usd), Ok(Money::zero(Currency::USD)));
+        assert_eq!(
+            usd.checked_add(eur),
+            Err(CurrencyMismatch {
+                left: Currency::USD,
+                right: Currency::EUR,
+            })
+        );
+        assert!(usd.checked_sub(eur).is_err());
+        assert_eq!((-eur).amount(), Amount::from_f64(-5.0));
+    }
+
+    #[test]
+    fn currency_buffer_tracks_currency_per_kind() -> Result<(), DateError> {
+        let times = vec![Date::new(2024, 1, 1)?, Date::new(2024, 2, 1)?];
+        let buffer = CashflowBuffer::new(1, 2, times).unwrap();
+        assert!(CurrencyCashflowBuffer::new(buffer.clone(), vec![Currency::USD]).is_err());
+
+        let mut buffer =
+            CurrencyCashflowBuffer::new(buffer, vec![Currency::USD, Currency::EUR]).unwrap();
+        buffer
+            .add_money(0, 1, 1, Money::new(Amount::from_f64(3.0), Currency::EUR))
+            .unwrap();
+        assert_eq!(
+            buffer.money(0, 1, 1),
+            Money::new(Amount::from_f64(3.0), Currency::EUR)
+        );
+        assert!(
+            buffer
+                .add_money(0, 0, 0, Money::new(Amount::from_f64(1.0), Currency::EUR))
+                .is_err()
+        );
+        assert_eq!(buffer.buffer().amount(0, 0, 0), Amount::zero());
+        assert_eq!(buffer.currency(0), Currency::USD);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn currency_types_round_trip_through_serde() -> Result<(), DateError> {
+        let money = Money::new(Amount::from_f64(2.5), Currency::EUR);
+        let json = serde_json::to_string(&money).unwrap();
+        assert_eq!(json, r#"{"amount":2.5,"currency":"EUR"}"#);
+        assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        assert!(serde_json::from_str::<Currency>(r#""eur""#).is_err());
+
+        let times = vec![Date::new(2024, 1, 1)?];
+        let buffer = CashflowBuffer::new(1, 2, times).unwrap();
+        let buffer =
+            CurrencyCashflowBuffer::new(buffer, vec![Currency::USD, Currency::EUR]).unwrap();
+        let json = serde_json::to_string(&buffer).unwrap();
+        let back: CurrencyCashflowBuffer = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.currencies(), buffer.currencies());
+
+        let short = json.replace(r#","EUR""#, "");
+        assert!(serde_json::from_str::<CurrencyCashflowBuffer>(&short).is_err());
+        Ok(())
+    }
+}
@@ -1,19 +1,141 @@
+use std::fmt;
+
+use crate::Frequency;
 use crate::rng::RngCore;
 
-use super::{Amount, ProductState, RequiredDataBuffer, RequiredDataLayout};
+use super::{Amount, CashflowKindId, ProductState, RequiredDataBuffer, RequiredDataLayout};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ProductDefinitionError;
 
-/// Fixed definition of a product's dimensions and required data.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl fmt::Display for ProductDefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid product definition")
+    }
+}
+
+impl std::error::Error for ProductDefinitionError {}
+
+/// Direction of a cashflow from the insurer's point of view.
+///
+/// Buffers hold amounts as positive magnitudes; the direction supplies the sign
+/// when cashflows are netted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlowDirection {
+    Inflow,
+    Outflow,
+}
+
+impl FlowDirection {
+    /// `+1.0` for inflows and `-1.0` for outflows.
+    pub const fn sign(self) -> f64 {
+        match self {
+            Self::Inflow => 1.0,
+            Self::Outflow => -1.0,
+        }
+    }
+
+    /// Returns `amount` signed from the insurer's point of view.
+    pub fn signed(self, amount: Amount) -> Amount {
+        amount * self.sign()
+    }
+}
+
+/// Accounting category of a cashflow kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CashflowCategory {
+    Premium,
+    Benefit,
+    Expense,
+    Commission,
+    Reinsurance,
+    Other,
+}
+
+/// When within a projection step a cashflow kind is paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CashflowTiming {
+    /// On the step date (in advance).
+    #[default]
+    StartOfStep,
+    /// On the next step date (in arrears).
+    EndOfStep,
+}
+
+/// Name, sign convention, accounting category and timing of a cashflow kind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KindMetadata {
+    pub name: String,
+    pub direction: FlowDirection,
+    pub category: CashflowCategory,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub timing: CashflowTiming,
+    /// Movement within an account value, such as a charge or credited
+    /// interest, rather than a payment. Its direction is that of the account
+    /// movement; cash totals leave it out, since the payments out of the
+    /// account already include it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub memo: bool,
+}
+
+impl KindMetadata {
+    pub fn new(
+        name: impl Into<String>,
+        direction: FlowDirection,
+        category: CashflowCategory,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            direction,
+            category,
+            timing: CashflowTiming::StartOfStep,
+            memo: false,
+        }
+    }
+
+    pub fn with_timing(mut self, timing: CashflowTiming) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn with_memo(mut self, memo: bool) -> Self {
+        self.memo = memo;
+        self
+    }
+
+    /// `amount` signed as it counts towards cash totals: zero for memo kinds.
+    pub fn cash(&self, amount: Amount) -> Amount {
+        if self.memo {
+            Amount::zero()
+        } else {
+            self.direction.signed(amount)
+        }
+    }
+}
+
+/// Fixed definition of a product's dimensions, labels and required data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ProductDefinitionParts"))]
 pub struct ProductDefinition {
     pub n_states: usize,
     pub n_kinds: usize,
     pub required_data: RequiredDataLayout,
+    states: Vec<String>,
+    kinds: Vec<KindMetadata>,
 }
 
 impl ProductDefinition {
+    /// Creates a definition with placeholder labels (`state_0`, `kind_0`, ...).
+    ///
+    /// Placeholder kinds are outflows in [`CashflowCategory::Other`].
     pub fn new(
         n_states: usize,
         n_kinds: usize,
@@ -26,8 +148,105 @@ impl ProductDefinition {
             n_states,
             n_kinds,
             required_data,
+            states: (0..n_states).map(|i| format!("state_{i}")).collect(),
+            kinds: (0..n_kinds)
+                .map(|i| {
+                    KindMetadata::new(
+                        format!("kind_{i}"),
+                        FlowDirection::Outflow,
+                        CashflowCategory::Other,
+                    )
+                })
+                .collect(),
         })
     }
+
+    /// Creates a definition whose dimensions follow the supplied labels.
+    ///
+    /// State and kind names must be non-empty and unique.
+    pub fn named(
+        states: Vec<String>,
+        kinds: Vec<KindMetadata>,
+        required_data: RequiredDataLayout,
+    ) -> Result<Self, ProductDefinitionError> {
+        if !unique_names(states.iter().map(String::as_str))
+            || !unique_names(kinds.iter().map(|k| k.name.as_str()))
+        {
+            return Err(ProductDefinitionError);
+        }
+        let mut def = Self::new(states.len(), kinds.len(), required_data)?;
+        def.states = states;
+        def.kinds = kinds;
+        Ok(def)
+    }
+
+    pub fn state_names(&self) -> &[String] {
+        &self.states
+    }
+
+    pub fn state_name(&self, state: usize) -> &str {
+        &self.states[state]
+    }
+
+    pub fn state_id(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|s| s == name)
+    }
+
+    pub fn kinds(&self) -> &[KindMetadata] {
+        &self.kinds
+    }
+
+    pub fn kind(&self, kind: CashflowKindId) -> &KindMetadata {
+        &self.kinds[kind.0]
+    }
+
+    pub fn kind_id(&self, name: &str) -> Option<CashflowKindId> {
+        self.kinds
+            .iter()
+            .position(|k| k.name == name)
+            .map(CashflowKindId)
+    }
+
+    /// Kinds belonging to `category`, in definition order.
+    pub fn kinds_in(
+        &self,
+        category: CashflowCategory,
+    ) -> impl Iterator<Item = CashflowKindId> + '_ {
+        self.kinds
+            .iter()
+            .enumerate()
+            .filter(move |(_, k)| k.category == category)
+            .map(|(i, _)| CashflowKindId(i))
+    }
+}
+
+fn unique_names<'a>(mut names: impl Iterator<Item = &'a str>) -> bool {
+    let mut seen = std::collections::HashSet::new();
+    names.all(|name| !name.is_empty() && seen.insert(name))
+}
+
+/// Unvalidated wire form of [`ProductDefinition`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct ProductDefinitionParts {
+    n_states: usize,
+    n_kinds: usize,
+    required_data: RequiredDataLayout,
+    states: Vec<String>,
+    kinds: Vec<KindMetadata>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<ProductDefinitionParts> for ProductDefinition {
+    type Error = ProductDefinitionError;
+
+    fn try_from(parts: ProductDefinitionParts) -> Result<Self, Self::Error> {
+        let def = Self::named(parts.states, parts.kinds, parts.required_data)?;
+        if def.n_states != parts.n_states || def.n_kinds != parts.n_kinds {
+            return Err(ProductDefinitionError);
+        }
+        Ok(def)
+    }
 }
 
 /// Product interface describing cashflow kinds, states, and required data.
@@ -74,6 +293,44 @@ pub trait Product {
         data: &RequiredDataBuffer,
         rng: &mut dyn RngCore,
     ) -> ProductState;
+
+    /// Writes the probabilities of moving from `state` to each state over the
+    /// step into `out` (length `definition().n_states`).
+    ///
+    /// Products that support expected-value projection return `true`; the
+    /// default returns `false`, leaving only simulation through `next_state`.
+    fn transition_probabilities(
+        &self,
+        _time_index: usize,
+        _state: &ProductState,
+        _data: &RequiredDataBuffer,
+        _out: &mut [f64],
+    ) -> bool {
+        false
+    }
+
+    /// Rolls forward everything in `state` except `state_id` over the step,
+    /// such as an account value.
+    ///
+    /// The expected-value model applies this to the state it generated the
+    /// step's required data for and carries the result into every state at the
+    /// next step. The default returns `state` unchanged.
+    fn advance_state(
+        &self,
+        _time_index: usize,
+        state: &ProductState,
+        _data: &RequiredDataBuffer,
+    ) -> ProductState {
+        *state
+    }
+
+    /// Step length the product's rates and amounts are expressed for.
+    ///
+    /// Models reject a configuration with any other frequency. The default,
+    /// `None`, is for products that do not depend on the step length.
+    fn frequency(&self) -> Option<Frequency> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -84,17 +341,115 @@ mod tests {
     #[test]
     fn product_definition_rejects_invalid_shapes() {
         let layout = RequiredDataLayout::new(1, 1).unwrap();
-        assert!(ProductDefinition::new(0, 1, layout).is_err());
+        assert!(ProductDefinition::new(0, 1, layout.clone()).is_err());
         assert!(ProductDefinition::new(1, 0, layout).is_err());
     }
 
     #[test]
     fn product_definition_accepts_valid_shapes() -> Result<(), RequiredDataLayoutError> {
         let layout = RequiredDataLayout::new(1, 2)?;
-        let def = ProductDefinition::new(2, 3, layout).unwrap();
+        let def = ProductDefinition::new(2, 3, layout.clone()).unwrap();
         assert_eq!(def.n_states, 2);
         assert_eq!(def.n_kinds, 3);
         assert_eq!(def.required_data, layout);
         Ok(())
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn product_definition_deserialization_validates_shape() {
+        let layout = RequiredDataLayout::new(1, 2).unwrap();
+        let def = ProductDefinition::new(2, 3, layout).unwrap();
+        let json = serde_json::to_string(&def).unwrap();
+        assert!(json.starts_with(
+            r#"{"n_states":2,"n_kinds":3,"required_data":{"policy_scalars":1,"state_vectors":2},"states":["state_0","state_1"],"kinds":[{"name":"kind_0","direction":"Outflow","category":"Other","timing":"StartOfStep"}"#
+        ));
+        assert_eq!(
+            serde_json::from_str::<ProductDefinition>(&json).unwrap(),
+            def
+        );
+
+        let invalid = json.replace(r#""n_states":2"#, r#""n_states":3"#);
+        assert!(serde_json::from_str::<ProductDefinition>(&invalid).is_err());
+        let duplicate = json.replace("state_1", "state_0");
+        assert!(serde_json::from_str::<ProductDefinition>(&duplicate).is_err());
+    }
+
+    fn labelled() -> ProductDefinition {
+        let layout = RequiredDataLayout::new(1, 0).unwrap();
+        ProductDefinition::named(
+            vec!["active".into(), "dead".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit),
+                KindMetadata::new(
+                    "surrender",
+                    FlowDirection::Outflow,
+                    CashflowCategory::Benefit,
+                ),
+                KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+            ],
+            layout,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn product_definition_looks_up_labels() {
+        let def = labelled();
+        assert_eq!(def.n_states, 2);
+        assert_eq!(def.n_kinds, 4);
+        assert_eq!(def.state_id("dead"), Some(1));
+        assert_eq!(def.state_name(0), "active");
+        assert_eq!(def.state_id("lapsed"), None);
+        assert_eq!(def.kind_id("death"), Some(CashflowKindId(1)));
+        assert_eq!(def.kind_id("claims"), None);
+        assert_eq!(def.kind(CashflowKindId(0)).direction, FlowDirection::Inflow);
+        let benefits: Vec<_> = def.kinds_in(CashflowCategory::Benefit).collect();
+        assert_eq!(benefits, vec![CashflowKindId(1), CashflowKindId(2)]);
+        assert_eq!(def.kinds_in(CashflowCategory::Commission).count(), 0);
+    }
+
+    #[test]
+    fn product_definition_rejects_duplicate_or_empty_labels() {
+        let layout = RequiredDataLayout::new(1, 0).unwrap();
+        let kind = KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium);
+        assert!(
+            ProductDefinition::named(
+                vec!["a".into()],
+                vec![kind.clone(), kind.clone()],
+                layout.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            ProductDefinition::named(
+                vec!["a".into(), "a".into()],
+                vec![kind.clone()],
+                layout.clone()
+            )
+            .is_err()
+        );
+        assert!(
+            ProductDefinition::named(vec![String::new()], vec![kind.clone()], layout.clone())
+                .is_err()
+        );
+        assert!(ProductDefinition::named(Vec::new(), vec![kind], layout).is_err());
+    }
+
+    #[test]
+    fn product_definition_defaults_to_placeholder_labels() {
+        let layout = RequiredDataLayout::new(1, 0).unwrap();
+        let def = ProductDefinition::new(2, 1, layout).unwrap();
+        assert_eq!(def.state_names(), &["state_0", "state_1"]);
+        assert_eq!(def.kinds()[0].name, "kind_0");
+        assert_eq!(def.kinds()[0].category, CashflowCategory::Other);
+    }
+
+    #[test]
+    fn flow_direction_signs_amounts() {
+        let amount = Amount::from_f64(10.0);
+        assert_eq!(FlowDirection::Inflow.signed(amount), amount);
+        assert_eq!(FlowDirection::Outflow.signed(amount), -amount);
+    }
 }
@@ -0,0 +1,621 @@
+//! Exact fixed-point amounts for the ledger side of a model.
+//!
+//! Projections, buffers and valuations all run on [`Amount`]; there is no
+//! switch that makes them compute in [`FixedAmount`]. Results that must tie
+//! to a ledger are converted once with [`FixedAmount::from_amount`] under an
+//! explicit [`RoundingMode`] and reconciled, summed or rounded to cents
+//! exactly from there.
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use super::Amount;
+
+/// Number of micro-units in one currency unit.
+const SCALE: i128 = 1_000_000;
+
+/// Decimal places held by [`FixedAmount`].
+pub const FIXED_AMOUNT_DECIMALS: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedAmountError;
+
+impl fmt::Display for FixedAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("value is not finite or does not fit a fixed-point amount")
+    }
+}
+
+impl std::error::Error for FixedAmountError {}
+
+/// Rounding applied when a value does not fit the target precision.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RoundingMode {
+    /// Ties round to the even neighbour (banker's rounding).
+    #[default]
+    HalfEven,
+    /// Ties round away from zero (commercial rounding).
+    HalfUp,
+    /// Discards the excess digits.
+    TowardZero,
+}
+
+/// Exact fixed-point amount stored as `i128` micro-units.
+///
+/// Mirrors the [`Amount`] API (`zero`, `from_cents`, `from_f64`, `value`,
+/// `Add`/`Sub`/`Neg`, scalar `Mul`/`Div` by `f64`, their assigning forms and
+/// `Sum`) so ledger code reads the same over either type; the projection
+/// types themselves hold `Amount` only. Scalar products round half-even to
+/// micro precision. Operators panic on overflow in every build profile; use
+/// the `checked_*` methods to handle it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct FixedAmount(i128);
+
+impl FixedAmount {
+    pub const fn zero() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Self(cents as i128 * (SCALE / 100))
+    }
+
+    pub const fn from_micros(micros: i128) -> Self {
+        Self(micros)
+    }
+
+    /// Converts from `f64` using [`RoundingMode::HalfEven`] at micro precision.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not finite or is outside the representable range.
+    pub fn from_f64(value: f64) -> Self {
+        Self::try_from_f64(value, RoundingMode::HalfEven)
+            .expect("FixedAmount::from_f64 requires a finite, representable value")
+    }
+
+    /// Converts from `f64`, rounding the exact binary value to micro
+    /// precision per `mode`.
+    ///
+    /// Fails if `value` is not finite or is outside the representable range.
+    pub fn try_from_f64(value: f64, mode: RoundingMode) -> Result<Self, FixedAmountError> {
+        let (negative, mantissa, exponent) = decompose(value).ok_or(FixedAmountError)?;
+        scale(SCALE, negative, mantissa, exponent, 1, mode)
+            .map(Self)
+            .ok_or(FixedAmountError)
+    }
+
+    pub fn from_amount(amount: Amount, mode: RoundingMode) -> Result<Self, FixedAmountError> {
+        Self::try_from_f64(amount.value(), mode)
+    }
+
+    pub const fn micros(self) -> i128 {
+        self.0
+    }
+
+    pub fn value(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    /// Rounds to `decimals` decimal places (at most [`FIXED_AMOUNT_DECIMALS`]).
+    pub fn round_to(self, decimals: u32, mode: RoundingMode) -> Self {
+        let decimals = decimals.min(FIXED_AMOUNT_DECIMALS);
+        let unit = 10i128.pow(FIXED_AMOUNT_DECIMALS - decimals);
+        Self(div_round(self.0, unit, mode) * unit)
+    }
+
+    pub fn round_to_cents(self, mode: RoundingMode) -> Self {
+        self.round_to(2, mode)
+    }
+
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_add(rhs.0) {
+            Some(v) => Some(Self(v)),
+            None => None,
+        }
+    }
+
+    pub const fn checked_sub(self, rhs: Self) -> Option<Self> {
+        match self.0.checked_sub(rhs.0) {
+            Some(v) => Some(Self(v)),
+            None => None,
+        }
+    }
+
+    /// Multiplies by `factor`, rounding the exact product to micro precision.
+    ///
+    /// Returns `None` if `factor` is not finite or the product overflows.
+    pub fn checked_mul_f64(self, factor: f64, mode: RoundingMode) -> Option<Self> {
+        let (negative, mantissa, exponent) = decompose(factor)?;
+        scale(self.0, negative, mantissa, exponent, 1, mode).map(Self)
+    }
+
+    /// Divides by `divisor`, rounding the exact quotient to micro precision.
+    ///
+    /// Returns `None` if `divisor` is zero or not finite or the quotient
+    /// overflows.
+    pub fn checked_div_f64(self, divisor: f64, mode: RoundingMode) -> Option<Self> {
+        let (negative, mantissa, exponent) = decompose(divisor)?;
+        if mantissa == 0 {
+            return None;
+        }
+        scale(self.0, negative, 1, -exponent, mantissa, mode).map(Self)
+    }
+
+    pub const fn checked_neg(self) -> Option<Self> {
+        match self.0.checked_neg() {
+            Some(v) => Some(Self(v)),
+            None => None,
+        }
+    }
+}
+
+impl From<FixedAmount> for Amount {
+    fn from(value: FixedAmount) -> Self {
+        Amount::from_f64(value.value())
+    }
+}
+
+impl Add for FixedAmount {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs)
+            .expect("FixedAmount addition overflowed")
+    }
+}
+
+impl Sub for FixedAmount {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs)
+            .expect("FixedAmount subtraction overflowed")
+    }
+}
+
+impl AddAssign for FixedAmount {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for FixedAmount {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for FixedAmount {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self.checked_neg().expect("FixedAmount negation overflowed")
+    }
+}
+
+impl Mul<f64> for FixedAmount {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        self.checked_mul_f64(rhs, RoundingMode::HalfEven)
+            .expect("FixedAmount multiplication by a non-finite factor or overflow")
+    }
+}
+
+impl Mul<FixedAmount> for f64 {
+    type Output = FixedAmount;
+
+    fn mul(self, rhs: FixedAmount) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Div<f64> for FixedAmount {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        self.checked_div_f64(rhs, RoundingMode::HalfEven)
+            .expect("FixedAmount division by zero, a non-finite divisor or overflow")
+    }
+}
+
+impl MulAssign<f64> for FixedAmount {
+    fn mul_assign(&mut self, rhs: f64) {
+        *self = *self * rhs;
+    }
+}
+
+impl DivAssign<f64> for FixedAmount {
+    fn div_assign(&mut self, rhs: f64) {
+        *self = *self / rhs;
+    }
+}
+
+impl Sum for FixedAmount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, v| acc + v)
+    }
+}
+
+impl<'a> Sum<&'a FixedAmount> for FixedAmount {
+    fn sum<I: Iterator<Item = &'a FixedAmount>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Sign, odd (or zero) mantissa and exponent of a finite `value`, so that
+/// `value = ±mantissa * 2^exponent`.
+fn decompose(value: f64) -> Option<(bool, u64, i32)> {
+    if !value.is_finite() {
+        return None;
+    }
+    let bits = value.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    let (mantissa, exponent) = match biased {
+        0 => (fraction, -1074),
+        _ => (fraction | 1 << 52, biased - 1075),
+    };
+    if mantissa == 0 {
+        return Some((false, 0, 0));
+    }
+    let zeros = mantissa.trailing_zeros();
+    Some((value < 0.0, mantissa >> zeros, exponent + zeros as i32))
+}
+
+/// `micros * numerator * 2^exponent / denominator`, negated if `negative`
+/// and rounded per `mode`, computed exactly in 256 bits.
+fn scale(
+    micros: i128,
+    negative: bool,
+    numerator: u64,
+    exponent: i32,
+    denominator: u64,
+    mode: RoundingMode,
+) -> Option<i128> {
+    if micros == 0 || numerator == 0 {
+        return Some(0);
+    }
+    let mut product = Wide::product(micros.unsigned_abs(), numerator);
+    if exponent > 0 {
+        product = product.shl(exponent as u32)?;
+    }
+    let (quotient, remainder) = product.div_rem(denominator);
+    let shift = exponent.min(0).unsigned_abs();
+    let magnitude = quotient.shr(shift);
+    // Fraction dropped by the shift and the division, against one half.
+    let (above, half) = if shift == 0 {
+        let twice = 2 * u128::from(remainder);
+        (
+            twice > u128::from(denominator),
+            twice == u128::from(denominator),
+        )
+    } else {
+        let half_bit = quotient.bit(shift - 1);
+        let below = quotient.any_below(shift - 1) || remainder > 0;
+        (half_bit && below, half_bit && !below)
+    };
+    let round_away = match mode {
+        RoundingMode::TowardZero => false,
+        RoundingMode::HalfUp => above || half,
+        RoundingMode::HalfEven => above || (half && magnitude.lo % 2 == 1),
+    };
+    if magnitude.hi != 0 {
+        return None;
+    }
+    let magnitude = magnitude.lo.checked_add(u128::from(round_away))?;
+    let negative = negative != (micros < 0);
+    if negative {
+        0i128.checked_sub_unsigned(magnitude)
+    } else {
+        i128::try_from(magnitude).ok()
+    }
+}
+
+/// Unsigned 256-bit integer for exact scaling.
+#[derive(Clone, Copy)]
+struct Wide {
+    hi: u128,
+    lo: u128,
+}
+
+impl Wide {
+    fn product(a: u128, b: u64) -> Self {
+        let b = u128::from(b);
+        let low = (a & u128::from(u64::MAX)) * b;
+        let high = (a >> 64) * b;
+        let lo = low.wrapping_add(high << 64);
+        Self {
+            hi: (high >> 64) + u128::from(lo < low),
+            lo,
+        }
+    }
+
+    fn shl(self, shift: u32) -> Option<Self> {
+        let zeros = match self.hi {
+            0 => 128 + self.lo.leading_zeros(),
+            hi => hi.leading_zeros(),
+        };
+        if shift > zeros {
+            return None;
+        }
+        Some(match shift {
+            0 => self,
+            1..128 => Self {
+                hi: self.hi << shift | self.lo >> (128 - shift),
+                lo: self.lo << shift,
+            },
+            _ => Self {
+                hi: self.lo << (shift - 128),
+                lo: 0,
+            },
+        })
+    }
+
+    fn shr(self, shift: u32) -> Self {
+        match shift {
+            0 => self,
+            1..128 => Self {
+                hi: self.hi >> shift,
+                lo: self.lo >> shift | self.hi << (128 - shift),
+            },
+            128..256 => Self {
+                hi: 0,
+                lo: self.hi >> (shift - 128),
+            },
+            _ => Self { hi: 0, lo: 0 },
+        }
+    }
+
+    fn bit(self, index: u32) -> bool {
+        match index {
+            0..128 => (self.lo >> index) & 1 == 1,
+            128..256 => (self.hi >> (index - 128)) & 1 == 1,
+            _ => false,
+        }
+    }
+
+    /// Whether any bit below `index` is set.
+    fn any_below(self, index: u32) -> bool {
+        let mask = |bits: u32| match bits {
+            0 => 0,
+            128.. => u128::MAX,
+            _ => (1 << bits) - 1,
+        };
+        self.lo & mask(index) != 0 || self.hi & mask(index.saturating_sub(128)) != 0
+    }
+
+    fn div_rem(self, divisor: u64) -> (Self, u64) {
+        let divisor = u128::from(divisor);
+        let low = u128::from(u64::MAX);
+        let mut limbs = [self.hi >> 64, self.hi & low, self.lo >> 64, self.lo & low];
+        let mut remainder = 0;
+        for limb in &mut limbs {
+            let current = remainder << 64 | *limb;
+            *limb = current / divisor;
+            remainder = current % divisor;
+        }
+        let quotient = Self {
+            hi: limbs[0] << 64 | limbs[1],
+            lo: limbs[2] << 64 | limbs[3],
+        };
+        (quotient, remainder as u64)
+    }
+}
+
+/// Integer division of `n` by a positive `d`, rounded per `mode`.
+fn div_round(n: i128, d: i128, mode: RoundingMode) -> i128 {
+    let q = n / d;
+    let r = n % d;
+    if r == 0 {
+        return q;
+    }
+    let step = n.signum();
+    let twice = r.abs() * 2;
+    let round_away = match mode {
+        RoundingMode::TowardZero => false,
+        RoundingMode::HalfUp => twice >= d,
+        RoundingMode::HalfEven => twice > d || (twice == d && q % 2 != 0),
+    };
+    if round_away { q + step } else { q }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_amount_arithmetic_is_exact() {
+        let dime = FixedAmount::from_cents(10);
+        let mut total = FixedAmount::zero();
+        for _ in 0..3 {
+            total += dime;
+        }
+        assert_eq!(total, FixedAmount::from_cents(30));
+        assert_ne!(
+            Amount::from_cents(10) + Amount::from_cents(10) + Amount::from_cents(10),
+            Amount::from_cents(30)
+        );
+
+        total -= FixedAmount::from_cents(5);
+        assert_eq!(total.micros(), 250_000);
+        assert_eq!(-total, FixedAmount::from_f64(-0.25));
+        assert_eq!(total - dime + dime, total);
+        assert_eq!(total.value(), 0.25);
+    }
+
+    #[test]
+    fn fixed_amount_rounds_half_even_and_half_up() {
+        let a = FixedAmount::from_f64(2.345);
+        assert_eq!(
+            a.round_to_cents(RoundingMode::HalfEven),
+            FixedAmount::from_cents(234)
+        );
+        assert_eq!(
+            a.round_to_cents(RoundingMode::HalfUp),
+            FixedAmount::from_cents(235)
+        );
+        assert_eq!(
+            a.round_to_cents(RoundingMode::TowardZero),
+            FixedAmount::from_cents(234)
+        );
+
+        let b = FixedAmount::from_f64(-2.355);
+        assert_eq!(
+            b.round_to_cents(RoundingMode::HalfEven),
+            FixedAmount::from_cents(-236)
+        );
+        assert_eq!(
+            b.round_to_cents(RoundingMode::HalfUp),
+            FixedAmount::from_cents(-236)
+        );
+
+        let c = FixedAmount::from_f64(1.004);
+        assert_eq!(
+            c.round_to_cents(RoundingMode::HalfUp),
+            FixedAmount::from_cents(100)
+        );
+        assert_eq!(c.round_to(6, RoundingMode::HalfUp), c);
+    }
+
+    #[test]
+    fn fixed_amount_from_f64_uses_rounding_mode() {
+        let convert = |value, mode| FixedAmount::try_from_f64(value, mode).unwrap().micros();
+        assert_eq!(convert(1.2345678, RoundingMode::HalfEven), 1_234_568);
+        assert_eq!(convert(1.2345678, RoundingMode::TowardZero), 1_234_567);
+        assert_eq!(convert(-1.2345678, RoundingMode::HalfUp), -1_234_568);
+        // No f64 is a decimal tie at micro precision; the nearest ones fall
+        // either side of the half, where scaling by 1e6 in floating point
+        // lands on 2.5 and 3.5 exactly.
+        assert_eq!(convert(0.000_002_5, RoundingMode::HalfEven), 3);
+        assert_eq!(convert(0.000_003_5, RoundingMode::HalfUp), 3);
+        assert_eq!(convert(0.000_000_5, RoundingMode::HalfUp), 0);
+        assert_eq!(convert(5e-324, RoundingMode::HalfUp), 0);
+        assert_eq!(
+            convert(1e30, RoundingMode::HalfEven),
+            1_000_000_000_000_000_019_884_624_838_656 * 1_000_000
+        );
+        assert!(FixedAmount::try_from_f64(f64::NAN, RoundingMode::HalfEven).is_err());
+        assert!(FixedAmount::try_from_f64(f64::MAX, RoundingMode::HalfEven).is_err());
+    }
+
+    #[test]
+    fn fixed_amount_checked_ops_report_overflow() {
+        let max = FixedAmount::from_micros(i128::MAX);
+        let min = FixedAmount::from_micros(i128::MIN);
+        assert!(max.checked_add(FixedAmount::from_micros(1)).is_none());
+        assert!(min.checked_sub(FixedAmount::from_micros(1)).is_none());
+        assert!(min.checked_neg().is_none());
+        assert_eq!(max.checked_sub(max), Some(FixedAmount::zero()));
+    }
+
+    #[test]
+    #[should_panic(expected = "overflowed")]
+    fn fixed_amount_add_panics_on_overflow() {
+        let _ = FixedAmount::from_micros(i128::MAX) + FixedAmount::from_micros(1);
+    }
+
+    #[test]
+    fn fixed_amount_converts_to_and_from_amount() {
+        let fixed = FixedAmount::from_amount(Amount::from_f64(12.5), RoundingMode::HalfEven);
+        assert_eq!(fixed, Ok(FixedAmount::from_cents(1_250)));
+        assert_eq!(
+            Amount::from(FixedAmount::from_cents(1_250)),
+            Amount::from_f64(12.5)
+        );
+    }
+
+    #[test]
+    fn fixed_amount_scales_and_sums() {
+        let amount = FixedAmount::from_cents(1_000);
+        assert_eq!(amount * 0.015, FixedAmount::from_cents(15));
+        assert_eq!(amount / 3.0, FixedAmount::from_micros(3_333_333));
+        let mut scaled = 2.0 * amount;
+        scaled /= 4.0;
+        scaled *= 3.0;
+        assert_eq!(scaled, FixedAmount::from_cents(1_500));
+        assert!(
+            FixedAmount::from_micros(i128::MAX)
+                .checked_mul_f64(2.0, RoundingMode::HalfEven)
+                .is_none()
+        );
+        assert_eq!(
+            FixedAmount::from_cents(100).checked_div_f64(0.0, RoundingMode::HalfEven),
+            None
+        );
+        assert_eq!(
+            FixedAmount::from_cents(100).checked_mul_f64(f64::NAN, RoundingMode::HalfEven),
+            None
+        );
+        let values = [FixedAmount::from_cents(10); 3];
+        assert_eq!(
+            values.iter().sum::<FixedAmount>(),
+            FixedAmount::from_cents(30)
+        );
+    }
+
+    #[test]
+    fn fixed_amount_scaling_is_exact_beyond_f64_precision() {
+        // 2^60 + 1 micro-units cannot be held in an f64.
+        let large = FixedAmount::from_micros((1 << 60) + 1);
+        assert_eq!(large * 2.0, FixedAmount::from_micros((1 << 61) + 2));
+        assert_eq!(large / 2.0, FixedAmount::from_micros(1 << 59));
+        assert_eq!(
+            large.checked_div_f64(2.0, RoundingMode::HalfUp),
+            Some(FixedAmount::from_micros((1 << 59) + 1))
+        );
+        assert_eq!(
+            (-large).checked_div_f64(2.0, RoundingMode::TowardZero),
+            Some(FixedAmount::from_micros(-(1 << 59)))
+        );
+        assert_eq!(large * 0.5, large / 2.0);
+        assert_eq!(
+            FixedAmount::from_micros(3 * (1 << 60)) / -3.0,
+            FixedAmount::from_micros(-(1 << 60))
+        );
+        // Ties at micro precision follow the rounding mode.
+        let five = FixedAmount::from_micros(5);
+        assert_eq!(five / 2.0, FixedAmount::from_micros(2));
+        assert_eq!(
+            five.checked_mul_f64(0.5, RoundingMode::HalfUp),
+            Some(FixedAmount::from_micros(3))
+        );
+        assert_eq!(
+            FixedAmount::from_micros(i128::MAX).checked_mul_f64(0.25, RoundingMode::TowardZero),
+            Some(FixedAmount::from_micros(i128::MAX >> 2))
+        );
+        assert_eq!(
+            FixedAmount::from_micros(i128::MIN).checked_mul_f64(1.0, RoundingMode::HalfEven),
+            Some(FixedAmount::from_micros(i128::MIN))
+        );
+        assert_eq!(five * 1e-300, FixedAmount::zero());
+        assert!(
+            five.checked_div_f64(1e-300, RoundingMode::HalfEven)
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn fixed_amount_division_by_zero_panics() {
+        let _ = FixedAmount::from_cents(100) / 0.0;
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn fixed_amount_serializes_as_micros() {
+        let amount = FixedAmount::from_cents(1_234);
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "12340000");
+        assert_eq!(serde_json::from_str::<FixedAmount>(&json).unwrap(), amount);
+        assert_eq!(
+            serde_json::to_string(&RoundingMode::HalfUp).unwrap(),
+            r#""HalfUp""#
+        );
+    }
+}
@@ -1,11 +1,22 @@
 pub mod cashflow;
+pub mod currency;
 pub mod definition;
+pub mod fixed;
 pub mod required;
 pub mod state;
 
-pub use cashflow::{Amount, Cashflow, CashflowBuffer, CashflowKindId};
-pub use definition::{Product, ProductDefinition};
-pub use required::{RequiredDataBuffer, RequiredDataLayout, RequiredDataLayoutError};
+pub use cashflow::{Amount, Cashflow, CashflowBuffer, CashflowBufferError, CashflowKindId};
+pub use currency::{Currency, CurrencyCashflowBuffer, CurrencyError, CurrencyMismatch, Money};
+pub use definition::{
+    CashflowCategory, CashflowTiming, FlowDirection, KindMetadata, Product, ProductDefinition,
+    ProductDefinitionError,
+};
+pub use fixed::{FIXED_AMOUNT_DECIMALS, FixedAmount, FixedAmountError, RoundingMode};
+pub use required::{
+    AmountField, CountField, FieldKind, FieldScope, FieldSpec, FieldType, FlagField, RateField,
+    RequiredDataBuffer, RequiredDataHistory, RequiredDataLayout, RequiredDataLayoutBuilder,
+    RequiredDataLayoutError, ScalarField, VectorField,
+};
 pub use state::ProductState;
 
 #[cfg(test)]
@@ -1,11 +1,189 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::Amount;
+use crate::Date;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RequiredDataLayoutError;
 
+impl fmt::Display for RequiredDataLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid required data layout")
+    }
+}
+
+impl std::error::Error for RequiredDataLayoutError {}
+
+/// Unit of a required data field, which fixes how its `f64` slot is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldType {
+    /// Probability or interest rate per step.
+    Rate,
+    /// Monetary amount.
+    Amount,
+    /// Non-negative whole number.
+    Count,
+    /// Boolean stored as `0.0` / `1.0`.
+    Flag,
+}
+
+/// Where a field lives in [`RequiredDataBuffer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FieldScope {
+    /// One value per policy.
+    PolicyScalar,
+    /// One value per state.
+    StateVector,
+}
+
+/// Name, unit and scope of a registered required data field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldSpec {
+    pub name: String,
+    pub field_type: FieldType,
+    pub scope: FieldScope,
+}
+
+/// Marker for a field unit, mapping the stored `f64` to a typed value.
+pub trait FieldKind {
+    const TYPE: FieldType;
+    type Value;
+
+    fn encode(value: Self::Value) -> f64;
+    fn decode(raw: f64) -> Self::Value;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RateField;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AmountField;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CountField;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlagField;
+
+impl FieldKind for RateField {
+    const TYPE: FieldType = FieldType::Rate;
+    type Value = f64;
+
+    fn encode(value: f64) -> f64 {
+        value
+    }
+
+    fn decode(raw: f64) -> f64 {
+        raw
+    }
+}
+
+impl FieldKind for AmountField {
+    const TYPE: FieldType = FieldType::Amount;
+    type Value = Amount;
+
+    fn encode(value: Amount) -> f64 {
+        value.value()
+    }
+
+    fn decode(raw: f64) -> Amount {
+        Amount::from_f64(raw)
+    }
+}
+
+impl FieldKind for CountField {
+    const TYPE: FieldType = FieldType::Count;
+    type Value = u64;
+
+    fn encode(value: u64) -> f64 {
+        value as f64
+    }
+
+    fn decode(raw: f64) -> u64 {
+        raw as u64
+    }
+}
+
+impl FieldKind for FlagField {
+    const TYPE: FieldType = FieldType::Flag;
+    type Value = bool;
+
+    fn encode(value: bool) -> f64 {
+        if value { 1.0 } else { 0.0 }
+    }
+
+    fn decode(raw: f64) -> bool {
+        raw != 0.0
+    }
+}
+
+/// Identity of a layout, shared by its clones and the handles it issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LayoutId(u64);
+
+impl LayoutId {
+    fn fresh() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Typed handle to a per-policy scalar field.
+///
+/// Handles are stamped with the layout they were obtained from and only work
+/// with buffers over that layout or its clones; the typed accessors panic on a
+/// handle from any other layout, even one with the same fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScalarField<K> {
+    layout: LayoutId,
+    index: usize,
+    kind: PhantomData<K>,
+}
+
+impl<K> ScalarField<K> {
+    pub const fn index(self) -> usize {
+        self.index
+    }
+}
+
+/// Typed handle to a per-state vector field, bound to its layout like
+/// [`ScalarField`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VectorField<K> {
+    layout: LayoutId,
+    index: usize,
+    kind: PhantomData<K>,
+}
+
+impl<K> VectorField<K> {
+    pub const fn index(self) -> usize {
+        self.index
+    }
+}
+
 /// Fixed required data dimensions for a product.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Layouts from [`RequiredDataLayout::new`] have anonymous slots addressed by
+/// index. Layouts from [`RequiredDataLayoutBuilder`] also carry a [`FieldSpec`]
+/// per slot, which typed handles are resolved against.
+///
+/// Equality compares dimensions and fields only; a deserialized layout equals
+/// its source but does not accept handles issued by it.
+#[derive(Debug, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RequiredDataLayoutParts"))]
 pub struct RequiredDataLayout {
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    id: LayoutId,
     policy_scalars: usize,
     state_vectors: usize,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    fields: Vec<FieldSpec>,
 }
 
 impl RequiredDataLayout {
@@ -17,11 +195,36 @@ impl RequiredDataLayout {
             return Err(RequiredDataLayoutError);
         }
         Ok(Self {
+            id: LayoutId::fresh(),
             policy_scalars,
             state_vectors,
+            fields: Vec::new(),
         })
     }
 
+    pub fn builder() -> RequiredDataLayoutBuilder {
+        RequiredDataLayoutBuilder::default()
+    }
+
+    /// Builds a layout from field specs, assigning slots in declaration order
+    /// within each scope.
+    pub fn from_fields(fields: Vec<FieldSpec>) -> Result<Self, RequiredDataLayoutError> {
+        let mut seen = std::collections::HashSet::new();
+        if !fields
+            .iter()
+            .all(|f| !f.name.is_empty() && seen.insert(f.name.as_str()))
+        {
+            return Err(RequiredDataLayoutError);
+        }
+        let count = |scope| fields.iter().filter(|f| f.scope == scope).count();
+        let mut layout = Self::new(
+            count(FieldScope::PolicyScalar),
+            count(FieldScope::StateVector),
+        )?;
+        layout.fields = fields;
+        Ok(layout)
+    }
+
     pub const fn policy_scalars(&self) -> usize {
         self.policy_scalars
     }
@@ -29,18 +232,204 @@ impl RequiredDataLayout {
     pub const fn state_vectors(&self) -> usize {
         self.state_vectors
     }
+
+    /// Field specs in declaration order; empty for anonymous layouts.
+    pub fn fields(&self) -> &[FieldSpec] {
+        &self.fields
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Resolves a named policy scalar, failing if it is missing or has another
+    /// unit.
+    pub fn scalar_field<K: FieldKind>(
+        &self,
+        name: &str,
+    ) -> Result<ScalarField<K>, RequiredDataLayoutError> {
+        self.resolve(name, FieldScope::PolicyScalar, K::TYPE)
+            .map(|index| ScalarField {
+                layout: self.id,
+                index,
+                kind: PhantomData,
+            })
+    }
+
+    /// Resolves a named state vector, failing if it is missing or has another
+    /// unit.
+    pub fn vector_field<K: FieldKind>(
+        &self,
+        name: &str,
+    ) -> Result<VectorField<K>, RequiredDataLayoutError> {
+        self.resolve(name, FieldScope::StateVector, K::TYPE)
+            .map(|index| VectorField {
+                layout: self.id,
+                index,
+                kind: PhantomData,
+            })
+    }
+
+    fn check(&self, layout: LayoutId) {
+        assert!(
+            self.id == layout,
+            "required data field handle from another layout"
+        );
+    }
+
+    fn resolve(
+        &self,
+        name: &str,
+        scope: FieldScope,
+        field_type: FieldType,
+    ) -> Result<usize, RequiredDataLayoutError> {
+        match self.field(name) {
+            Some(spec) if spec.scope == scope && spec.field_type == field_type => {
+                slot_of(&self.fields, name, scope).ok_or(RequiredDataLayoutError)
+            }
+            _ => Err(RequiredDataLayoutError),
+        }
+    }
+}
+
+impl PartialEq for RequiredDataLayout {
+    fn eq(&self, other: &Self) -> bool {
+        self.policy_scalars == other.policy_scalars
+            && self.state_vectors == other.state_vectors
+            && self.fields == other.fields
+    }
+}
+
+/// Registers named, typed fields and hands back handles to them.
+///
+/// Producers and consumers of required data may each register the fields they
+/// use. Registering a name again with the same unit and scope returns the
+/// existing handle; a conflicting registration makes [`build`] fail.
+///
+/// [`build`]: RequiredDataLayoutBuilder::build
+#[derive(Debug, Clone)]
+pub struct RequiredDataLayoutBuilder {
+    id: LayoutId,
+    fields: Vec<FieldSpec>,
+    conflict: bool,
+}
+
+impl Default for RequiredDataLayoutBuilder {
+    fn default() -> Self {
+        Self {
+            id: LayoutId::fresh(),
+            fields: Vec::new(),
+            conflict: false,
+        }
+    }
+}
+
+impl RequiredDataLayoutBuilder {
+    pub fn policy_scalar<K: FieldKind>(&mut self, name: &str) -> ScalarField<K> {
+        ScalarField {
+            layout: self.id,
+            index: self.register(name, FieldScope::PolicyScalar, K::TYPE),
+            kind: PhantomData,
+        }
+    }
+
+    pub fn state_vector<K: FieldKind>(&mut self, name: &str) -> VectorField<K> {
+        VectorField {
+            layout: self.id,
+            index: self.register(name, FieldScope::StateVector, K::TYPE),
+            kind: PhantomData,
+        }
+    }
+
+    /// Fails on conflicting registrations, empty names, or an empty layout.
+    ///
+    /// The layout accepts the handles this builder issued.
+    pub fn build(self) -> Result<RequiredDataLayout, RequiredDataLayoutError> {
+        if self.conflict {
+            return Err(RequiredDataLayoutError);
+        }
+        let mut layout = RequiredDataLayout::from_fields(self.fields)?;
+        layout.id = self.id;
+        Ok(layout)
+    }
+
+    fn register(&mut self, name: &str, scope: FieldScope, field_type: FieldType) -> usize {
+        if let Some(existing) = self.fields.iter().find(|f| f.name == name) {
+            if existing.scope != scope || existing.field_type != field_type {
+                // The handle is never usable: `build` reports the conflict.
+                self.conflict = true;
+                return 0;
+            }
+            return slot_of(&self.fields, name, scope).unwrap_or_default();
+        }
+        let index = self.fields.iter().filter(|f| f.scope == scope).count();
+        self.fields.push(FieldSpec {
+            name: name.to_owned(),
+            field_type,
+            scope,
+        });
+        index
+    }
+}
+
+/// Slot of `name` among the fields sharing `scope`.
+fn slot_of(fields: &[FieldSpec], name: &str, scope: FieldScope) -> Option<usize> {
+    fields
+        .iter()
+        .filter(|f| f.scope == scope)
+        .position(|f| f.name == name)
+}
+
+/// Unvalidated wire form of [`RequiredDataLayout`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RequiredDataLayoutParts {
+    policy_scalars: usize,
+    state_vectors: usize,
+    #[serde(default)]
+    fields: Vec<FieldSpec>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RequiredDataLayoutParts> for RequiredDataLayout {
+    type Error = RequiredDataLayoutError;
+
+    fn try_from(parts: RequiredDataLayoutParts) -> Result<Self, Self::Error> {
+        if parts.fields.is_empty() {
+            return Self::new(parts.policy_scalars, parts.state_vectors);
+        }
+        let layout = Self::from_fields(parts.fields)?;
+        if layout.policy_scalars != parts.policy_scalars
+            || layout.state_vectors != parts.state_vectors
+        {
+            return Err(RequiredDataLayoutError);
+        }
+        Ok(layout)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RequiredDataBufferError;
 
+impl fmt::Display for RequiredDataBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid required data buffer dimensions")
+    }
+}
+
+impl std::error::Error for RequiredDataBufferError {}
+
 /// SoA required data storage: per-policy scalars and per-state vectors.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RequiredDataBufferParts"))]
 pub struct RequiredDataBuffer {
     layout: RequiredDataLayout,
     n_states: usize,
     policy_scalars: Vec<f64>,
     state_vectors: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    history: Option<RequiredDataHistory>,
 }
 
 impl RequiredDataBuffer {
@@ -62,6 +451,7 @@ impl RequiredDataBuffer {
             n_states,
             policy_scalars: scalars,
             state_vectors: vectors,
+            history: None,
         })
     }
 
@@ -89,11 +479,45 @@ impl RequiredDataBuffer {
             n_states,
             policy_scalars,
             state_vectors,
+            history: None,
         })
     }
 
-    pub const fn layout(&self) -> RequiredDataLayout {
-        self.layout
+    /// Starts keeping a copy of every recorded step, aligned with `times`
+    /// (normally [`CashflowBuffer::times`]).
+    ///
+    /// Any previously recorded history is discarded.
+    ///
+    /// [`CashflowBuffer::times`]: super::CashflowBuffer::times
+    pub fn enable_history(&mut self, times: Vec<Date>) -> Result<(), RequiredDataBufferError> {
+        self.history = Some(RequiredDataHistory::new(
+            self.layout.clone(),
+            self.n_states,
+            times,
+        )?);
+        Ok(())
+    }
+
+    pub fn history(&self) -> Option<&RequiredDataHistory> {
+        self.history.as_ref()
+    }
+
+    pub fn take_history(&mut self) -> Option<RequiredDataHistory> {
+        self.history.take()
+    }
+
+    /// Copies the current values into the history at `step`.
+    ///
+    /// Models call this after generating each step's data; it does nothing when
+    /// history is disabled.
+    pub fn record(&mut self, step: usize) {
+        if let Some(history) = &mut self.history {
+            history.record_values(step, &self.policy_scalars, &self.state_vectors);
+        }
+    }
+
+    pub fn layout(&self) -> &RequiredDataLayout {
+        &self.layout
     }
 
     pub const fn n_states(&self) -> usize {
@@ -120,12 +544,183 @@ impl RequiredDataBuffer {
         &mut self.state_vectors[start..start + self.n_states]
     }
 
+    pub fn get<K: FieldKind>(&self, field: ScalarField<K>) -> K::Value {
+        self.layout.check(field.layout);
+        K::decode(self.policy_scalars[field.index])
+    }
+
+    pub fn set<K: FieldKind>(&mut self, field: ScalarField<K>, value: K::Value) {
+        self.layout.check(field.layout);
+        self.policy_scalars[field.index] = K::encode(value);
+    }
+
+    /// Value of a typed state vector for one state.
+    pub fn get_state<K: FieldKind>(&self, field: VectorField<K>, state: usize) -> K::Value {
+        K::decode(self.vector(field)[state])
+    }
+
+    pub fn set_state<K: FieldKind>(
+        &mut self,
+        field: VectorField<K>,
+        state: usize,
+        value: K::Value,
+    ) {
+        self.vector_mut(field)[state] = K::encode(value);
+    }
+
+    /// Raw slots of a typed state vector, one per state.
+    pub fn vector<K: FieldKind>(&self, field: VectorField<K>) -> &[f64] {
+        self.layout.check(field.layout);
+        self.state_vector(field.index)
+    }
+
+    pub fn vector_mut<K: FieldKind>(&mut self, field: VectorField<K>) -> &mut [f64] {
+        self.layout.check(field.layout);
+        self.state_vector_mut(field.index)
+    }
+
     fn state_vector_offset(&self, field: usize) -> usize {
         debug_assert!(field < self.layout.state_vectors());
         field * self.n_states
     }
 }
 
+/// Per-step copies of required data, in SoA layout.
+///
+/// Policy scalars are stored as `field * steps + step` and state vectors as
+/// `(field * n_states + state) * steps + step`, so each series is contiguous and
+/// lines up with the projection dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequiredDataHistory {
+    layout: RequiredDataLayout,
+    n_states: usize,
+    times: Vec<Date>,
+    policy_scalars: Vec<f64>,
+    state_vectors: Vec<f64>,
+}
+
+impl RequiredDataHistory {
+    pub fn new(
+        layout: RequiredDataLayout,
+        n_states: usize,
+        times: Vec<Date>,
+    ) -> Result<Self, RequiredDataBufferError> {
+        if n_states == 0 || times.is_empty() {
+            return Err(RequiredDataBufferError);
+        }
+        let steps = times.len();
+        let scalars_len = layout
+            .policy_scalars()
+            .checked_mul(steps)
+            .ok_or(RequiredDataBufferError)?;
+        let vectors_len = layout
+            .state_vectors()
+            .checked_mul(n_states)
+            .and_then(|len| len.checked_mul(steps))
+            .ok_or(RequiredDataBufferError)?;
+        Ok(Self {
+            layout,
+            n_states,
+            times,
+            policy_scalars: vec![0.0; scalars_len],
+            state_vectors: vec![0.0; vectors_len],
+        })
+    }
+
+    pub fn layout(&self) -> &RequiredDataLayout {
+        &self.layout
+    }
+
+    pub const fn n_states(&self) -> usize {
+        self.n_states
+    }
+
+    pub fn times(&self) -> &[Date] {
+        &self.times
+    }
+
+    pub fn len_steps(&self) -> usize {
+        self.times.len()
+    }
+
+    /// Values of one policy scalar across all steps.
+    pub fn policy_scalar_series(&self, index: usize) -> &[f64] {
+        let steps = self.len_steps();
+        &self.policy_scalars[index * steps..(index + 1) * steps]
+    }
+
+    /// Values of one state vector entry across all steps.
+    pub fn state_vector_series(&self, field: usize, state: usize) -> &[f64] {
+        debug_assert!(state < self.n_states);
+        let steps = self.len_steps();
+        let start = (field * self.n_states + state) * steps;
+        &self.state_vectors[start..start + steps]
+    }
+
+    pub fn series<K: FieldKind>(&self, field: ScalarField<K>) -> &[f64] {
+        self.layout.check(field.layout);
+        self.policy_scalar_series(field.index)
+    }
+
+    pub fn state_series<K: FieldKind>(&self, field: VectorField<K>, state: usize) -> &[f64] {
+        self.layout.check(field.layout);
+        self.state_vector_series(field.index, state)
+    }
+
+    /// Rebuilds the buffer contents recorded at `step`.
+    pub fn snapshot(&self, step: usize) -> RequiredDataBuffer {
+        let steps = self.len_steps();
+        let policy_scalars = (0..self.layout.policy_scalars())
+            .map(|field| self.policy_scalars[field * steps + step])
+            .collect();
+        let state_vectors = (0..self.layout.state_vectors() * self.n_states)
+            .map(|slot| self.state_vectors[slot * steps + step])
+            .collect();
+        RequiredDataBuffer {
+            layout: self.layout.clone(),
+            n_states: self.n_states,
+            policy_scalars,
+            state_vectors,
+            history: None,
+        }
+    }
+
+    fn record_values(&mut self, step: usize, policy_scalars: &[f64], state_vectors: &[f64]) {
+        let steps = self.len_steps();
+        assert!(step < steps, "history step out of range");
+        for (slot, &value) in policy_scalars.iter().enumerate() {
+            self.policy_scalars[slot * steps + step] = value;
+        }
+        for (slot, &value) in state_vectors.iter().enumerate() {
+            self.state_vectors[slot * steps + step] = value;
+        }
+    }
+}
+
+/// Unvalidated wire form of [`RequiredDataBuffer`].
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RequiredDataBufferParts {
+    layout: RequiredDataLayout,
+    n_states: usize,
+    policy_scalars: Vec<f64>,
+    state_vectors: Vec<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RequiredDataBufferParts> for RequiredDataBuffer {
+    type Error = RequiredDataBufferError;
+
+    fn try_from(parts: RequiredDataBufferParts) -> Result<Self, Self::Error> {
+        Self::from_parts(
+            parts.layout,
+            parts.n_states,
+            parts.policy_scalars,
+            parts.state_vectors,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,4 +736,155 @@ mod tests {
         assert_eq!(data.state_vector(2)[3], -7.0);
         assert_eq!(data.state_vector(0).len(), 4);
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn required_data_deserialization_validates_sizes() {
+        let invalid = r#"{"policy_scalars":0,"state_vectors":0}"#;
+        assert!(serde_json::from_str::<RequiredDataLayout>(invalid).is_err());
+
+        let mut builder = RequiredDataLayout::builder();
+        builder.state_vector::<RateField>("q_x");
+        let named = builder.build().unwrap();
+        let json = serde_json::to_string(&named).unwrap();
+        assert_eq!(
+            json,
+            r#"{"policy_scalars":0,"state_vectors":1,"fields":[{"name":"q_x","field_type":"Rate","scope":"StateVector"}]}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<RequiredDataLayout>(&json).unwrap(),
+            named
+        );
+        let miscounted = json.replace(r#""state_vectors":1"#, r#""state_vectors":2"#);
+        assert!(serde_json::from_str::<RequiredDataLayout>(&miscounted).is_err());
+
+        let layout = RequiredDataLayout::new(1, 1).unwrap();
+        let mut data = RequiredDataBuffer::new(layout, 2).unwrap();
+        data.set_policy_scalar(0, 0.5);
+        data.state_vector_mut(0)[1] = 0.25;
+        let json = serde_json::to_string(&data).unwrap();
+        let back: RequiredDataBuffer = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.policy_scalar(0), 0.5);
+        assert_eq!(back.state_vector(0), &[0.0, 0.25]);
+
+        let mismatched = r#"{"layout":{"policy_scalars":1,"state_vectors":1},"n_states":2,"policy_scalars":[0.5],"state_vectors":[0.0]}"#;
+        assert!(serde_json::from_str::<RequiredDataBuffer>(mismatched).is_err());
+    }
+
+    #[test]
+    fn builder_hands_out_typed_handles() {
+        let mut builder = RequiredDataLayout::builder();
+        let qx = builder.state_vector::<RateField>("q_x");
+        let sum_assured = builder.policy_scalar::<AmountField>("sum_assured");
+        let lapse = builder.state_vector::<RateField>("lapse");
+        let lives = builder.policy_scalar::<CountField>("lives");
+        let smoker = builder.policy_scalar::<FlagField>("smoker");
+        assert_eq!(builder.state_vector::<RateField>("q_x"), qx);
+        let layout = builder.build().unwrap();
+
+        assert_eq!(layout.policy_scalars(), 3);
+        assert_eq!(layout.state_vectors(), 2);
+        assert_eq!(layout.field("lapse").unwrap().field_type, FieldType::Rate);
+        assert_eq!(layout.vector_field::<RateField>("lapse"), Ok(lapse));
+        assert_eq!(layout.scalar_field::<CountField>("lives"), Ok(lives));
+
+        let mut data = RequiredDataBuffer::new(layout, 2).unwrap();
+        data.set(sum_assured, Amount::from_f64(1000.0));
+        data.set(lives, 3);
+        data.set(smoker, true);
+        data.set_state(qx, 1, 0.02);
+        data.vector_mut(lapse)[0] = 0.05;
+        assert_eq!(data.get(sum_assured), Amount::from_f64(1000.0));
+        assert_eq!(data.get(lives), 3);
+        assert!(data.get(smoker));
+        assert_eq!(data.get_state(qx, 1), 0.02);
+        assert_eq!(data.vector(lapse), &[0.05, 0.0]);
+        assert_eq!(data.policy_scalar(sum_assured.index()), 1000.0);
+    }
+
+    #[test]
+    fn builder_rejects_conflicting_registrations() {
+        let mut builder = RequiredDataLayout::builder();
+        builder.state_vector::<RateField>("q_x");
+        builder.state_vector::<AmountField>("q_x");
+        assert!(builder.build().is_err());
+
+        let mut builder = RequiredDataLayout::builder();
+        builder.policy_scalar::<RateField>("rate");
+        builder.state_vector::<RateField>("rate");
+        assert!(builder.build().is_err());
+
+        assert!(RequiredDataLayout::builder().build().is_err());
+        let mut builder = RequiredDataLayout::builder();
+        builder.policy_scalar::<FlagField>("");
+        assert!(builder.build().is_err());
+    }
+
+    #[test]
+    fn layout_lookup_checks_unit_and_scope() {
+        let mut builder = RequiredDataLayout::builder();
+        builder.state_vector::<RateField>("q_x");
+        let layout = builder.build().unwrap();
+        assert!(layout.vector_field::<AmountField>("q_x").is_err());
+        assert!(layout.scalar_field::<RateField>("q_x").is_err());
+        assert!(layout.vector_field::<RateField>("lapse").is_err());
+        assert!(
+            RequiredDataLayout::new(1, 1)
+                .unwrap()
+                .scalar_field::<RateField>("q_x")
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "handle from another layout")]
+    fn handles_are_bound_to_their_layout() {
+        let build = || {
+            let mut builder = RequiredDataLayout::builder();
+            let qx = builder.state_vector::<RateField>("q_x");
+            (builder.build().unwrap(), qx)
+        };
+        let (layout, qx) = build();
+        let (twin, foreign) = build();
+        assert_eq!(layout, twin);
+        assert_eq!(layout.vector_field::<RateField>("q_x"), Ok(qx));
+
+        let mut data = RequiredDataBuffer::new(layout.clone(), 1).unwrap();
+        data.set_state(qx, 0, 0.5);
+        assert_eq!(data.get_state(qx, 0), 0.5);
+        data.get_state(foreign, 0);
+    }
+
+    #[test]
+    fn history_keeps_each_recorded_step() -> Result<(), crate::DateError> {
+        let mut builder = RequiredDataLayout::builder();
+        let rate = builder.policy_scalar::<RateField>("interest");
+        let qx = builder.state_vector::<RateField>("q_x");
+        let layout = builder.build().unwrap();
+        let times = vec![Date::new(2024, 1, 31)?, Date::new(2024, 2, 29)?];
+
+        let mut data = RequiredDataBuffer::new(layout, 2).unwrap();
+        data.record(0);
+        assert!(data.history().is_none());
+        assert!(data.enable_history(Vec::new()).is_err());
+        data.enable_history(times.clone()).unwrap();
+
+        for step in 0..2 {
+            data.set(rate, 0.01 * (step + 1) as f64);
+            data.set_state(qx, 1, 0.1 * (step + 1) as f64);
+            data.record(step);
+        }
+
+        let history = data.history().unwrap();
+        assert_eq!(history.times(), times.as_slice());
+        assert_eq!(history.series(rate), &[0.01, 0.02]);
+        assert_eq!(history.state_series(qx, 0), &[0.0, 0.0]);
+        assert_eq!(history.state_series(qx, 1), &[0.1, 0.2]);
+
+        let first = history.snapshot(0);
+        assert_eq!(first.get(rate), 0.01);
+        assert_eq!(first.vector(qx), &[0.0, 0.1]);
+        assert_eq!(first.layout(), data.layout());
+        Ok(())
+    }
 }
@@ -1,10 +1,17 @@
 use super::Amount;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProductState {
     pub state_id: usize,
     pub in_force: u64,
     pub reserves: Amount,
+    /// Policyholder account value, for products that carry one.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    pub account_value: Amount,
+    /// Guarantee benefit base, for products with account value guarantees.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    pub benefit_base: Amount,
 }
 
 impl ProductState {
@@ -13,10 +20,27 @@ impl ProductState {
             state_id,
             in_force,
             reserves,
+            account_value: Amount::zero(),
+            benefit_base: Amount::zero(),
         }
     }
 
+    pub const fn with_account_value(mut self, account_value: Amount) -> Self {
+        self.account_value = account_value;
+        self
+    }
+
+    pub const fn with_benefit_base(mut self, benefit_base: Amount) -> Self {
+        self.benefit_base = benefit_base;
+        self
+    }
+
     pub fn apply_reserve_change(&mut self, delta: Amount) {
         self.reserves += delta;
     }
 }
+
+#[cfg(feature = "serde")]
+fn is_zero(amount: &Amount) -> bool {
+    *amount == Amount::zero()
+}
@@ -0,0 +1,781 @@
+use super::{Cession, ReinsuranceError, TreatyFlow};
+use crate::product::{
+    Amount, CashflowBuffer, CashflowCategory, CashflowKindId, FlowDirection, KindMetadata,
+    ProductDefinition,
+};
+use crate::rng::{RngCore, next_standard_normal, next_uniform};
+
+/// Loss a non-proportional layer responds to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LayerCover {
+    /// Each claim on its own (per-risk excess of loss).
+    PerRisk,
+    /// The claims of one event together (catastrophe excess of loss).
+    PerEvent,
+    /// All claims of the treaty year (stop loss).
+    Aggregate,
+}
+
+/// Claim on one risk from event `event`, paid at `step` and booked to `state`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Claim {
+    pub step: usize,
+    pub state: usize,
+    pub event: usize,
+    pub amount: Amount,
+}
+
+/// Stability clause scaling retention and limit with an index.
+///
+/// The factor at a step is the index relative to its first value, applied in
+/// full once it moves by more than `margin` (a franchise) and not at all
+/// before. Steps beyond the index use its last value.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Indexation {
+    index: Vec<f64>,
+    margin: f64,
+}
+
+impl Indexation {
+    pub fn new(index: Vec<f64>, margin: f64) -> Result<Self, ReinsuranceError> {
+        if index.is_empty() || !index.iter().all(|v| v.is_finite() && *v > 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("index"));
+        }
+        if !(margin.is_finite() && margin >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("index margin"));
+        }
+        Ok(Self { index, margin })
+    }
+
+    pub fn factor(&self, step: usize) -> f64 {
+        let ratio = self.index[step.min(self.index.len() - 1)] / self.index[0];
+        if (ratio - 1.0).abs() > self.margin {
+            ratio
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Excess-of-loss or stop-loss layer of `limit` in excess of `retention`.
+///
+/// Within a treaty year, layer losses first erode the annual aggregate
+/// deductible, and recoveries stop once the annual capacity is used: the
+/// limit plus one limit per reinstatement, capped by any annual aggregate
+/// limit. Reinstatement premiums are charged on the layer premium pro rata to
+/// the amount reinstated, at each reinstatement's rate; a rate of zero is a
+/// free reinstatement.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Layer {
+    name: String,
+    cover: LayerCover,
+    retention: f64,
+    limit: f64,
+    aggregate_deductible: f64,
+    aggregate_limit: Option<f64>,
+    reinstatements: Vec<f64>,
+    premium: f64,
+    indexation: Option<Indexation>,
+}
+
+/// A layer's running position within a treaty year.
+struct Position {
+    deductible: f64,
+    recovered: f64,
+    reinstated: f64,
+    aggregate: f64,
+}
+
+impl Layer {
+    pub fn per_risk(
+        name: impl Into<String>,
+        retention: f64,
+        limit: f64,
+    ) -> Result<Self, ReinsuranceError> {
+        Self::new(name.into(), LayerCover::PerRisk, retention, limit)
+    }
+
+    pub fn catastrophe(
+        name: impl Into<String>,
+        retention: f64,
+        limit: f64,
+    ) -> Result<Self, ReinsuranceError> {
+        Self::new(name.into(), LayerCover::PerEvent, retention, limit)
+    }
+
+    pub fn stop_loss(
+        name: impl Into<String>,
+        retention: f64,
+        limit: f64,
+    ) -> Result<Self, ReinsuranceError> {
+        Self::new(name.into(), LayerCover::Aggregate, retention, limit)
+    }
+
+    fn new(
+        name: String,
+        cover: LayerCover,
+        retention: f64,
+        limit: f64,
+    ) -> Result<Self, ReinsuranceError> {
+        if name.is_empty() {
+            return Err(ReinsuranceError::InvalidParameter("layer name"));
+        }
+        if !(retention.is_finite() && retention >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("retention"));
+        }
+        if !(limit.is_finite() && limit > 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("limit"));
+        }
+        Ok(Self {
+            name,
+            cover,
+            retention,
+            limit,
+            aggregate_deductible: 0.0,
+            aggregate_limit: None,
+            reinstatements: Vec::new(),
+            premium: 0.0,
+            indexation: None,
+        })
+    }
+
+    pub fn with_aggregate_deductible(mut self, amount: f64) -> Result<Self, ReinsuranceError> {
+        if !(amount.is_finite() && amount >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("aggregate deductible"));
+        }
+        self.aggregate_deductible = amount;
+        Ok(self)
+    }
+
+    pub fn with_aggregate_limit(mut self, amount: f64) -> Result<Self, ReinsuranceError> {
+        if !(amount.is_finite() && amount >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("aggregate limit"));
+        }
+        self.aggregate_limit = Some(amount);
+        Ok(self)
+    }
+
+    /// Reinstatements in order, each as the fraction of the layer premium
+    /// charged to reinstate the full limit. Stop-loss layers have none.
+    pub fn with_reinstatements(mut self, rates: Vec<f64>) -> Result<Self, ReinsuranceError> {
+        if self.cover == LayerCover::Aggregate || !rates.iter().all(|r| r.is_finite() && *r >= 0.0)
+        {
+            return Err(ReinsuranceError::InvalidParameter("reinstatements"));
+        }
+        self.reinstatements = rates;
+        Ok(self)
+    }
+
+    /// Premium paid at the start of each treaty year.
+    pub fn with_premium(mut self, premium: f64) -> Result<Self, ReinsuranceError> {
+        if !(premium.is_finite() && premium >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("layer premium"));
+        }
+        self.premium = premium;
+        Ok(self)
+    }
+
+    pub fn with_indexation(mut self, indexation: Indexation) -> Self {
+        self.indexation = Some(indexation);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cover(&self) -> LayerCover {
+        self.cover
+    }
+
+    fn open(&self) -> Position {
+        Position {
+            deductible: self.aggregate_deductible,
+            recovered: 0.0,
+            reinstated: 0.0,
+            aggregate: 0.0,
+        }
+    }
+
+    /// Recovery and reinstatement premium for `loss` at `step`.
+    fn recover(&self, position: &mut Position, step: usize, loss: f64) -> (f64, f64) {
+        let factor = self.indexation.as_ref().map_or(1.0, |i| i.factor(step));
+        let (retention, limit) = (self.retention * factor, self.limit * factor);
+        let layer = |x: f64| (x - retention).clamp(0.0, limit);
+        let mut loss = match self.cover {
+            LayerCover::Aggregate => {
+                let before = layer(position.aggregate);
+                position.aggregate += loss;
+                layer(position.aggregate) - before
+            }
+            LayerCover::PerRisk | LayerCover::PerEvent => layer(loss),
+        };
+        let deducted = loss.min(position.deductible);
+        position.deductible -= deducted;
+        loss -= deducted;
+        let capacity = limit * (1 + self.reinstatements.len()) as f64;
+        let capacity = self
+            .aggregate_limit
+            .map_or(capacity, |aal| aal.min(capacity));
+        let recovery = loss.min(capacity - position.recovered).max(0.0);
+        position.recovered += recovery;
+
+        let before = position.reinstated;
+        let after = (before + recovery).min(limit * self.reinstatements.len() as f64);
+        position.reinstated = after;
+        let premium = self
+            .reinstatements
+            .iter()
+            .enumerate()
+            .map(|(i, rate)| {
+                let start = limit * i as f64;
+                let reinstated = (after.min(start + limit) - before.max(start)).max(0.0);
+                self.premium * rate * reinstated / limit
+            })
+            .sum();
+        (recovery, premium)
+    }
+}
+
+/// Layers evaluated in order against individual claims.
+///
+/// Consecutive layers with the same cover stack on the same losses; each
+/// change of cover starts a new section on the losses net of earlier
+/// sections' recoveries, so per-risk layers inure to the benefit of a
+/// following catastrophe layer or stop loss. Treaty years are
+/// `period_steps` steps long. Recoveries are booked to each claim's state and
+/// step in the layer's recovery kind, with event recoveries shared pro rata
+/// to the claims; layer and reinstatement premiums are written to the first
+/// state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Programme {
+    layers: Vec<Layer>,
+    period_steps: usize,
+}
+
+impl Programme {
+    pub fn new(period_steps: usize) -> Result<Self, ReinsuranceError> {
+        if period_steps == 0 {
+            return Err(ReinsuranceError::InvalidParameter("treaty period"));
+        }
+        Ok(Self {
+            layers: Vec::new(),
+            period_steps,
+        })
+    }
+
+    /// Appends a layer; names must be unique.
+    pub fn with_layer(mut self, layer: Layer) -> Result<Self, ReinsuranceError> {
+        if self.layers.iter().any(|l| l.name == layer.name) {
+            return Err(ReinsuranceError::InvalidParameter("layer name"));
+        }
+        self.layers.push(layer);
+        Ok(self)
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    /// Recoveries on `claims` of the gross projection `gross`.
+    ///
+    /// The claims at each state and step must add up to the gross benefits
+    /// there, so that the recoveries in `net` offset claims it holds.
+    pub fn apply(
+        &self,
+        definition: &ProductDefinition,
+        gross: &CashflowBuffer,
+        claims: &[Claim],
+    ) -> Result<Cession, ReinsuranceError> {
+        let recoveries = self
+            .layers
+            .iter()
+            .map(|layer| {
+                KindMetadata::new(
+                    format!("recovery_{}", layer.name),
+                    FlowDirection::Inflow,
+                    CashflowCategory::Reinsurance,
+                )
+            })
+            .collect();
+        let mut cession = Cession::new(definition, gross, recoveries)?;
+        let steps = gross.len_steps();
+        if claims
+            .iter()
+            .any(|c| c.step >= steps || c.state >= definition.n_states)
+        {
+            return Err(ReinsuranceError::Shape);
+        }
+        if !claims
+            .iter()
+            .all(|c| c.amount.value().is_finite() && c.amount.value() >= 0.0)
+        {
+            return Err(ReinsuranceError::InvalidParameter("claim amount"));
+        }
+        let mut claimed = vec![0.0; definition.n_states * steps];
+        for claim in claims {
+            claimed[claim.state * steps + claim.step] += claim.amount.value();
+        }
+        let benefits: Vec<usize> = definition
+            .kinds_in(CashflowCategory::Benefit)
+            .map(|kind| kind.0)
+            .collect();
+        for state in 0..definition.n_states {
+            for step in 0..steps {
+                let paid: f64 = benefits
+                    .iter()
+                    .map(|&kind| gross.amount(state, kind, step).value())
+                    .sum();
+                if (claimed[state * steps + step] - paid).abs() > 1e-9 * paid.abs().max(1.0) {
+                    return Err(ReinsuranceError::Claims);
+                }
+            }
+        }
+        for layer in &self.layers {
+            for step in (0..steps).step_by(self.period_steps) {
+                let premium = Amount::from_f64(layer.premium);
+                cession.add_flow(TreatyFlow::ReinsurancePremium, 0, step, premium);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..claims.len()).collect();
+        order.sort_by_key(|&i| (claims[i].step, claims[i].event));
+        let mut basis: Vec<f64> = claims.iter().map(|c| c.amount.value()).collect();
+        let mut recovered = vec![0.0; claims.len()];
+        for (index, layer) in self.layers.iter().enumerate() {
+            if index > 0 && self.layers[index - 1].cover != layer.cover {
+                for (loss, recovery) in basis.iter_mut().zip(&mut recovered) {
+                    *loss -= std::mem::take(recovery);
+                }
+            }
+            let same_event = |a: &usize, b: &usize| {
+                layer.cover == LayerCover::PerEvent
+                    && claims[*a].step == claims[*b].step
+                    && claims[*a].event == claims[*b].event
+            };
+            let mut year = None;
+            let mut position = layer.open();
+            for group in order.chunk_by(same_event) {
+                let step = claims[group[0]].step;
+                if year != Some(step / self.period_steps) {
+                    year = Some(step / self.period_steps);
+                    position = layer.open();
+                }
+                let loss: f64 = group.iter().map(|&i| basis[i]).sum();
+                let (recovery, premium) = layer.recover(&mut position, step, loss);
+                if premium > 0.0 {
+                    let premium = Amount::from_f64(premium);
+                    cession.add_flow(TreatyFlow::ReinstatementPremium, 0, step, premium);
+                }
+                if recovery <= 0.0 {
+                    continue;
+                }
+                for &i in group {
+                    let share = recovery * basis[i] / loss;
+                    recovered[i] += share;
+                    let kind = cession.recovery(index);
+                    cession.add_extension(kind, claims[i].state, step, Amount::from_f64(share));
+                }
+            }
+        }
+        Ok(cession)
+    }
+
+    /// Recoveries on claims drawn from `losses` over the steps of `gross`.
+    ///
+    /// `gross` holds no benefits of its own; the sampled claims are added to
+    /// the loss model's kind and the cession is of the resulting projection.
+    pub fn simulate(
+        &self,
+        definition: &ProductDefinition,
+        gross: &CashflowBuffer,
+        losses: &LossModel,
+        rng: &mut dyn RngCore,
+    ) -> Result<Cession, ReinsuranceError> {
+        if gross.n_states() != definition.n_states
+            || gross.n_kinds() != definition.n_kinds
+            || losses.state >= definition.n_states
+            || losses.kind.0 >= definition.n_kinds
+        {
+            return Err(ReinsuranceError::Shape);
+        }
+        let claims = losses.sample(gross.len_steps(), rng);
+        let mut gross = gross.clone();
+        for claim in &claims {
+            *gross.amount_mut(claim.state, losses.kind.0, claim.step) += claim.amount;
+        }
+        self.apply(definition, &gross, &claims)
+    }
+}
+
+/// Distribution of individual claim amounts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// `exp(mu + sigma * Z)` for standard normal `Z`.
+    LogNormal { mu: f64, sigma: f64 },
+    /// Pareto with minimum `scale` and tail index `shape`.
+    Pareto { scale: f64, shape: f64 },
+}
+
+impl Severity {
+    pub fn sample(&self, rng: &mut dyn RngCore) -> f64 {
+        match *self {
+            Self::LogNormal { mu, sigma } => (mu + sigma * next_standard_normal(rng)).exp(),
+            Self::Pareto { scale, shape } => scale * (1.0 - next_uniform(rng)).powf(-1.0 / shape),
+        }
+    }
+}
+
+/// Compound Poisson claims: a Poisson number of events per step, each
+/// affecting one risk plus a Poisson number of further risks, with
+/// independent claim amounts. Claims are booked to `state` and, when
+/// simulated against a projection, to the benefit kind `kind`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LossModel {
+    events_per_step: f64,
+    extra_claims_per_event: f64,
+    severity: Severity,
+    state: usize,
+    kind: CashflowKindId,
+}
+
+impl LossModel {
+    /// Fails unless both means are finite and non-negative.
+    pub fn new(
+        events_per_step: f64,
+        extra_claims_per_event: f64,
+        severity: Severity,
+        state: usize,
+        kind: CashflowKindId,
+    ) -> Result<Self, ReinsuranceError> {
+        if !(events_per_step.is_finite() && events_per_step >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("events per step"));
+        }
+        if !(extra_claims_per_event.is_finite() && extra_claims_per_event >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("extra claims per event"));
+        }
+        Ok(Self {
+            events_per_step,
+            extra_claims_per_event,
+            severity,
+            state,
+            kind,
+        })
+    }
+
+    pub fn sample(&self, steps: usize, rng: &mut dyn RngCore) -> Vec<Claim> {
+        let mut claims = Vec::new();
+        let mut event = 0;
+        for step in 0..steps {
+            for _ in 0..poisson(self.events_per_step, rng) {
+                for _ in 0..=poisson(self.extra_claims_per_event, rng) {
+                    claims.push(Claim {
+                        step,
+                        state: self.state,
+                        event,
+                        amount: Amount::from_f64(self.severity.sample(rng)),
+                    });
+                }
+                event += 1;
+            }
+        }
+        claims
+    }
+}
+
+/// Largest mean drawn in one pass of [`poisson`]; `exp(-mean)` stays well
+/// clear of underflow below it.
+const POISSON_CHUNK: f64 = 500.0;
+
+/// Poisson draw by multiplying uniforms. Larger means are split into chunks
+/// of at most [`POISSON_CHUNK`] whose independent draws are summed, the sum
+/// of Poisson variables being Poisson with the summed mean.
+fn poisson(mean: f64, rng: &mut dyn RngCore) -> usize {
+    let mut remaining = mean;
+    let mut count = 0;
+    while remaining > 0.0 {
+        let chunk = remaining.min(POISSON_CHUNK);
+        remaining -= chunk;
+        let threshold = (-chunk).exp();
+        let mut product = next_uniform(rng);
+        while product > threshold {
+            product *= next_uniform(rng);
+            count += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::RequiredDataLayout;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, Frequency, generate_cashflow_dates};
+
+    fn setup() -> (ProductDefinition, CashflowBuffer) {
+        let definition = ProductDefinition::named(
+            vec!["active".into(), "dead".into()],
+            vec![KindMetadata::new(
+                "death",
+                FlowDirection::Outflow,
+                CashflowCategory::Benefit,
+            )],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 4, Frequency::SemiAnnual).unwrap();
+        let gross = CashflowBuffer::new(2, 1, times).unwrap();
+        (definition, gross)
+    }
+
+    /// `gross` with `claims` paid as deaths.
+    fn with_claims(gross: &CashflowBuffer, claims: &[Claim]) -> CashflowBuffer {
+        let mut gross = gross.clone();
+        for claim in claims {
+            *gross.amount_mut(claim.state, 0, claim.step) += claim.amount;
+        }
+        gross
+    }
+
+    fn claim(step: usize, event: usize, amount: f64) -> Claim {
+        Claim {
+            step,
+            state: 1,
+            event,
+            amount: Amount::from_f64(amount),
+        }
+    }
+
+    fn values(cession: &Cession, state: usize, kind: usize) -> Vec<f64> {
+        cession
+            .ceded
+            .series(state, kind)
+            .iter()
+            .map(|a| a.value())
+            .collect()
+    }
+
+    #[test]
+    fn per_risk_layer_applies_deductible_and_reinstatements() {
+        let (definition, gross) = setup();
+        let layer = Layer::per_risk("xl", 100.0, 500.0)
+            .unwrap()
+            .with_aggregate_deductible(50.0)
+            .unwrap()
+            .with_reinstatements(vec![1.0])
+            .unwrap()
+            .with_premium(40.0)
+            .unwrap();
+        let programme = Programme::new(2).unwrap().with_layer(layer).unwrap();
+        let claims = [
+            claim(0, 0, 300.0),
+            claim(1, 1, 700.0),
+            claim(1, 2, 1_000.0),
+            claim(2, 3, 200.0),
+        ];
+        let gross = with_claims(&gross, &claims);
+        let cession = programme.apply(&definition, &gross, &claims).unwrap();
+        // Year one: 200 - 50 deductible, then 500, then the 350 left of the
+        // reinstated capacity; year two starts afresh.
+        assert_eq!(
+            values(&cession, 1, cession.recovery(0).0),
+            [150.0, 850.0, 50.0, 0.0]
+        );
+        let reinstatement = cession.kind(TreatyFlow::ReinstatementPremium).0;
+        assert_eq!(values(&cession, 0, reinstatement), [12.0, 28.0, 4.0, 0.0]);
+        let premium = cession.kind(TreatyFlow::ReinsurancePremium).0;
+        assert_eq!(values(&cession, 0, premium), [40.0, 0.0, 40.0, 0.0]);
+        assert_eq!(
+            cession.definition.kind_id("recovery_xl"),
+            Some(cession.recovery(0))
+        );
+        // Gross claims stay in the net buffer; recoveries offset them.
+        assert_eq!(cession.net.series(1, 0), gross.series(1, 0));
+        assert_eq!(cession.net.series(1, 4), cession.ceded.series(1, 4));
+        let signed = |step| -> f64 {
+            (0..2)
+                .flat_map(|state| (0..cession.definition.n_kinds).map(move |k| (state, k)))
+                .map(|(state, kind)| {
+                    let amount = cession.net.amount(state, kind, step).value();
+                    match cession.definition.kinds()[kind].direction {
+                        FlowDirection::Inflow => amount,
+                        FlowDirection::Outflow => -amount,
+                    }
+                })
+                .sum()
+        };
+        // 1 700 of claims less 850 recovered and 28 of reinstatement premium.
+        assert!((signed(1) + 1_700.0 - 850.0 + 28.0).abs() < 1e-9);
+        assert!((signed(0) + 300.0 - 150.0 + 40.0 + 12.0).abs() < 1e-9);
+
+        // Claims must reconcile with the gross deaths they recover.
+        assert_eq!(
+            programme
+                .apply(&definition, &gross, &claims[..3])
+                .unwrap_err(),
+            ReinsuranceError::Claims
+        );
+    }
+
+    #[test]
+    fn sections_inure_in_programme_order() {
+        let (definition, gross) = setup();
+        let programme = Programme::new(2)
+            .unwrap()
+            .with_layer(
+                Layer::per_risk("risk_1", 100.0, 100.0)
+                    .unwrap()
+                    .with_reinstatements(vec![0.0])
+                    .unwrap(),
+            )
+            .unwrap()
+            .with_layer(Layer::per_risk("risk_2", 200.0, 300.0).unwrap())
+            .unwrap()
+            .with_layer(Layer::catastrophe("cat", 200.0, 500.0).unwrap())
+            .unwrap()
+            .with_layer(Layer::stop_loss("stop_loss", 150.0, 200.0).unwrap())
+            .unwrap();
+        let claims = [claim(0, 7, 600.0), claim(0, 7, 200.0)];
+        let gross = with_claims(&gross, &claims);
+        let cession = programme.apply(&definition, &gross, &claims).unwrap();
+        let total = |layer: usize| values(&cession, 1, cession.recovery(layer).0)[0];
+        // Stacked per-risk layers, the first reinstated for free, give 400
+        // and 100; the event then loses 300 net, of which the catastrophe
+        // layer takes 100; the stop loss takes the 50 of the remaining 200
+        // above its retention.
+        assert!((total(0) - 200.0).abs() < 1e-9);
+        assert!((total(1) - 300.0).abs() < 1e-9);
+        assert!((total(2) - 100.0).abs() < 1e-9);
+        assert!((total(3) - 50.0).abs() < 1e-9);
+
+        assert!(
+            Programme::new(1)
+                .unwrap()
+                .with_layer(Layer::stop_loss("a", 0.0, 1.0).unwrap())
+                .unwrap()
+                .with_layer(Layer::stop_loss("a", 0.0, 1.0).unwrap())
+                .is_err()
+        );
+        assert!(
+            Layer::stop_loss("a", 0.0, 1.0)
+                .unwrap()
+                .with_reinstatements(vec![1.0])
+                .is_err()
+        );
+        assert!(Layer::per_risk("a", 0.0, 0.0).is_err());
+        assert_eq!(
+            programme
+                .apply(&definition, &gross, &[claim(4, 0, 1.0)])
+                .unwrap_err(),
+            ReinsuranceError::Shape
+        );
+    }
+
+    #[test]
+    fn indexation_moves_layers_beyond_the_margin() {
+        let index = Indexation::new(vec![100.0, 102.0, 110.0], 0.05).unwrap();
+        assert_eq!(index.factor(1), 1.0);
+        assert!((index.factor(3) - 1.1).abs() < 1e-12);
+        assert!(Indexation::new(vec![], 0.0).is_err());
+
+        let (definition, gross) = setup();
+        let layer = Layer::per_risk("xl", 100.0, 500.0)
+            .unwrap()
+            .with_indexation(index);
+        let programme = Programme::new(2).unwrap().with_layer(layer).unwrap();
+        let claims = [claim(1, 0, 700.0), claim(2, 1, 700.0)];
+        let gross = with_claims(&gross, &claims);
+        let cession = programme.apply(&definition, &gross, &claims).unwrap();
+        let recoveries = values(&cession, 1, cession.recovery(0).0);
+        assert_eq!(recoveries[1], 500.0);
+        assert!((recoveries[2] - 550.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn simulated_claims_follow_the_loss_model() {
+        let losses = LossModel::new(
+            2.0,
+            0.5,
+            Severity::Pareto {
+                scale: 100.0,
+                shape: 3.0,
+            },
+            1,
+            CashflowKindId(0),
+        )
+        .unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(11);
+        let claims = losses.sample(20_000, &mut rng);
+        let events = claims.last().unwrap().event + 1;
+        assert!((events as f64 / 20_000.0 - 2.0).abs() < 0.05);
+        assert!((claims.len() as f64 / events as f64 - 1.5).abs() < 0.05);
+        let mean = claims.iter().map(|c| c.amount.value()).sum::<f64>() / claims.len() as f64;
+        assert!((mean - 150.0).abs() < 5.0);
+
+        let (definition, gross) = setup();
+        let programme = Programme::new(2)
+            .unwrap()
+            .with_layer(Layer::catastrophe("cat", 200.0, 1_000.0).unwrap())
+            .unwrap();
+        let simulated = programme
+            .simulate(
+                &definition,
+                &gross,
+                &losses,
+                &mut Xoshiro256StarStar::from_seed64(5),
+            )
+            .unwrap();
+        let sampled = losses.sample(4, &mut Xoshiro256StarStar::from_seed64(5));
+        let gross = with_claims(&gross, &sampled);
+        let applied = programme.apply(&definition, &gross, &sampled).unwrap();
+        assert_eq!(
+            simulated.ceded.series(1, simulated.recovery(0).0),
+            applied.ceded.series(1, applied.recovery(0).0)
+        );
+        // The sampled claims are in the gross deaths of the net buffer.
+        assert_eq!(simulated.net.series(1, 0), gross.series(1, 0));
+        assert!(gross.series(1, 0).iter().any(|a| a.value() > 0.0));
+    }
+
+    #[test]
+    fn loss_models_reject_invalid_means() {
+        let severity = Severity::LogNormal {
+            mu: 0.0,
+            sigma: 1.0,
+        };
+        for mean in [-1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                LossModel::new(mean, 0.0, severity, 1, CashflowKindId(0)),
+                Err(ReinsuranceError::InvalidParameter("events per step"))
+            );
+            assert_eq!(
+                LossModel::new(1.0, mean, severity, 1, CashflowKindId(0)),
+                Err(ReinsuranceError::InvalidParameter("extra claims per event"))
+            );
+        }
+        let none = LossModel::new(0.0, 0.0, severity, 1, CashflowKindId(0)).unwrap();
+        assert!(
+            none.sample(10, &mut Xoshiro256StarStar::from_seed64(1))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn poisson_draws_hold_their_mean_beyond_underflow() {
+        // exp(-2000) underflows to zero, which a single pass would never reach.
+        let mut rng = Xoshiro256StarStar::from_seed64(3);
+        let draws = 200;
+        let total: usize = (0..draws).map(|_| poisson(2_000.0, &mut rng)).sum();
+        let mean = total as f64 / draws as f64;
+        // Standard error of the mean is sqrt(2000 / 200) ~ 3.2.
+        assert!((mean - 2_000.0).abs() < 16.0, "{mean}");
+    }
+}
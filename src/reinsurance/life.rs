@@ -0,0 +1,516 @@
+use super::{Cession, ProportionalCover, ReinsuranceError};
+use crate::life::Schedule;
+use crate::model::{ExpectedValueModel, ModelConfig};
+use crate::product::{
+    Amount, AmountField, CashflowBuffer, CashflowCategory, CashflowKindId, FlowDirection,
+    KindMetadata, Product, ProductDefinition, RequiredDataBuffer,
+};
+use crate::rng::RngCore;
+use crate::{Frequency, generate_cashflow_dates};
+
+/// Ceded cashflows of a life treaty, each written to its own kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifeFlow {
+    /// Coinsured share of gross premiums, or YRT premiums.
+    CededPremium,
+    /// Coinsured share of gross benefits, or the NAR share of death claims.
+    BenefitRecovery,
+    ExpenseAllowance,
+    /// Change in the modco reserve less interest on it, paid by the
+    /// reinsurer; negative while the reserve runs off.
+    ModcoAdjustment,
+}
+
+impl LifeFlow {
+    pub const ALL: [Self; 4] = [
+        Self::CededPremium,
+        Self::BenefitRecovery,
+        Self::ExpenseAllowance,
+        Self::ModcoAdjustment,
+    ];
+
+    fn metadata(self) -> KindMetadata {
+        let (name, direction, category) = match self {
+            Self::CededPremium => (
+                "ceded_premium",
+                FlowDirection::Outflow,
+                CashflowCategory::Reinsurance,
+            ),
+            Self::BenefitRecovery => (
+                "benefit_recovery",
+                FlowDirection::Inflow,
+                CashflowCategory::Reinsurance,
+            ),
+            Self::ExpenseAllowance => (
+                "expense_allowance",
+                FlowDirection::Inflow,
+                CashflowCategory::Commission,
+            ),
+            Self::ModcoAdjustment => (
+                "modco_adjustment",
+                FlowDirection::Inflow,
+                CashflowCategory::Reinsurance,
+            ),
+        };
+        KindMetadata::new(name, direction, category)
+    }
+}
+
+/// Gross projection of a life product with the per-policy exposure that
+/// life treaties are priced on.
+///
+/// `in_force` is the expected number of lives in the insured state at the
+/// start of each step; `sum_assured` and the reserves are per policy. Reserves
+/// are zero unless set with [`with_reserves`](Self::with_reserves).
+#[derive(Debug, Clone)]
+pub struct LifeProjection {
+    pub definition: ProductDefinition,
+    pub gross: CashflowBuffer,
+    frequency: Frequency,
+    insured_state: usize,
+    in_force: Vec<f64>,
+    sum_assured: Vec<Amount>,
+    reserves: Vec<Amount>,
+}
+
+impl LifeProjection {
+    pub fn new(
+        definition: ProductDefinition,
+        gross: CashflowBuffer,
+        frequency: Frequency,
+        insured_state: usize,
+        in_force: Vec<f64>,
+        sum_assured: Vec<Amount>,
+    ) -> Result<Self, ReinsuranceError> {
+        let steps = gross.len_steps();
+        if gross.n_states() != definition.n_states
+            || gross.n_kinds() != definition.n_kinds
+            || insured_state >= definition.n_states
+            || in_force.len() != steps
+            || sum_assured.len() != steps
+        {
+            return Err(ReinsuranceError::Shape);
+        }
+        Ok(Self {
+            definition,
+            gross,
+            frequency,
+            insured_state,
+            in_force,
+            sum_assured,
+            reserves: vec![Amount::zero(); steps],
+        })
+    }
+
+    /// Projects `product` with the expected-value model. The product must
+    /// write its per-policy `sum_assured` amount to the required data.
+    pub fn project(
+        product: &dyn Product,
+        config: &ModelConfig,
+        insured_state: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Self, ReinsuranceError> {
+        let definition = product.definition().clone();
+        let field = definition
+            .required_data
+            .scalar_field::<AmountField>("sum_assured")
+            .map_err(|_| ReinsuranceError::Projection)?;
+        let times = generate_cashflow_dates(config.start, config.steps, config.frequency)
+            .map_err(|_| ReinsuranceError::Projection)?;
+        let mut gross = CashflowBuffer::new(definition.n_states, definition.n_kinds, times.clone())
+            .map_err(|_| ReinsuranceError::Projection)?;
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states)
+                .map_err(|_| ReinsuranceError::Projection)?;
+        data.enable_history(times)
+            .map_err(|_| ReinsuranceError::Projection)?;
+        let occupancy = ExpectedValueModel
+            .run_with_occupancy(product, config, rng, &mut gross, &mut data)
+            .map_err(|_| ReinsuranceError::Projection)?;
+        let sum_assured = data
+            .history()
+            .ok_or(ReinsuranceError::Projection)?
+            .series(field)
+            .iter()
+            .map(|&v| Amount::from_f64(v))
+            .collect();
+        let lives = product.initial_state().in_force as f64;
+        let in_force = occupancy
+            .chunks_exact(definition.n_states)
+            .map(|row| row.get(insured_state).map_or(0.0, |p| p * lives))
+            .collect();
+        Self::new(
+            definition,
+            gross,
+            config.frequency,
+            insured_state,
+            in_force,
+            sum_assured,
+        )
+    }
+
+    /// Per-policy reserves at the start of each step.
+    pub fn with_reserves(mut self, reserves: Vec<Amount>) -> Result<Self, ReinsuranceError> {
+        if reserves.len() != self.gross.len_steps() {
+            return Err(ReinsuranceError::Shape);
+        }
+        self.reserves = reserves;
+        Ok(self)
+    }
+
+    pub fn in_force(&self) -> &[f64] {
+        &self.in_force
+    }
+
+    pub fn sum_assured(&self) -> &[Amount] {
+        &self.sum_assured
+    }
+
+    pub fn reserves(&self) -> &[Amount] {
+        &self.reserves
+    }
+
+    /// Per-policy net amount at risk at `step`, floored at zero.
+    pub fn net_amount_at_risk(&self, step: usize) -> f64 {
+        (self.sum_assured[step] - self.reserves[step])
+            .value()
+            .max(0.0)
+    }
+}
+
+/// Allowances paid by the reinsurer as fractions of the ceded premium.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpenseAllowance {
+    pub first_year: f64,
+    pub renewal: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Basis {
+    Yrt { rates: Schedule },
+    Coinsurance,
+    Modco { interest: f64 },
+}
+
+/// Yearly renewable term, coinsurance or modified coinsurance of a life
+/// projection.
+///
+/// The cover sets the ceded share from each step's sum assured. YRT cedes that
+/// share of the net amount at risk: premiums are the annual rate for the
+/// policy year, spread over the steps, on the ceded amount at risk of lives
+/// in force, and death claims are recovered in the ratio of the ceded amount
+/// at risk to the sum assured. Coinsurance and modco cede the share of gross
+/// premiums, benefits and reserves; under modco the cedant keeps the reserve
+/// and the reinsurer pays the modco adjustment at the end of each step.
+/// Expense allowances apply to ceded premiums. Ceded cashflows are
+/// [`LifeFlow`] kinds; gross kinds pass to the net buffer unchanged. The
+/// ceded reserve is a balance and is returned in
+/// [`Cession::ceded_reserves`] rather than as a kind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifeTreaty {
+    basis: Basis,
+    cover: ProportionalCover,
+    allowance: ExpenseAllowance,
+    death_kind: Option<CashflowKindId>,
+}
+
+impl LifeTreaty {
+    /// YRT with annual reinsurance rates per unit of amount at risk by policy
+    /// year.
+    pub fn yrt(rates: Schedule, cover: ProportionalCover) -> Self {
+        Self::new(Basis::Yrt { rates }, cover)
+    }
+
+    pub fn coinsurance(cover: ProportionalCover) -> Self {
+        Self::new(Basis::Coinsurance, cover)
+    }
+
+    /// Modco crediting the reserve with the annual rate `interest`.
+    pub fn modco(cover: ProportionalCover, interest: f64) -> Result<Self, ReinsuranceError> {
+        if !(interest.is_finite() && interest > -1.0) {
+            return Err(ReinsuranceError::InvalidParameter("modco interest"));
+        }
+        Ok(Self::new(Basis::Modco { interest }, cover))
+    }
+
+    fn new(basis: Basis, cover: ProportionalCover) -> Self {
+        Self {
+            basis,
+            cover,
+            allowance: ExpenseAllowance::default(),
+            death_kind: None,
+        }
+    }
+
+    pub fn with_allowance(mut self, allowance: ExpenseAllowance) -> Result<Self, ReinsuranceError> {
+        let valid = |rate: f64| rate.is_finite() && rate >= 0.0;
+        if !valid(allowance.first_year) || !valid(allowance.renewal) {
+            return Err(ReinsuranceError::InvalidParameter("expense allowance"));
+        }
+        self.allowance = allowance;
+        Ok(self)
+    }
+
+    /// Kind of the death claims a YRT treaty recovers; defaults to the kind
+    /// named `death`.
+    pub fn with_death_kind(mut self, kind: CashflowKindId) -> Self {
+        self.death_kind = Some(kind);
+        self
+    }
+
+    pub fn apply(&self, projection: &LifeProjection) -> Result<Cession, ReinsuranceError> {
+        let definition = &projection.definition;
+        let gross = &projection.gross;
+        let extra = LifeFlow::ALL.map(LifeFlow::metadata).to_vec();
+        let mut cession = Cession::new(definition, gross, extra)?;
+        let periods = projection.frequency.periods_per_year();
+        let steps = gross.len_steps();
+        let insured = projection.insured_state;
+        let premium_kind = cession.life(LifeFlow::CededPremium);
+        let recovery_kind = cession.life(LifeFlow::BenefitRecovery);
+        let allowance_kind = cession.life(LifeFlow::ExpenseAllowance);
+        let modco_kind = cession.life(LifeFlow::ModcoAdjustment);
+
+        let death = match (&self.basis, self.death_kind) {
+            (Basis::Yrt { .. }, Some(kind)) if kind.0 < definition.n_kinds => Some(kind),
+            (Basis::Yrt { .. }, Some(_)) => return Err(ReinsuranceError::Shape),
+            (Basis::Yrt { .. }, None) => Some(
+                definition
+                    .kind_id("death")
+                    .ok_or(ReinsuranceError::InvalidParameter("death kind"))?,
+            ),
+            _ => None,
+        };
+        let share_at = |step: usize| self.cover.ceded_share(projection.sum_assured[step].value());
+        let ceded_reserve =
+            |step: usize| projection.reserves[step] * (share_at(step) * projection.in_force[step]);
+
+        for step in 0..steps {
+            let share = share_at(step);
+            let allowance = if step < periods as usize {
+                self.allowance.first_year
+            } else {
+                self.allowance.renewal
+            };
+            match &self.basis {
+                Basis::Yrt { rates } => {
+                    let ceded_nar = projection.net_amount_at_risk(step) * share;
+                    let rate = rates.at(step / periods as usize) / f64::from(periods);
+                    let premium = Amount::from_f64(rate * ceded_nar * projection.in_force[step]);
+                    cession.add_extension(premium_kind, insured, step, premium);
+                    cession.add_extension(allowance_kind, insured, step, premium * allowance);
+                    let sum_assured = projection.sum_assured[step].value();
+                    let ratio = if sum_assured > 0.0 {
+                        ceded_nar / sum_assured
+                    } else {
+                        0.0
+                    };
+                    let death = death.expect("YRT resolves its death kind").0;
+                    for state in 0..definition.n_states {
+                        let recovery = gross.amount(state, death, step) * ratio;
+                        cession.add_extension(recovery_kind, state, step, recovery);
+                    }
+                }
+                Basis::Coinsurance | Basis::Modco { .. } => {
+                    for state in 0..definition.n_states {
+                        for (kind, metadata) in definition.kinds().iter().enumerate() {
+                            let ceded = gross.amount(state, kind, step) * share;
+                            match metadata.category {
+                                CashflowCategory::Premium => {
+                                    cession.add_extension(premium_kind, state, step, ceded);
+                                    let allowance = ceded * allowance;
+                                    cession.add_extension(allowance_kind, state, step, allowance);
+                                }
+                                CashflowCategory::Benefit => {
+                                    cession.add_extension(recovery_kind, state, step, ceded);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    let reserve = ceded_reserve(step);
+                    cession.ceded_reserves.push(reserve);
+                    if let Basis::Modco { interest } = self.basis {
+                        let growth = (1.0 + interest).powf(1.0 / f64::from(periods));
+                        let next = if step + 1 < steps {
+                            ceded_reserve(step + 1)
+                        } else {
+                            Amount::zero()
+                        };
+                        cession.add_extension(modco_kind, insured, step, next - reserve * growth);
+                    }
+                }
+            }
+        }
+        Ok(cession)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life::{LifeAnnuity, MortalityTable, TermLife};
+    use crate::product::RequiredDataLayout;
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::{Date, generate_cashflow_dates};
+
+    fn config(steps: usize) -> ModelConfig {
+        ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps,
+        }
+    }
+
+    fn series(cession: &Cession, state: usize, flow: LifeFlow) -> Vec<f64> {
+        cession
+            .net
+            .series(state, cession.life(flow).0)
+            .iter()
+            .map(|a| a.value())
+            .collect()
+    }
+
+    #[test]
+    fn yrt_charges_rates_on_the_ceded_amount_at_risk() {
+        let table = MortalityTable::new(40, vec![0.01; 20]).unwrap();
+        let product = TermLife::new(table, 40, 10, 100_000.0, 500.0).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        let projection = LifeProjection::project(&product, &config(10), TermLife::ACTIVE, &mut rng)
+            .unwrap()
+            .with_reserves(
+                (0..10)
+                    .map(|t| Amount::from_f64(1_000.0 * t as f64))
+                    .collect(),
+            )
+            .unwrap();
+        assert_eq!(projection.sum_assured()[3].value(), 100_000.0);
+        assert!((projection.in_force()[2] - 0.99 * 0.99).abs() < 1e-12);
+        assert_eq!(projection.net_amount_at_risk(2), 98_000.0);
+
+        let treaty = LifeTreaty::yrt(
+            Schedule::level(0.003),
+            ProportionalCover::QuotaShare { share: 0.4 },
+        )
+        .with_allowance(ExpenseAllowance {
+            first_year: 1.0,
+            renewal: 0.1,
+        })
+        .unwrap();
+        let cession = treaty.apply(&projection).unwrap();
+        assert!(cession.ceded_reserves.is_empty());
+        let premiums = series(&cession, TermLife::ACTIVE, LifeFlow::CededPremium);
+        let allowances = series(&cession, TermLife::ACTIVE, LifeFlow::ExpenseAllowance);
+        assert!((premiums[0] - 120.0).abs() < 1e-9);
+        assert!((allowances[0] - 120.0).abs() < 1e-9);
+        let expected = 0.003 * 0.4 * 98_000.0 * 0.9801;
+        assert!((premiums[2] - expected).abs() < 1e-9);
+        assert!((allowances[2] - 0.1 * expected).abs() < 1e-9);
+        let recoveries = series(&cession, TermLife::ACTIVE, LifeFlow::BenefitRecovery);
+        let death = 100_000.0 * 0.01 * 0.9801;
+        assert!((recoveries[2] - death * 0.4 * 0.98).abs() < 1e-9);
+        assert_eq!(
+            cession.net.series(TermLife::ACTIVE, TermLife::DEATH.0),
+            projection.gross.series(TermLife::ACTIVE, TermLife::DEATH.0)
+        );
+
+        let annuity =
+            LifeAnnuity::new(MortalityTable::new(40, vec![0.01; 20]).unwrap(), 40, 1.0).unwrap();
+        assert_eq!(
+            LifeProjection::project(&annuity, &config(2), 0, &mut rng).unwrap_err(),
+            ReinsuranceError::Projection
+        );
+    }
+
+    fn projection() -> LifeProjection {
+        let definition = ProductDefinition::named(
+            vec!["active".into(), "dead".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("claim", FlowDirection::Outflow, CashflowCategory::Benefit),
+                KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 3, Frequency::Annual).unwrap();
+        let mut gross = CashflowBuffer::new(2, 3, times).unwrap();
+        gross.series_mut(0, 0).fill(Amount::from_f64(100.0));
+        gross.series_mut(0, 1).fill(Amount::from_f64(20.0));
+        gross.series_mut(0, 2).fill(Amount::from_f64(5.0));
+        LifeProjection::new(
+            definition,
+            gross,
+            Frequency::Annual,
+            0,
+            vec![1.0, 0.9, 0.8],
+            vec![Amount::from_f64(1_000.0); 3],
+        )
+        .unwrap()
+        .with_reserves([0.0, 50.0, 80.0].map(Amount::from_f64).to_vec())
+        .unwrap()
+    }
+
+    #[test]
+    fn coinsurance_and_modco_split_premiums_benefits_and_reserves() {
+        let projection = projection();
+        let cover = ProportionalCover::QuotaShare { share: 0.5 };
+        let allowance = ExpenseAllowance {
+            first_year: 0.5,
+            renewal: 0.1,
+        };
+        let coinsurance = LifeTreaty::coinsurance(cover)
+            .with_allowance(allowance)
+            .unwrap()
+            .apply(&projection)
+            .unwrap();
+        assert_eq!(series(&coinsurance, 0, LifeFlow::CededPremium), [50.0; 3]);
+        assert_eq!(
+            series(&coinsurance, 0, LifeFlow::BenefitRecovery),
+            [10.0; 3]
+        );
+        assert_eq!(
+            series(&coinsurance, 0, LifeFlow::ExpenseAllowance),
+            [25.0, 5.0, 5.0]
+        );
+        assert_eq!(
+            coinsurance.ceded_reserves,
+            [0.0, 22.5, 32.0].map(Amount::from_f64)
+        );
+        assert_eq!(coinsurance.definition.n_kinds, 3 + 4 + 4);
+        assert_eq!(series(&coinsurance, 0, LifeFlow::ModcoAdjustment), [0.0; 3]);
+
+        let modco = LifeTreaty::modco(cover, 0.04)
+            .unwrap()
+            .apply(&projection)
+            .unwrap();
+        let adjustment = series(&modco, 0, LifeFlow::ModcoAdjustment);
+        for (actual, expected) in adjustment
+            .iter()
+            .zip([22.5, 32.0 - 1.04 * 22.5, -1.04 * 32.0])
+        {
+            assert!((actual - expected).abs() < 1e-9);
+        }
+        assert_eq!(series(&modco, 0, LifeFlow::CededPremium), [50.0; 3]);
+
+        assert_eq!(
+            LifeTreaty::yrt(Schedule::level(0.001), cover)
+                .apply(&projection)
+                .unwrap_err(),
+            ReinsuranceError::InvalidParameter("death kind")
+        );
+        assert!(
+            LifeTreaty::yrt(Schedule::level(0.001), cover)
+                .with_death_kind(CashflowKindId(1))
+                .apply(&projection)
+                .is_ok()
+        );
+        assert!(LifeTreaty::modco(cover, f64::NAN).is_err());
+        assert_eq!(
+            projection.with_reserves(Vec::new()).unwrap_err(),
+            ReinsuranceError::Shape
+        );
+    }
+}
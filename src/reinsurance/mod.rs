@@ -0,0 +1,225 @@
+//! Reinsurance treaties applied to projected gross cashflows and claims.
+
+mod excess;
+mod life;
+mod proportional;
+
+pub use excess::{Claim, Indexation, Layer, LayerCover, LossModel, Programme, Severity};
+pub use life::{ExpenseAllowance, LifeFlow, LifeProjection, LifeTreaty};
+pub use proportional::{
+    ProfitCommission, ProportionalCover, ProportionalTreaty, ReinsurancePremium,
+};
+
+use std::fmt;
+
+use crate::product::{
+    Amount, CashflowBuffer, CashflowCategory, CashflowKindId, FlowDirection, KindMetadata,
+    ProductDefinition,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReinsuranceError {
+    /// Shares, retentions, limits or rates are out of range.
+    InvalidParameter(&'static str),
+    /// The gross buffer does not match the product definition.
+    Shape,
+    /// The gross definition already uses a treaty kind name.
+    KindName,
+    /// The product could not be projected or does not publish a sum assured.
+    Projection,
+    /// Claims do not add up to the gross benefits at some state and step.
+    Claims,
+}
+
+impl fmt::Display for ReinsuranceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParameter(name) => write!(f, "invalid {name}"),
+            Self::Shape => f.write_str("gross buffer does not match the product definition"),
+            Self::KindName => f.write_str("gross definition already uses a treaty kind name"),
+            Self::Projection => f.write_str("product projection failed"),
+            Self::Claims => f.write_str("claims do not reconcile with gross benefits"),
+        }
+    }
+}
+
+impl std::error::Error for ReinsuranceError {}
+
+/// Flows between cedant and reinsurer that have no gross counterpart.
+///
+/// A [`Cession`] appends one kind per flow, in this order, after the gross
+/// kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreatyFlow {
+    /// Premium paid to the reinsurer on a schedule rather than on original
+    /// terms.
+    ReinsurancePremium,
+    /// Premium for restoring non-proportional cover after a loss.
+    ReinstatementPremium,
+    CedingCommission,
+    ProfitCommission,
+}
+
+impl TreatyFlow {
+    pub const ALL: [Self; 4] = [
+        Self::ReinsurancePremium,
+        Self::ReinstatementPremium,
+        Self::CedingCommission,
+        Self::ProfitCommission,
+    ];
+
+    fn metadata(self) -> KindMetadata {
+        match self {
+            Self::ReinsurancePremium => KindMetadata::new(
+                "reinsurance_premium",
+                FlowDirection::Outflow,
+                CashflowCategory::Reinsurance,
+            ),
+            Self::ReinstatementPremium => KindMetadata::new(
+                "reinstatement_premium",
+                FlowDirection::Outflow,
+                CashflowCategory::Reinsurance,
+            ),
+            Self::CedingCommission => KindMetadata::new(
+                "ceding_commission",
+                FlowDirection::Inflow,
+                CashflowCategory::Commission,
+            ),
+            Self::ProfitCommission => KindMetadata::new(
+                "profit_commission",
+                FlowDirection::Inflow,
+                CashflowCategory::Commission,
+            ),
+        }
+    }
+}
+
+/// Result of applying a treaty to a gross buffer.
+///
+/// `definition` is the gross definition with a kind appended for each
+/// [`TreatyFlow`] and then either one `recovery_<layer>` kind per
+/// non-proportional layer or one kind per [`LifeFlow`]; both buffers use it.
+/// For gross kinds, `ceded` holds the share passed to the reinsurer and `net`
+/// the share retained. Appended kinds are the same in both buffers, with
+/// directions from the cedant's point of view, so the signed total of the
+/// non-memo kinds of `net` ([`CashflowBuffer::net_over_kinds`]) is the gross
+/// result after reinsurance.
+#[derive(Debug, Clone)]
+pub struct Cession {
+    pub definition: ProductDefinition,
+    pub ceded: CashflowBuffer,
+    pub net: CashflowBuffer,
+    /// Reinsurer's share of the reserve at the start of each step, for
+    /// treaties that cede reserves; empty otherwise.
+    pub ceded_reserves: Vec<Amount>,
+    gross_kinds: usize,
+}
+
+impl Cession {
+    /// Cession of `gross` with nothing ceded, checked against `definition`,
+    /// with `extra` kinds appended after the treaty flows.
+    fn new(
+        definition: &ProductDefinition,
+        gross: &CashflowBuffer,
+        extra: Vec<KindMetadata>,
+    ) -> Result<Self, ReinsuranceError> {
+        if gross.n_states() != definition.n_states || gross.n_kinds() != definition.n_kinds {
+            return Err(ReinsuranceError::Shape);
+        }
+        let mut kinds = definition.kinds().to_vec();
+        kinds.extend(TreatyFlow::ALL.map(TreatyFlow::metadata));
+        kinds.extend(extra);
+        let extended = ProductDefinition::named(
+            definition.state_names().to_vec(),
+            kinds,
+            definition.required_data.clone(),
+        )
+        .map_err(|_| ReinsuranceError::KindName)?;
+        let buffer =
+            CashflowBuffer::new(extended.n_states, extended.n_kinds, gross.times().to_vec())
+                .map_err(|_| ReinsuranceError::Shape)?;
+        let mut cession = Self {
+            definition: extended,
+            ceded: buffer.clone(),
+            net: buffer,
+            ceded_reserves: Vec::new(),
+            gross_kinds: definition.n_kinds,
+        };
+        for state in 0..definition.n_states {
+            for kind in 0..definition.n_kinds {
+                cession
+                    .net
+                    .series_mut(state, kind)
+                    .copy_from_slice(gross.series(state, kind));
+            }
+        }
+        Ok(cession)
+    }
+
+    /// Kind holding `flow` in both buffers.
+    pub fn kind(&self, flow: TreatyFlow) -> CashflowKindId {
+        let offset = TreatyFlow::ALL
+            .iter()
+            .position(|&f| f == flow)
+            .expect("every flow is listed");
+        CashflowKindId(self.gross_kinds + offset)
+    }
+
+    /// Recovery kind of non-proportional layer `layer`, in programme order.
+    pub fn recovery(&self, layer: usize) -> CashflowKindId {
+        self.extension(layer)
+    }
+
+    /// Kind of a life treaty flow, on cessions from a [`LifeTreaty`].
+    pub fn life(&self, flow: LifeFlow) -> CashflowKindId {
+        let offset = LifeFlow::ALL
+            .iter()
+            .position(|&f| f == flow)
+            .expect("every flow is listed");
+        self.extension(offset)
+    }
+
+    fn extension(&self, index: usize) -> CashflowKindId {
+        CashflowKindId(self.gross_kinds + TreatyFlow::ALL.len() + index)
+    }
+
+    /// Number of kinds taken from the gross definition.
+    pub fn gross_kinds(&self) -> usize {
+        self.gross_kinds
+    }
+
+    /// Sets the ceded share of gross kind `kind` and the retained remainder.
+    fn split(
+        &mut self,
+        gross: &CashflowBuffer,
+        state: usize,
+        kind: usize,
+        step: usize,
+        ceded: Amount,
+    ) {
+        *self.ceded.amount_mut(state, kind, step) = ceded;
+        *self.net.amount_mut(state, kind, step) = gross.amount(state, kind, step) - ceded;
+    }
+
+    /// Adds `amount` of a treaty flow to both buffers.
+    fn add_flow(&mut self, flow: TreatyFlow, state: usize, step: usize, amount: Amount) {
+        let kind = self.kind(flow).0;
+        *self.ceded.amount_mut(state, kind, step) += amount;
+        *self.net.amount_mut(state, kind, step) += amount;
+    }
+
+    /// Adds `amount` of an appended kind to both buffers.
+    fn add_extension(&mut self, kind: CashflowKindId, state: usize, step: usize, amount: Amount) {
+        let kind = kind.0;
+        *self.ceded.amount_mut(state, kind, step) += amount;
+        *self.net.amount_mut(state, kind, step) += amount;
+    }
+
+    /// Total of a treaty flow across states at `step`.
+    fn flow_at(&self, flow: TreatyFlow, step: usize) -> Amount {
+        let kind = self.kind(flow).0;
+        (0..self.ceded.n_states())
+            .map(|state| self.ceded.amount(state, kind, step))
+            .sum()
+    }
+}
@@ -0,0 +1,372 @@
+use super::{Cession, ReinsuranceError, TreatyFlow};
+use crate::product::{Amount, CashflowBuffer, CashflowCategory, ProductDefinition};
+
+/// How a proportional treaty sets the ceded share of a risk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProportionalCover {
+    /// Cedes `share` of every risk.
+    QuotaShare { share: f64 },
+    /// Cedes the part of the sum assured above `retention`, up to `lines`
+    /// times the retention.
+    Surplus { retention: f64, lines: f64 },
+}
+
+impl ProportionalCover {
+    /// Proportion of a risk with `sum_assured` that is ceded.
+    pub fn ceded_share(self, sum_assured: f64) -> f64 {
+        match self {
+            Self::QuotaShare { share } => share,
+            Self::Surplus { retention, lines } => {
+                if sum_assured <= retention {
+                    0.0
+                } else {
+                    (sum_assured - retention).min(lines * retention) / sum_assured
+                }
+            }
+        }
+    }
+}
+
+/// Basis of the premium paid to the reinsurer.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReinsurancePremium {
+    /// The ceded share of the gross premium kinds.
+    #[default]
+    OriginalTerms,
+    /// Agreed amounts by step, paid as [`TreatyFlow::ReinsurancePremium`];
+    /// gross premiums are then retained in full and steps beyond the schedule
+    /// pay nothing.
+    Schedule(Vec<Amount>),
+}
+
+/// Share of the reinsurer's profit returned to the cedant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfitCommission {
+    pub rate: f64,
+    /// Reinsurer's expenses as a fraction of the premium it receives.
+    pub management_expense: f64,
+    /// Steps per accounting period; the commission is paid at the last step
+    /// of each period and losses are carried forward.
+    pub period_steps: usize,
+}
+
+/// Quota share or surplus treaty sharing gross cashflows in proportion.
+///
+/// Kinds in the ceded categories (premiums and benefits by default) are
+/// ceded in the treaty's share; other kinds are retained. The ceding
+/// commission is a rate on the premium received by the reinsurer. Scheduled
+/// premiums and profit commissions are treaty-level flows written to the
+/// first state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProportionalTreaty {
+    cover: ProportionalCover,
+    categories: Vec<CashflowCategory>,
+    premium: ReinsurancePremium,
+    ceding_commission: f64,
+    profit_commission: Option<ProfitCommission>,
+}
+
+impl ProportionalTreaty {
+    pub fn quota_share(share: f64) -> Result<Self, ReinsuranceError> {
+        if !(0.0..=1.0).contains(&share) {
+            return Err(ReinsuranceError::InvalidParameter("share"));
+        }
+        Ok(Self::new(ProportionalCover::QuotaShare { share }))
+    }
+
+    /// Surplus treaty of `lines` lines of `retention` each.
+    pub fn surplus(retention: f64, lines: f64) -> Result<Self, ReinsuranceError> {
+        if !(retention.is_finite() && retention > 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("retention"));
+        }
+        if !(lines.is_finite() && lines >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("lines"));
+        }
+        Ok(Self::new(ProportionalCover::Surplus { retention, lines }))
+    }
+
+    fn new(cover: ProportionalCover) -> Self {
+        Self {
+            cover,
+            categories: vec![CashflowCategory::Premium, CashflowCategory::Benefit],
+            premium: ReinsurancePremium::OriginalTerms,
+            ceding_commission: 0.0,
+            profit_commission: None,
+        }
+    }
+
+    /// Categories of gross kinds that are ceded.
+    pub fn with_categories(mut self, categories: Vec<CashflowCategory>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    pub fn with_premium(mut self, premium: ReinsurancePremium) -> Self {
+        self.premium = premium;
+        self
+    }
+
+    pub fn with_ceding_commission(mut self, rate: f64) -> Result<Self, ReinsuranceError> {
+        if !(rate.is_finite() && rate >= 0.0) {
+            return Err(ReinsuranceError::InvalidParameter("ceding commission"));
+        }
+        self.ceding_commission = rate;
+        Ok(self)
+    }
+
+    pub fn with_profit_commission(
+        mut self,
+        commission: ProfitCommission,
+    ) -> Result<Self, ReinsuranceError> {
+        let valid = (0.0..=1.0).contains(&commission.rate)
+            && commission.management_expense.is_finite()
+            && commission.management_expense >= 0.0
+            && commission.period_steps > 0;
+        if !valid {
+            return Err(ReinsuranceError::InvalidParameter("profit commission"));
+        }
+        self.profit_commission = Some(commission);
+        Ok(self)
+    }
+
+    pub fn cover(&self) -> ProportionalCover {
+        self.cover
+    }
+
+    /// Cedes `gross`, the projection of a risk with `sum_assured`, which sets
+    /// the share of a surplus treaty.
+    pub fn apply(
+        &self,
+        definition: &ProductDefinition,
+        gross: &CashflowBuffer,
+        sum_assured: f64,
+    ) -> Result<Cession, ReinsuranceError> {
+        let mut cession = Cession::new(definition, gross, Vec::new())?;
+        let share = self.cover.ceded_share(sum_assured);
+        let scheduled = matches!(self.premium, ReinsurancePremium::Schedule(_));
+        let steps = gross.len_steps();
+        // Reinsurer's result by step: ceded flows signed from its side.
+        let mut result = vec![Amount::zero(); steps];
+        let mut premium = vec![Amount::zero(); steps];
+        for state in 0..definition.n_states {
+            for (kind, metadata) in definition.kinds().iter().enumerate() {
+                let ceded = self.categories.contains(&metadata.category)
+                    && !(scheduled && metadata.category == CashflowCategory::Premium);
+                for step in 0..steps {
+                    let amount = if ceded {
+                        gross.amount(state, kind, step) * share
+                    } else {
+                        Amount::zero()
+                    };
+                    cession.split(gross, state, kind, step, amount);
+                    result[step] += metadata.cash(amount);
+                    if metadata.category == CashflowCategory::Premium {
+                        premium[step] += amount;
+                        let commission = amount * self.ceding_commission;
+                        cession.add_flow(TreatyFlow::CedingCommission, state, step, commission);
+                    }
+                }
+            }
+        }
+        if let ReinsurancePremium::Schedule(schedule) = &self.premium {
+            for (step, &amount) in schedule.iter().take(steps).enumerate() {
+                cession.add_flow(TreatyFlow::ReinsurancePremium, 0, step, amount);
+                let commission = amount * self.ceding_commission;
+                cession.add_flow(TreatyFlow::CedingCommission, 0, step, commission);
+                result[step] += amount;
+                premium[step] += amount;
+            }
+        }
+        if let Some(commission) = self.profit_commission {
+            let mut carried = Amount::zero();
+            let mut period = Amount::zero();
+            for step in 0..steps {
+                period += result[step]
+                    - premium[step] * commission.management_expense
+                    - cession.flow_at(TreatyFlow::CedingCommission, step);
+                if (step + 1) % commission.period_steps == 0 || step + 1 == steps {
+                    let profit = period - carried;
+                    if profit.value() > 0.0 {
+                        let paid = profit * commission.rate;
+                        cession.add_flow(TreatyFlow::ProfitCommission, 0, step, paid);
+                        carried = Amount::zero();
+                    } else {
+                        carried = -profit;
+                    }
+                    period = Amount::zero();
+                }
+            }
+        }
+        Ok(cession)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{FlowDirection, KindMetadata, RequiredDataLayout};
+    use crate::{Date, Frequency, generate_cashflow_dates};
+
+    fn definition() -> ProductDefinition {
+        ProductDefinition::named(
+            vec!["active".into(), "dead".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit),
+                KindMetadata::new("expense", FlowDirection::Outflow, CashflowCategory::Expense),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// Premiums of 100 and expenses of 10 in `active`, claims of `claims`
+    /// booked to `dead`.
+    fn gross(claims: [f64; 4]) -> CashflowBuffer {
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 4, Frequency::Annual).unwrap();
+        let mut gross = CashflowBuffer::new(2, 3, times).unwrap();
+        gross.series_mut(0, 0).fill(Amount::from_f64(100.0));
+        gross.series_mut(0, 2).fill(Amount::from_f64(10.0));
+        for (amount, claim) in gross.series_mut(1, 1).iter_mut().zip(claims) {
+            *amount = Amount::from_f64(claim);
+        }
+        gross
+    }
+
+    fn values(buffer: &CashflowBuffer, state: usize, kind: usize) -> Vec<f64> {
+        buffer
+            .series(state, kind)
+            .iter()
+            .map(|a| a.value())
+            .collect()
+    }
+
+    fn signed_total(definition: &ProductDefinition, buffer: &CashflowBuffer) -> f64 {
+        let mut total = 0.0;
+        for (kind, metadata) in definition.kinds().iter().enumerate() {
+            for state in 0..buffer.n_states() {
+                for &amount in buffer.series(state, kind) {
+                    total += metadata.cash(amount).value();
+                }
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn quota_share_splits_premiums_and_claims() {
+        let definition = definition();
+        let gross = gross([50.0, 0.0, 200.0, 0.0]);
+        let cession = ProportionalTreaty::quota_share(0.4)
+            .unwrap()
+            .with_ceding_commission(0.25)
+            .unwrap()
+            .apply(&definition, &gross, 1.0e6)
+            .unwrap();
+        assert_eq!(cession.definition.n_kinds, 7);
+        assert_eq!(cession.gross_kinds(), 3);
+        assert_eq!(
+            cession.definition.kind_id("ceding_commission"),
+            Some(cession.kind(TreatyFlow::CedingCommission))
+        );
+        assert_eq!(values(&cession.ceded, 0, 0), [40.0; 4]);
+        assert_eq!(values(&cession.net, 0, 0), [60.0; 4]);
+        assert_eq!(values(&cession.ceded, 1, 1), [20.0, 0.0, 80.0, 0.0]);
+        assert_eq!(values(&cession.net, 1, 1), [30.0, 0.0, 120.0, 0.0]);
+        assert_eq!(values(&cession.ceded, 0, 2), [0.0; 4]);
+        assert_eq!(values(&cession.net, 0, 2), [10.0; 4]);
+        let commission = cession.kind(TreatyFlow::CedingCommission).0;
+        assert_eq!(values(&cession.net, 0, commission), [10.0; 4]);
+
+        // Net result = gross - ceded premiums + ceded claims + commission.
+        let net = signed_total(&cession.definition, &cession.net);
+        let gross_total = signed_total(&definition, &gross);
+        assert!((net - (gross_total - 160.0 + 100.0 + 40.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn surplus_cedes_lines_above_retention() {
+        let cover = ProportionalTreaty::surplus(100_000.0, 4.0).unwrap().cover();
+        assert_eq!(cover.ceded_share(50_000.0), 0.0);
+        assert!((cover.ceded_share(250_000.0) - 0.6).abs() < 1e-12);
+        assert!((cover.ceded_share(700_000.0) - 400.0 / 700.0).abs() < 1e-12);
+
+        let definition = definition();
+        let gross = gross([0.0, 500.0, 0.0, 0.0]);
+        let treaty = ProportionalTreaty::surplus(100_000.0, 4.0)
+            .unwrap()
+            .with_profit_commission(ProfitCommission {
+                rate: 0.5,
+                management_expense: 0.1,
+                period_steps: 2,
+            })
+            .unwrap();
+        let cession = treaty.apply(&definition, &gross, 250_000.0).unwrap();
+        assert_eq!(values(&cession.ceded, 0, 0), [60.0; 4]);
+        assert_eq!(values(&cession.ceded, 1, 1), [0.0, 300.0, 0.0, 0.0]);
+        // First period: 120 - 12 - 300 = -192 carried forward; second:
+        // 120 - 12 - 192 = -84, so no commission is paid.
+        let profit = cession.kind(TreatyFlow::ProfitCommission).0;
+        assert_eq!(values(&cession.net, 0, profit), [0.0; 4]);
+
+        let cession = treaty
+            .with_premium(ReinsurancePremium::Schedule(vec![
+                Amount::from_f64(200.0);
+                3
+            ]))
+            .apply(&definition, &gross, 250_000.0)
+            .unwrap();
+        assert_eq!(values(&cession.ceded, 0, 0), [0.0; 4]);
+        assert_eq!(values(&cession.net, 0, 0), [100.0; 4]);
+        let premium = cession.kind(TreatyFlow::ReinsurancePremium).0;
+        assert_eq!(values(&cession.net, 0, premium), [200.0, 200.0, 200.0, 0.0]);
+        // Periods: 360 - 300 = 60 and 180, half returned.
+        assert_eq!(values(&cession.net, 0, profit), [0.0, 30.0, 0.0, 90.0]);
+    }
+
+    #[test]
+    fn rejects_invalid_treaties_and_buffers() {
+        assert!(ProportionalTreaty::quota_share(1.5).is_err());
+        assert!(ProportionalTreaty::surplus(0.0, 2.0).is_err());
+        assert!(ProportionalTreaty::surplus(1.0, f64::NAN).is_err());
+        let treaty = ProportionalTreaty::quota_share(0.5).unwrap();
+        assert!(treaty.clone().with_ceding_commission(-0.1).is_err());
+        assert!(
+            treaty
+                .clone()
+                .with_profit_commission(ProfitCommission {
+                    rate: 0.5,
+                    management_expense: 0.0,
+                    period_steps: 0,
+                })
+                .is_err()
+        );
+
+        let times =
+            generate_cashflow_dates(Date::constant(2024, 1, 1), 2, Frequency::Annual).unwrap();
+        let mismatched = CashflowBuffer::new(2, 2, times.clone()).unwrap();
+        assert_eq!(
+            treaty.apply(&definition(), &mismatched, 0.0).unwrap_err(),
+            ReinsuranceError::Shape
+        );
+        let clash = ProductDefinition::named(
+            vec!["active".into()],
+            vec![KindMetadata::new(
+                "ceding_commission",
+                FlowDirection::Inflow,
+                CashflowCategory::Commission,
+            )],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let gross = CashflowBuffer::new(1, 1, times).unwrap();
+        assert_eq!(
+            treaty.apply(&clash, &gross, 0.0).unwrap_err(),
+            ReinsuranceError::KindName
+        );
+    }
+}
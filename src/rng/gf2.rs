@@ -0,0 +1,208 @@
+//! Polynomial arithmetic over GF(2) for jump-ahead of F2-linear generators.
+//!
+//! An F2-linear generator with transition matrix `A` advances by `delta` steps
+//! via `A^delta s = q(A) s`, where `q(x) = x^delta mod p(x)` and `p` is the
+//! minimal polynomial of the output sequence. `q(A) s` is evaluated by summing
+//! the states `A^i s` for every non-zero coefficient of `q`.
+
+/// Packed polynomial: bit `i % 64` of word `i / 64` is the coefficient of `x^i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Gf2Poly {
+    words: Vec<u64>,
+}
+
+impl Gf2Poly {
+    pub(crate) fn one() -> Self {
+        Self { words: vec![1] }
+    }
+
+    pub(crate) fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub(crate) fn degree(&self) -> Option<usize> {
+        self.words
+            .iter()
+            .rposition(|&w| w != 0)
+            .map(|i| i * 64 + 63 - self.words[i].leading_zeros() as usize)
+    }
+
+    #[inline]
+    pub(crate) fn coeff(&self, i: usize) -> bool {
+        self.words
+            .get(i / 64)
+            .is_some_and(|&w| (w >> (i % 64)) & 1 == 1)
+    }
+
+    /// Returns `x^exponent mod self`.
+    pub(crate) fn pow_x_mod(&self, exponent: u128) -> Self {
+        let mut acc = Self::one();
+        let bits = 128 - exponent.leading_zeros();
+        for bit in (0..bits).rev() {
+            acc = acc.square_mod(self);
+            if (exponent >> bit) & 1 == 1 {
+                acc = acc.shl1();
+                acc.reduce(self);
+            }
+        }
+        acc
+    }
+
+    /// Returns `self^2 mod modulus`.
+    pub(crate) fn square_mod(&self, modulus: &Self) -> Self {
+        let mut sq = self.square();
+        sq.reduce(modulus);
+        sq
+    }
+
+    fn square(&self) -> Self {
+        let mut words = Vec::with_capacity(self.words.len() * 2);
+        for &w in &self.words {
+            words.push(spread(w as u32));
+            words.push(spread((w >> 32) as u32));
+        }
+        Self { words }
+    }
+
+    fn shl1(&self) -> Self {
+        let mut words = Vec::with_capacity(self.words.len() + 1);
+        let mut carry = 0u64;
+        for &w in &self.words {
+            words.push((w << 1) | carry);
+            carry = w >> 63;
+        }
+        words.push(carry);
+        Self { words }
+    }
+
+    fn reduce(&mut self, modulus: &Self) {
+        let d = modulus.degree().expect("modulus must be non-zero");
+        if let Some(top) = self.degree() {
+            for i in (d..=top).rev() {
+                if self.coeff(i) {
+                    self.xor_shifted(modulus, i - d);
+                }
+            }
+        }
+        self.words.truncate(d.div_ceil(64).max(1));
+    }
+
+    fn xor_shifted(&mut self, other: &Self, shift: usize) {
+        let word_shift = shift / 64;
+        let bit_shift = shift % 64;
+        let needed = other.words.len() + word_shift + 1;
+        if self.words.len() < needed {
+            self.words.resize(needed, 0);
+        }
+        for (i, &w) in other.words.iter().enumerate() {
+            self.words[i + word_shift] ^= w << bit_shift;
+            if bit_shift != 0 {
+                self.words[i + word_shift + 1] ^= w >> (64 - bit_shift);
+            }
+        }
+    }
+
+    /// Parity of `sum_k self_k * bits[offset + k]` for `k < len`.
+    fn dot_window(&self, bits: &[u64], offset: usize, len: usize) -> bool {
+        let mut acc = 0u64;
+        let n_words = len.div_ceil(64).min(self.words.len());
+        for (w, &coeffs) in self.words[..n_words].iter().enumerate() {
+            let pos = offset + w * 64;
+            let lo = bits[pos / 64] >> (pos % 64);
+            let hi = if pos.is_multiple_of(64) {
+                0
+            } else {
+                bits[pos / 64 + 1] << (64 - pos % 64)
+            };
+            let remaining = len - w * 64;
+            let mask = if remaining >= 64 {
+                u64::MAX
+            } else {
+                (1u64 << remaining) - 1
+            };
+            acc ^= coeffs & (lo | hi) & mask;
+        }
+        acc.count_ones() & 1 == 1
+    }
+}
+
+/// Berlekamp-Massey: returns the characteristic polynomial `p` of the shortest
+/// linear recurrence generating `sequence`, normalised so that
+/// `sum_j p_j s_{n+j} = 0`.
+pub(crate) fn minimal_polynomial(sequence: &[bool]) -> Gf2Poly {
+    let n = sequence.len();
+    let mut reversed = vec![0u64; n / 64 + 2];
+    for (i, &bit) in sequence.iter().enumerate() {
+        if bit {
+            let j = n - 1 - i;
+            reversed[j / 64] |= 1u64 << (j % 64);
+        }
+    }
+
+    let mut c = Gf2Poly::one();
+    let mut b = Gf2Poly::one();
+    let mut l = 0usize;
+    let mut m = 1usize;
+    for i in 0..n {
+        if !c.dot_window(&reversed, n - 1 - i, l + 1) {
+            m += 1;
+        } else if 2 * l <= i {
+            let t = c.clone();
+            c.xor_shifted(&b, m);
+            l = i + 1 - l;
+            b = t;
+            m = 1;
+        } else {
+            c.xor_shifted(&b, m);
+            m += 1;
+        }
+    }
+
+    let mut words = vec![0u64; l / 64 + 1];
+    for j in 0..=l {
+        if c.coeff(l - j) {
+            words[j / 64] |= 1u64 << (j % 64);
+        }
+    }
+    Gf2Poly { words }
+}
+
+#[inline]
+fn spread(x: u32) -> u64 {
+    let mut v = x as u64;
+    v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+    v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+    v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+    v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+    v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_polynomial_recovers_lfsr_recurrence() {
+        // s_{n+4} = s_{n+1} + s_n has characteristic polynomial x^4 + x + 1.
+        let mut seq = vec![true, false, false, false];
+        for n in 0..28 {
+            let next = seq[n + 1] ^ seq[n];
+            seq.push(next);
+        }
+        let p = minimal_polynomial(&seq);
+        assert_eq!(p.words(), &[0b10011]);
+        assert_eq!(p.degree(), Some(4));
+    }
+
+    #[test]
+    fn pow_x_mod_matches_repeated_multiplication() {
+        let p = Gf2Poly {
+            words: vec![0b10011],
+        };
+        // x^15 = 1 mod x^4 + x + 1 (primitive polynomial of order 15).
+        assert_eq!(p.pow_x_mod(15), Gf2Poly::one());
+        assert_eq!(p.pow_x_mod(4).words(), &[0b0011]);
+        assert_eq!(p.pow_x_mod(0), Gf2Poly::one());
+    }
+}
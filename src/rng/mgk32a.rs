@@ -1,6 +1,6 @@
 use std::convert::Infallible;
 
-use crate::rng::{BlockSplit, JumpAhead, RngCore};
+use crate::rng::{BlockSplit, JumpAhead, RngCore, SplitMix64};
 
 const M1: u64 = 4_294_967_087;
 const M2: u64 = 4_294_944_443;
@@ -56,13 +56,7 @@ impl Mgk32a {
         if delta == 0 {
             return;
         }
-        let m1 = Matrix3::mrg32k3a_m1();
-        let m2 = Matrix3::mrg32k3a_m2();
-        let m1p = m1.pow(delta, M1);
-        let m2p = m2.pow(delta, M2);
-
-        self.s1 = m1p.mul_vec(self.s1, M1);
-        self.s2 = m2p.mul_vec(self.s2, M2);
+        Mgk32aJump::new(delta).apply(self);
     }
 
     pub fn for_stream(seed: [u64; 6], stream: u128, stride: u128) -> Result<Self, SeedError> {
@@ -72,6 +66,181 @@ impl Mgk32a {
     }
 }
 
+/// Precomputed jump `A^delta` for both MRG32k3a components.
+///
+/// Building a jump costs one matrix exponentiation per component; applying it
+/// costs one matrix-vector product per component. Reuse a jump when many
+/// streams share the same stride.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mgk32aJump {
+    m1: Matrix3,
+    m2: Matrix3,
+}
+
+impl Mgk32aJump {
+    pub fn new(delta: u128) -> Self {
+        Self {
+            m1: Matrix3::mrg32k3a_m1().pow(delta, M1),
+            m2: Matrix3::mrg32k3a_m2().pow(delta, M2),
+        }
+    }
+
+    /// Jump of `2^127` steps separating RngStreams streams.
+    pub const fn stream() -> Self {
+        Self {
+            m1: Matrix3::from_rows([
+                [1_230_515_664, 986_791_581, 1_988_835_001],
+                [3_580_155_704, 1_230_515_664, 226_153_695],
+                [949_770_784, 3_580_155_704, 2_427_906_178],
+            ]),
+            m2: Matrix3::from_rows([
+                [2_093_834_863, 32_183_930, 2_824_425_944],
+                [1_022_607_788, 1_464_411_153, 32_183_930],
+                [1_610_723_613, 277_697_599, 1_464_411_153],
+            ]),
+        }
+    }
+
+    /// Jump of `2^76` steps separating RngStreams substreams.
+    pub const fn substream() -> Self {
+        Self {
+            m1: Matrix3::from_rows([
+                [69_195_019, 3_528_743_235, 3_672_091_415],
+                [1_871_391_091, 69_195_019, 3_672_831_523],
+                [4_127_413_238, 1_871_391_091, 82_758_667],
+            ]),
+            m2: Matrix3::from_rows([
+                [3_708_466_080, 4_292_754_251, 3_859_662_829],
+                [3_889_917_532, 1_511_326_704, 4_292_754_251],
+                [1_610_795_712, 3_759_209_742, 1_511_326_704],
+            ]),
+        }
+    }
+
+    /// Returns the jump applied `times` times in succession.
+    pub fn repeat(&self, times: u128) -> Self {
+        Self {
+            m1: self.m1.pow(times, M1),
+            m2: self.m2.pow(times, M2),
+        }
+    }
+
+    #[inline]
+    pub fn apply(&self, rng: &mut Mgk32a) {
+        rng.s1 = self.m1.mul_vec(rng.s1, M1);
+        rng.s2 = self.m2.mul_vec(rng.s2, M2);
+    }
+}
+
+/// RngStreams-style stream factory (L'Ecuyer, Simard, Chen & Kelton, 2002).
+///
+/// Each call to [`Mgk32aStreams::next_stream`] returns the stream starting at the
+/// current seed and moves the seed forward by the stream jump, so creating a
+/// stream costs one matrix-vector product.
+#[derive(Debug, Clone, Copy)]
+pub struct Mgk32aStreams {
+    next: Mgk32a,
+    jump: Mgk32aJump,
+}
+
+impl Mgk32aStreams {
+    /// Streams separated by `2^127` steps, as in RngStreams.
+    pub fn new(seed: [u64; 6]) -> Result<Self, SeedError> {
+        Self::with_jump(seed, Mgk32aJump::stream())
+    }
+
+    /// Streams separated by `stride` steps, aligned with [`Mgk32a::for_stream`].
+    pub fn with_stride(seed: [u64; 6], stride: u128) -> Result<Self, SeedError> {
+        Self::with_jump(seed, Mgk32aJump::new(stride))
+    }
+
+    pub fn with_jump(seed: [u64; 6], jump: Mgk32aJump) -> Result<Self, SeedError> {
+        Ok(Self {
+            next: Mgk32a::new(seed)?,
+            jump,
+        })
+    }
+
+    /// Seed of the stream returned by the next call to `next_stream`.
+    #[inline]
+    pub fn next_seed(&self) -> [u64; 6] {
+        self.next.state()
+    }
+
+    pub fn next_stream(&mut self) -> Mgk32aStream {
+        let stream = Mgk32aStream::from_rng(self.next);
+        self.jump.apply(&mut self.next);
+        stream
+    }
+}
+
+impl Iterator for Mgk32aStreams {
+    type Item = Mgk32aStream;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_stream())
+    }
+}
+
+/// A single RngStreams stream partitioned into substreams of `2^76` steps.
+///
+/// Tracks the stream start, the current substream start, and the current
+/// position, mirroring the `Ig`, `Bg` and `Cg` seeds of RngStreams.
+#[derive(Debug, Clone, Copy)]
+pub struct Mgk32aStream {
+    start: Mgk32a,
+    substream: Mgk32a,
+    current: Mgk32a,
+}
+
+impl Mgk32aStream {
+    pub fn new(seed: [u64; 6]) -> Result<Self, SeedError> {
+        Ok(Self::from_rng(Mgk32a::new(seed)?))
+    }
+
+    fn from_rng(rng: Mgk32a) -> Self {
+        Self {
+            start: rng,
+            substream: rng,
+            current: rng,
+        }
+    }
+
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        self.current.next_f64()
+    }
+
+    #[inline]
+    pub fn state(&self) -> [u64; 6] {
+        self.current.state()
+    }
+
+    /// Rewinds to the beginning of the stream.
+    pub fn reset_start_stream(&mut self) {
+        self.substream = self.start;
+        self.current = self.start;
+    }
+
+    /// Rewinds to the beginning of the current substream.
+    pub fn reset_start_substream(&mut self) {
+        self.current = self.substream;
+    }
+
+    /// Moves to the beginning of the next substream.
+    pub fn reset_next_substream(&mut self) {
+        Mgk32aJump::substream().apply(&mut self.substream);
+        self.current = self.substream;
+    }
+}
+
+impl RngCore for Mgk32aStream {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.current.next_u32()
+    }
+}
+
 impl RngCore for Mgk32a {
     #[inline]
     fn next_u32(&mut self) -> u32 {
@@ -125,7 +294,7 @@ fn mod_m(value: i128, modulus: u64) -> u64 {
     v as u64
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Matrix3 {
     a00: u64,
     a01: u64,
@@ -139,6 +308,20 @@ struct Matrix3 {
 }
 
 impl Matrix3 {
+    const fn from_rows(rows: [[u64; 3]; 3]) -> Self {
+        Self {
+            a00: rows[0][0],
+            a01: rows[0][1],
+            a02: rows[0][2],
+            a10: rows[1][0],
+            a11: rows[1][1],
+            a12: rows[1][2],
+            a20: rows[2][0],
+            a21: rows[2][1],
+            a22: rows[2][2],
+        }
+    }
+
     const fn mrg32k3a_m1() -> Self {
         Self {
             a00: 0,
@@ -252,24 +435,6 @@ impl Matrix3 {
     }
 }
 
-struct SplitMix64 {
-    state: u64,
-}
-
-impl SplitMix64 {
-    fn new(seed: u64) -> Self {
-        Self { state: seed }
-    }
-
-    fn next_u64(&mut self) -> u64 {
-        let mut z = self.state.wrapping_add(0x9E3779B97F4A7C15);
-        self.state = z;
-        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
-        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
-        z ^ (z >> 31)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,4 +528,70 @@ mod tests {
         }
         assert_eq!(base.state(), split.state());
     }
+
+    #[test]
+    fn mgk32a_rngstreams_jumps_match_exponentiation() {
+        assert_eq!(Mgk32aJump::stream(), Mgk32aJump::new(1u128 << 127));
+        assert_eq!(Mgk32aJump::substream(), Mgk32aJump::new(1u128 << 76));
+        assert_eq!(
+            Mgk32aJump::substream().repeat(1u128 << 51),
+            Mgk32aJump::stream()
+        );
+    }
+
+    #[test]
+    fn mgk32a_jump_apply_matches_advance() {
+        let seed = [3, 5, 7, 11, 13, 17];
+        let jump = Mgk32aJump::new(12_345);
+        let mut jumped = Mgk32a::new(seed).unwrap();
+        let mut advanced = Mgk32a::new(seed).unwrap();
+        jump.apply(&mut jumped);
+        advanced.advance(12_345);
+        assert_eq!(jumped.state(), advanced.state());
+    }
+
+    #[test]
+    fn mgk32a_streams_match_for_stream() {
+        let seed = [7, 11, 13, 17, 19, 23];
+        let stride = 1_000u128;
+        let streams = Mgk32aStreams::with_stride(seed, stride).unwrap();
+        for (index, stream) in streams.take(4).enumerate() {
+            let expected = Mgk32a::for_stream(seed, index as u128, stride).unwrap();
+            assert_eq!(stream.state(), expected.state());
+        }
+    }
+
+    #[test]
+    fn mgk32a_streams_default_to_rngstreams_spacing() {
+        let seed = [12345; 6];
+        let mut streams = Mgk32aStreams::new(seed).unwrap();
+        let first = streams.next_stream();
+        assert_eq!(first.state(), seed);
+        let mut expected = Mgk32a::new(seed).unwrap();
+        expected.advance(1u128 << 127);
+        assert_eq!(streams.next_seed(), expected.state());
+    }
+
+    #[test]
+    fn mgk32a_stream_substream_resets() {
+        let seed = [1, 2, 3, 4, 5, 6];
+        let mut stream = Mgk32aStream::new(seed).unwrap();
+        let first = stream.next_u32();
+        stream.next_u32();
+        stream.reset_start_substream();
+        assert_eq!(stream.next_u32(), first);
+
+        stream.reset_next_substream();
+        let mut expected = Mgk32a::new(seed).unwrap();
+        expected.advance(1u128 << 76);
+        assert_eq!(stream.state(), expected.state());
+
+        let substream_first = stream.next_u32();
+        stream.reset_start_substream();
+        assert_eq!(stream.next_u32(), substream_first);
+
+        stream.reset_start_stream();
+        assert_eq!(stream.state(), seed);
+        assert_eq!(stream.next_u32(), first);
+    }
 }
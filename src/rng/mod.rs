@@ -1,5 +1,9 @@
+mod gf2;
 pub mod mgk32a;
+pub mod mt19937;
+pub mod pcg64;
 pub mod sobol;
+pub mod xoshiro256;
 
 /// Deterministic jump-ahead for reproducible streams.
 pub trait JumpAhead {
@@ -46,9 +50,44 @@ pub trait RngCore {
     }
 }
 
+/// Uniform draw on `[0, 1)` from the top 53 bits of [`RngCore::next_u64`].
+#[inline]
+pub fn next_uniform<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    (rng.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Standard normal draw by the Box–Muller transform, using two uniforms and
+/// discarding the second normal.
+#[inline]
+pub fn next_standard_normal<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    let u1 = 1.0 - next_uniform(rng);
+    let u2 = next_uniform(rng);
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+/// SplitMix64 seed expander used to derive full generator states from a `u64`.
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        let mut z = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        self.state = z;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::RngCore;
+    use super::{RngCore, next_standard_normal};
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
 
     struct CounterRng {
         next: u32,
@@ -95,4 +134,16 @@ mod tests {
         expected[8] = second[0];
         assert_eq!(buffer, expected);
     }
+
+    #[test]
+    fn standard_normals_have_unit_moments() {
+        let mut rng = Xoshiro256StarStar::from_seed64(11);
+        let n = 100_000;
+        let draws: Vec<f64> = (0..n).map(|_| next_standard_normal(&mut rng)).collect();
+        let mean = draws.iter().sum::<f64>() / n as f64;
+        let variance = draws.iter().map(|z| (z - mean).powi(2)).sum::<f64>() / n as f64;
+        assert!(mean.abs() < 0.02);
+        assert!((variance - 1.0).abs() < 0.02);
+        assert!(draws.iter().all(|z| z.is_finite()));
+    }
 }
@@ -0,0 +1,292 @@
+use std::convert::Infallible;
+use std::sync::OnceLock;
+
+use crate::rng::gf2::{Gf2Poly, minimal_polynomial};
+use crate::rng::{BlockSplit, JumpAhead, RngCore};
+
+const N: usize = 624;
+const M: usize = 397;
+const MATRIX_A: u32 = 0x9908_b0df;
+const UPPER_MASK: u32 = 0x8000_0000;
+const LOWER_MASK: u32 = 0x7fff_ffff;
+const DEFAULT_SEED: u32 = 5489;
+
+/// Jumps shorter than this are applied by stepping the generator directly.
+const POLYNOMIAL_JUMP_THRESHOLD: u128 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedError;
+
+/// MT19937 32-bit Mersenne Twister (Matsumoto & Nishimura), as in `mt19937ar.c`.
+///
+/// Intended for reproducing legacy runs; one step produces one 32-bit output.
+/// Jump-ahead uses the characteristic polynomial of the generator, so long
+/// jumps cost polynomial arithmetic of degree 19937 rather than `delta` steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mt19937 {
+    mt: [u32; N],
+    index: usize,
+}
+
+impl Mt19937 {
+    /// Seeds the generator as `init_genrand(seed)`.
+    pub fn new(seed: u32) -> Self {
+        let mut mt = [0u32; N];
+        mt[0] = seed;
+        for i in 1..N {
+            mt[i] = 1_812_433_253u32
+                .wrapping_mul(mt[i - 1] ^ (mt[i - 1] >> 30))
+                .wrapping_add(i as u32);
+        }
+        Self { mt, index: N }
+    }
+
+    /// Seeds the generator as `init_by_array(key)`.
+    pub fn from_key(key: &[u32]) -> Result<Self, SeedError> {
+        if key.is_empty() {
+            return Err(SeedError);
+        }
+        let mut rng = Self::new(19_650_218);
+        let mt = &mut rng.mt;
+        let mut i = 1usize;
+        let mut j = 0usize;
+        for _ in 0..N.max(key.len()) {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1_664_525))
+                .wrapping_add(key[j])
+                .wrapping_add(j as u32);
+            i += 1;
+            j += 1;
+            if i >= N {
+                mt[0] = mt[N - 1];
+                i = 1;
+            }
+            if j >= key.len() {
+                j = 0;
+            }
+        }
+        for _ in 0..N - 1 {
+            mt[i] = (mt[i] ^ (mt[i - 1] ^ (mt[i - 1] >> 30)).wrapping_mul(1_566_083_941))
+                .wrapping_sub(i as u32);
+            i += 1;
+            if i >= N {
+                mt[0] = mt[N - 1];
+                i = 1;
+            }
+        }
+        mt[0] = UPPER_MASK;
+        Ok(rng)
+    }
+
+    /// Returns a double in `[0, 1)` with 53-bit resolution (`genrand_res53`).
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        let a = (self.next_u32() >> 5) as f64;
+        let b = (self.next_u32() >> 6) as f64;
+        (a * 67_108_864.0 + b) * (1.0 / 9_007_199_254_740_992.0)
+    }
+
+    pub fn advance(&mut self, delta: u128) {
+        let mut remaining = delta;
+        while remaining > 0 && self.index < N {
+            self.index += 1;
+            remaining -= 1;
+        }
+        if remaining < POLYNOMIAL_JUMP_THRESHOLD {
+            for _ in 0..remaining {
+                self.next_word();
+            }
+            return;
+        }
+
+        // With `index == N`, `mt` is a ring whose oldest word sits at position 0.
+        let poly = characteristic_polynomial().pow_x_mod(remaining);
+        let mut acc = [0u32; N];
+        let mut ring = self.mt;
+        let mut pos = 0usize;
+        for i in 0..=poly.degree().unwrap_or(0) {
+            if poly.coeff(i) {
+                for (j, a) in acc.iter_mut().enumerate() {
+                    *a ^= ring[(pos + j) % N];
+                }
+            }
+            ring_step(&mut ring, &mut pos);
+        }
+        self.mt = acc;
+        self.index = N;
+    }
+
+    pub fn for_stream(seed: u32, stream: u128, stride: u128) -> Self {
+        let mut rng = Self::new(seed);
+        rng.advance(stream.saturating_mul(stride));
+        rng
+    }
+
+    #[inline]
+    fn next_word(&mut self) -> u32 {
+        if self.index >= N {
+            self.twist();
+        }
+        let y = self.mt[self.index];
+        self.index += 1;
+        y
+    }
+
+    fn twist(&mut self) {
+        for i in 0..N {
+            self.mt[i] = twist_word(self.mt[i], self.mt[(i + 1) % N], self.mt[(i + M) % N]);
+        }
+        self.index = 0;
+    }
+}
+
+impl Default for Mt19937 {
+    fn default() -> Self {
+        Self::new(DEFAULT_SEED)
+    }
+}
+
+impl RngCore for Mt19937 {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        let mut y = self.next_word();
+        y ^= y >> 11;
+        y ^= (y << 7) & 0x9d2c_5680;
+        y ^= (y << 15) & 0xefc6_0000;
+        y ^ (y >> 18)
+    }
+}
+
+impl JumpAhead for Mt19937 {
+    type Error = Infallible;
+
+    fn advance(&mut self, delta: u128) -> Result<(), Self::Error> {
+        Mt19937::advance(self, delta);
+        Ok(())
+    }
+}
+
+impl BlockSplit for Mt19937 {
+    type Seed = u32;
+    type Error = Infallible;
+
+    fn for_stream(seed: Self::Seed, stream: u128, stride: u128) -> Result<Self, Self::Error> {
+        Ok(Mt19937::for_stream(seed, stream, stride))
+    }
+}
+
+#[inline]
+fn twist_word(current: u32, next: u32, far: u32) -> u32 {
+    let y = (current & UPPER_MASK) | (next & LOWER_MASK);
+    let mag = if y & 1 == 1 { MATRIX_A } else { 0 };
+    far ^ (y >> 1) ^ mag
+}
+
+#[inline]
+fn ring_step(ring: &mut [u32; N], pos: &mut usize) {
+    let p = *pos;
+    ring[p] = twist_word(ring[p], ring[(p + 1) % N], ring[(p + M) % N]);
+    *pos = (p + 1) % N;
+}
+
+/// Characteristic polynomial of the MT19937 recurrence (degree 19937).
+fn characteristic_polynomial() -> &'static Gf2Poly {
+    static POLY: OnceLock<Gf2Poly> = OnceLock::new();
+    POLY.get_or_init(|| {
+        let mut ring = Mt19937::new(DEFAULT_SEED).mt;
+        let mut pos = 0usize;
+        let bits: Vec<bool> = (0..2 * 19_937)
+            .map(|_| {
+                ring_step(&mut ring, &mut pos);
+                ring[(pos + N - 1) % N] & 1 == 1
+            })
+            .collect();
+        minimal_polynomial(&bits)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mt19937_matches_reference_output() {
+        let mut rng = Mt19937::default();
+        let expected = [3_499_211_612, 581_869_302, 3_890_346_734, 3_586_334_585];
+        for &value in &expected {
+            assert_eq!(rng.next_u32(), value);
+        }
+        // The C++ standard requires the 10000th output of a default mt19937.
+        for _ in expected.len()..9_999 {
+            rng.next_u32();
+        }
+        assert_eq!(rng.next_u32(), 4_123_659_995);
+    }
+
+    #[test]
+    fn mt19937_init_by_array_matches_mt19937ar_out() {
+        let mut rng = Mt19937::from_key(&[0x123, 0x234, 0x345, 0x456]).unwrap();
+        let expected = [1_067_595_299, 955_945_823, 477_289_528, 4_107_218_783];
+        for &value in &expected {
+            assert_eq!(rng.next_u32(), value);
+        }
+    }
+
+    #[test]
+    fn mt19937_rejects_empty_key() {
+        assert!(Mt19937::from_key(&[]).is_err());
+    }
+
+    #[test]
+    fn mt19937_characteristic_polynomial_has_full_degree() {
+        assert_eq!(characteristic_polynomial().degree(), Some(19_937));
+    }
+
+    #[test]
+    fn mt19937_short_advance_matches_iter() {
+        let mut advanced = Mt19937::new(17);
+        let mut iterated = advanced.clone();
+        advanced.next_u32();
+        iterated.next_u32();
+        advanced.advance(1_000);
+        for _ in 0..1_000 {
+            iterated.next_u32();
+        }
+        assert_eq!(advanced, iterated);
+    }
+
+    #[test]
+    fn mt19937_polynomial_advance_matches_iter() {
+        let delta = 100_003u128;
+        let mut advanced = Mt19937::new(42);
+        let mut iterated = advanced.clone();
+        advanced.next_u32();
+        iterated.next_u32();
+        advanced.advance(delta);
+        for _ in 0..delta {
+            iterated.next_u32();
+        }
+        for _ in 0..N + 5 {
+            assert_eq!(advanced.next_u32(), iterated.next_u32());
+        }
+    }
+
+    #[test]
+    fn mt19937_next_f64_stays_in_unit_interval() {
+        let mut rng = Mt19937::new(1);
+        for _ in 0..10 {
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn mt19937_block_splitting_aligns_streams() {
+        let stride = 1_000u128;
+        let mut base = Mt19937::for_stream(99, 0, stride);
+        let mut split = Mt19937::for_stream(99, 1, stride);
+        for _ in 0..stride {
+            base.next_u32();
+        }
+        assert_eq!(base.next_u32(), split.next_u32());
+    }
+}
@@ -0,0 +1,253 @@
+use std::convert::Infallible;
+use std::sync::OnceLock;
+
+use crate::rng::gf2::{Gf2Poly, minimal_polynomial};
+use crate::rng::{BlockSplit, JumpAhead, RngCore, SplitMix64};
+
+/// Coefficients of `x^(2^128) mod p(x)` published with the reference implementation.
+const JUMP: [u64; 4] = [
+    0x180e_c6d3_3cfd_0aba,
+    0xd5a6_1266_f0c9_392c,
+    0xa958_2618_e03f_c9aa,
+    0x39ab_dc45_29b1_661c,
+];
+
+/// Coefficients of `x^(2^192) mod p(x)` published with the reference implementation.
+const LONG_JUMP: [u64; 4] = [
+    0x76e1_5d3e_fefd_cbbf,
+    0xc500_4e44_1c52_2fb3,
+    0x7771_0069_854e_e241,
+    0x3910_9bb0_2acb_e635,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedError;
+
+/// xoshiro256** 64-bit generator (Blackman & Vigna).
+///
+/// One step produces one 64-bit output; `next_u32` returns its upper half.
+#[derive(Debug, Clone, Copy)]
+pub struct Xoshiro256StarStar {
+    s: [u64; 4],
+}
+
+impl Xoshiro256StarStar {
+    pub fn new(seed: [u64; 4]) -> Result<Self, SeedError> {
+        if seed == [0; 4] {
+            return Err(SeedError);
+        }
+        Ok(Self { s: seed })
+    }
+
+    pub fn from_seed64(seed: u64) -> Self {
+        let mut sm = SplitMix64::new(seed);
+        let mut s = [0u64; 4];
+        for v in &mut s {
+            *v = sm.next_u64();
+        }
+        if s == [0; 4] {
+            s[0] = 1;
+        }
+        Self { s }
+    }
+
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    #[inline]
+    pub fn state(&self) -> [u64; 4] {
+        self.s
+    }
+
+    /// Advances by `2^128` steps, producing non-overlapping parallel streams.
+    pub fn jump(&mut self) {
+        self.apply_polynomial(&JUMP);
+    }
+
+    /// Advances by `2^192` steps, producing non-overlapping groups of streams.
+    pub fn long_jump(&mut self) {
+        self.apply_polynomial(&LONG_JUMP);
+    }
+
+    pub fn advance(&mut self, delta: u128) {
+        if delta < 256 {
+            for _ in 0..delta {
+                self.step();
+            }
+            return;
+        }
+        let poly = characteristic_polynomial().pow_x_mod(delta);
+        let mut coeffs = [0u64; 4];
+        coeffs[..poly.words().len()].copy_from_slice(poly.words());
+        self.apply_polynomial(&coeffs);
+    }
+
+    pub fn for_stream(seed: [u64; 4], stream: u128, stride: u128) -> Result<Self, SeedError> {
+        let mut rng = Self::new(seed)?;
+        rng.advance(stream.saturating_mul(stride));
+        Ok(rng)
+    }
+
+    fn apply_polynomial(&mut self, coeffs: &[u64; 4]) {
+        let mut acc = [0u64; 4];
+        for &word in coeffs {
+            for bit in 0..64 {
+                if (word >> bit) & 1 == 1 {
+                    for (a, s) in acc.iter_mut().zip(self.s.iter()) {
+                        *a ^= s;
+                    }
+                }
+                self.step();
+            }
+        }
+        self.s = acc;
+    }
+
+    #[inline]
+    fn step(&mut self) {
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+    }
+}
+
+impl RngCore for Xoshiro256StarStar {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        let result = self.s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        self.step();
+        result
+    }
+}
+
+impl JumpAhead for Xoshiro256StarStar {
+    type Error = Infallible;
+
+    fn advance(&mut self, delta: u128) -> Result<(), Self::Error> {
+        Xoshiro256StarStar::advance(self, delta);
+        Ok(())
+    }
+}
+
+impl BlockSplit for Xoshiro256StarStar {
+    type Seed = [u64; 4];
+    type Error = SeedError;
+
+    fn for_stream(seed: Self::Seed, stream: u128, stride: u128) -> Result<Self, Self::Error> {
+        Xoshiro256StarStar::for_stream(seed, stream, stride)
+    }
+}
+
+/// Characteristic polynomial of the xoshiro256 linear engine (degree 256).
+fn characteristic_polynomial() -> &'static Gf2Poly {
+    static POLY: OnceLock<Gf2Poly> = OnceLock::new();
+    POLY.get_or_init(|| {
+        let mut rng = Xoshiro256StarStar { s: [1, 0, 0, 0] };
+        let bits: Vec<bool> = (0..512)
+            .map(|_| {
+                let bit = rng.s[0] & 1 == 1;
+                rng.step();
+                bit
+            })
+            .collect();
+        minimal_polynomial(&bits)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xoshiro_matches_reference_output() {
+        let mut rng = Xoshiro256StarStar::new([1, 2, 3, 4]).unwrap();
+        let expected = [
+            11_520,
+            0,
+            1_509_978_240,
+            1_215_971_899_390_074_240,
+            1_216_172_134_540_287_360,
+            607_988_272_756_665_600,
+            16_172_922_978_634_559_625,
+            8_476_171_486_693_032_832,
+            10_595_114_339_597_558_777,
+            2_904_607_092_377_533_576,
+        ];
+        for &value in &expected {
+            assert_eq!(rng.next_u64(), value);
+        }
+    }
+
+    #[test]
+    fn xoshiro_characteristic_polynomial_reproduces_published_jumps() {
+        let poly = characteristic_polynomial();
+        assert_eq!(poly.degree(), Some(256));
+
+        let mut jump = poly.pow_x_mod(1u128 << 127);
+        jump = jump.square_mod(poly);
+        assert_eq!(jump.words(), &JUMP);
+
+        for _ in 0..64 {
+            jump = jump.square_mod(poly);
+        }
+        assert_eq!(jump.words(), &LONG_JUMP);
+    }
+
+    #[test]
+    fn xoshiro_advance_matches_iter() {
+        let mut advanced = Xoshiro256StarStar::from_seed64(7);
+        let mut iterated = advanced;
+        advanced.advance(1_000);
+        for _ in 0..1_000 {
+            iterated.next_u64();
+        }
+        assert_eq!(advanced.state(), iterated.state());
+        assert_eq!(advanced.next_u64(), iterated.next_u64());
+    }
+
+    #[test]
+    fn xoshiro_rejects_zero_seed() {
+        assert!(Xoshiro256StarStar::new([0; 4]).is_err());
+    }
+
+    #[test]
+    fn xoshiro_next_f64_stays_in_unit_interval() {
+        let mut rng = Xoshiro256StarStar::from_seed64(3);
+        for _ in 0..10 {
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+
+    #[test]
+    fn xoshiro_block_splitting_aligns_streams() {
+        let seed = [5, 7, 11, 13];
+        let stride = 300u128;
+        let mut base = Xoshiro256StarStar::for_stream(seed, 0, stride).unwrap();
+        let split = Xoshiro256StarStar::for_stream(seed, 1, stride).unwrap();
+        for _ in 0..stride {
+            base.next_u32();
+        }
+        assert_eq!(base.state(), split.state());
+    }
+
+    #[test]
+    fn xoshiro_long_jump_differs_from_jump() {
+        let mut a = Xoshiro256StarStar::new([1, 2, 3, 4]).unwrap();
+        let mut b = a;
+        a.jump();
+        b.long_jump();
+        assert_ne!(a.state(), b.state());
+    }
+}
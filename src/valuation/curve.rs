@@ -0,0 +1,73 @@
+use super::ValuationError;
+
+/// Annually compounded zero rates by term in years.
+///
+/// Rates are interpolated linearly between pillars and held flat outside
+/// them, so `discount(t) = (1 + z(t))^-t`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscountCurve {
+    terms: Vec<f64>,
+    rates: Vec<f64>,
+}
+
+impl DiscountCurve {
+    /// Curve through `(term, zero rate)` pillars with strictly increasing,
+    /// non-negative terms.
+    pub fn new(pillars: Vec<(f64, f64)>) -> Result<Self, ValuationError> {
+        let valid = !pillars.is_empty()
+            && pillars.iter().all(|&(term, rate)| {
+                term.is_finite() && term >= 0.0 && rate.is_finite() && rate > -1.0
+            })
+            && pillars.windows(2).all(|pair| pair[0].0 < pair[1].0);
+        if !valid {
+            return Err(ValuationError::InvalidCurve);
+        }
+        let (terms, rates) = pillars.into_iter().unzip();
+        Ok(Self { terms, rates })
+    }
+
+    pub fn flat(rate: f64) -> Result<Self, ValuationError> {
+        Self::new(vec![(0.0, rate)])
+    }
+
+    pub fn zero_rate(&self, term: f64) -> f64 {
+        let upper = self.terms.partition_point(|&t| t <= term);
+        if upper == 0 {
+            return self.rates[0];
+        }
+        if upper == self.terms.len() {
+            return self.rates[upper - 1];
+        }
+        let (t0, t1) = (self.terms[upper - 1], self.terms[upper]);
+        let (r0, r1) = (self.rates[upper - 1], self.rates[upper]);
+        r0 + (r1 - r0) * (term - t0) / (t1 - t0)
+    }
+
+    /// Value today of 1 paid in `term` years.
+    pub fn discount(&self, term: f64) -> f64 {
+        (1.0 + self.zero_rate(term)).powf(-term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_zero_rates_and_discounts() {
+        let flat = DiscountCurve::flat(0.05).unwrap();
+        assert!((flat.discount(2.0) - 1.05f64.powi(-2)).abs() < 1e-15);
+        assert_eq!(flat.discount(0.0), 1.0);
+
+        let curve = DiscountCurve::new(vec![(1.0, 0.02), (3.0, 0.04)]).unwrap();
+        assert_eq!(curve.zero_rate(0.5), 0.02);
+        assert!((curve.zero_rate(2.0) - 0.03).abs() < 1e-15);
+        assert_eq!(curve.zero_rate(10.0), 0.04);
+        assert!((curve.discount(2.0) - 1.03f64.powi(-2)).abs() < 1e-15);
+
+        assert!(DiscountCurve::new(Vec::new()).is_err());
+        assert!(DiscountCurve::new(vec![(1.0, 0.02), (1.0, 0.03)]).is_err());
+        assert!(DiscountCurve::flat(-1.0).is_err());
+    }
+}
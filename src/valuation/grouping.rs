@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+
+use crate::product::CashflowBuffer;
+
+/// Re-estimated cashflows taking effect at `step`.
+///
+/// The buffer covers the whole projection; how amounts before `step` are
+/// read depends on the measurement, which treats them either as actual
+/// cashflows to date or as part of the estimate being replaced.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    pub step: usize,
+    pub cashflows: CashflowBuffer,
+}
+
+/// Cashflows of the items sharing a key, summed.
+#[derive(Debug, Clone)]
+pub(crate) struct Grouped<K> {
+    pub key: K,
+    pub cashflows: CashflowBuffer,
+    /// Indices of the grouped items in the input.
+    pub members: Vec<usize>,
+}
+
+/// Groups `items` by `key` in key order, summing their cashflows.
+///
+/// Fails with `mismatch` when the cashflows of a group do not share one
+/// shape and dates, or with the first error from `key`.
+pub(crate) fn group_cashflows<T, K: Ord + Clone, E: Copy>(
+    items: &[T],
+    cashflows: impl Fn(&T) -> &CashflowBuffer,
+    mut key: impl FnMut(&T) -> Result<K, E>,
+    mismatch: E,
+) -> Result<Vec<Grouped<K>>, E> {
+    let mut groups: BTreeMap<K, Grouped<K>> = BTreeMap::new();
+    for (index, item) in items.iter().enumerate() {
+        let key = key(item)?;
+        match groups.get_mut(&key) {
+            Some(group) => {
+                group
+                    .cashflows
+                    .add_buffer(cashflows(item))
+                    .map_err(|_| mismatch)?;
+                group.members.push(index);
+            }
+            None => {
+                let group = Grouped {
+                    key: key.clone(),
+                    cashflows: cashflows(item).clone(),
+                    members: vec![index],
+                };
+                groups.insert(key, group);
+            }
+        }
+    }
+    Ok(groups.into_values().collect())
+}
@@ -0,0 +1,505 @@
+//! Policy reserves valued from projected cashflows.
+
+mod curve;
+mod grouping;
+mod projection;
+
+pub use curve::DiscountCurve;
+pub use grouping::Revision;
+pub use projection::ReservedProduct;
+
+pub(crate) use grouping::group_cashflows;
+
+use std::fmt;
+
+use crate::product::{
+    Amount, CashflowBuffer, CashflowCategory, CashflowKindId, CashflowTiming, KindMetadata,
+    ProductDefinition,
+};
+use crate::{Date, Frequency};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuationError {
+    /// Curve pillars must be finite, with increasing terms and rates above
+    /// -100%.
+    InvalidCurve,
+    /// The cashflow buffer or in-force series does not match.
+    Shape,
+    /// The product could not be projected.
+    Model,
+}
+
+impl fmt::Display for ValuationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCurve => f.write_str("invalid discount curve"),
+            Self::Shape => f.write_str("cashflows do not match the product definition"),
+            Self::Model => f.write_str("product projection failed"),
+        }
+    }
+}
+
+impl std::error::Error for ValuationError {}
+
+/// Cashflows a reserve values and the premiums it allows for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReserveBasis {
+    /// Gross premiums, benefits, expenses and commissions.
+    GrossPremium,
+    /// Benefits less the level proportion of gross premiums that funds them
+    /// at issue; expenses are ignored.
+    NetPremium,
+    /// Net premium reserve with the first policy year valued as one-year
+    /// term, so the reserve at the end of that year is zero.
+    FullPreliminaryTerm,
+}
+
+/// Reserves at the start of each projection step, before that step's
+/// cashflows, for the expected in-force of the projection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reserves {
+    basis: ReserveBasis,
+    times: Vec<Date>,
+    values: Vec<Amount>,
+}
+
+impl Reserves {
+    pub fn basis(&self) -> ReserveBasis {
+        self.basis
+    }
+
+    pub fn times(&self) -> &[Date] {
+        &self.times
+    }
+
+    pub fn values(&self) -> &[Amount] {
+        &self.values
+    }
+
+    pub fn at(&self, step: usize) -> Amount {
+        self.values[step]
+    }
+
+    /// Reserves per policy given the expected number in force at each step;
+    /// zero where nothing is in force.
+    pub fn per_policy(&self, in_force: &[f64]) -> Result<Vec<Amount>, ValuationError> {
+        if in_force.len() != self.values.len() {
+            return Err(ValuationError::Shape);
+        }
+        Ok(self
+            .values
+            .iter()
+            .zip(in_force)
+            .map(|(&value, &n)| if n > 0.0 { value / n } else { Amount::zero() })
+            .collect())
+    }
+}
+
+/// Signed amounts of one step, paid at its start and at its end.
+#[derive(Debug, Clone, Copy, Default)]
+struct Flows {
+    start: f64,
+    end: f64,
+}
+
+impl Flows {
+    /// Value at time zero given discount factors to each step date.
+    fn value(self, step: usize, discount: &[f64]) -> f64 {
+        self.start * discount[step] + self.end * discount[step + 1]
+    }
+}
+
+/// Reserves from a projected cashflow buffer on a discount curve.
+///
+/// Step `k` falls `k / periods_per_year` years after the projection start.
+/// Amounts are summed over states and signed by their kind's direction;
+/// start-of-step kinds are discounted from the step date and end-of-step kinds
+/// from the next. The gross premium basis values premium, benefit, expense and
+/// commission kinds unless [`with_categories`](Self::with_categories) says
+/// otherwise; the net premium bases value premiums and benefits only. Kinds
+/// selected by category never include memo kinds, which move money within an
+/// account value that the payments out of it already reflect; value those
+/// explicitly with [`present_value_of`](Self::present_value_of).
+/// Prospective reserves value the
+/// outgo less income of the step and all later steps, so they are zero after
+/// the last step. Retrospective reserves accumulate income less outgo of
+/// earlier steps, and equal the prospective reserve when the basis balances
+/// at issue, as the net premium bases do.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Valuation {
+    curve: DiscountCurve,
+    frequency: Frequency,
+    in_force_states: Vec<usize>,
+    categories: Vec<CashflowCategory>,
+}
+
+impl Valuation {
+    pub fn new(curve: DiscountCurve, frequency: Frequency) -> Self {
+        Self {
+            curve,
+            frequency,
+            in_force_states: Vec::new(),
+            categories: vec![
+                CashflowCategory::Premium,
+                CashflowCategory::Benefit,
+                CashflowCategory::Expense,
+                CashflowCategory::Commission,
+            ],
+        }
+    }
+
+    /// Categories valued on the gross premium basis, replacing the default of
+    /// premiums, benefits, expenses and commissions.
+    pub fn with_categories(mut self, categories: Vec<CashflowCategory>) -> Self {
+        self.categories = categories;
+        self
+    }
+
+    /// States whose occupants count as in force when [`run`](Self::run)
+    /// converts reserves to per-policy amounts; defaults to the product's
+    /// initial state.
+    pub fn with_in_force_states(mut self, states: Vec<usize>) -> Self {
+        self.in_force_states = states;
+        self
+    }
+
+    pub fn curve(&self) -> &DiscountCurve {
+        &self.curve
+    }
+
+    pub fn prospective(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        basis: ReserveBasis,
+    ) -> Result<Reserves, ValuationError> {
+        let (net, discount) = self.valued_flows(definition, cashflows, basis)?;
+        let mut values = vec![Amount::zero(); net.len()];
+        let mut outgo = 0.0;
+        for step in (0..net.len()).rev() {
+            outgo -= net[step];
+            values[step] = Amount::from_f64(outgo / discount[step]);
+        }
+        Ok(Reserves {
+            basis,
+            times: cashflows.times().to_vec(),
+            values,
+        })
+    }
+
+    pub fn retrospective(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        basis: ReserveBasis,
+    ) -> Result<Reserves, ValuationError> {
+        let (net, discount) = self.valued_flows(definition, cashflows, basis)?;
+        let mut values = Vec::with_capacity(net.len());
+        let mut fund = 0.0;
+        for (step, value) in net.iter().enumerate() {
+            values.push(Amount::from_f64(fund / discount[step]));
+            fund += value;
+        }
+        Ok(Reserves {
+            basis,
+            times: cashflows.times().to_vec(),
+            values,
+        })
+    }
+
+    /// Signed present value at each step of the non-memo kinds in
+    /// `categories` paid in that step and later; positive when income
+    /// exceeds outgo.
+    pub fn present_value(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        categories: &[CashflowCategory],
+    ) -> Result<Vec<Amount>, ValuationError> {
+        let flows = self.flows(definition, cashflows, |_, metadata| {
+            valued(categories, metadata)
+        })?;
+        Ok(self.discount_flows(&flows))
+    }
+
+    /// Signed present value at each step of `kinds` paid in that step and
+    /// later.
+    pub fn present_value_of(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        kinds: &[CashflowKindId],
+    ) -> Result<Vec<Amount>, ValuationError> {
+        if kinds.iter().any(|kind| kind.0 >= definition.n_kinds) {
+            return Err(ValuationError::Shape);
+        }
+        let flows = self.flows(definition, cashflows, |kind, _| {
+            kinds.contains(&CashflowKindId(kind))
+        })?;
+        Ok(self.discount_flows(&flows))
+    }
+
+    fn discount_flows(&self, flows: &[Flows]) -> Vec<Amount> {
+        let discount = self.discount_factors(flows.len());
+        let mut values = vec![Amount::zero(); flows.len()];
+        let mut total = 0.0;
+        for step in (0..flows.len()).rev() {
+            total += flows[step].value(step, &discount);
+            values[step] = Amount::from_f64(total / discount[step]);
+        }
+        values
+    }
+
+    /// Discount factors to each of `steps` step dates and the end date.
+    fn discount_factors(&self, steps: usize) -> Vec<f64> {
+        let periods = f64::from(self.frequency.periods_per_year());
+        (0..=steps)
+            .map(|step| self.curve.discount(step as f64 / periods))
+            .collect()
+    }
+
+    /// Signed amounts of the kinds selected by `include` by step, summed over
+    /// states.
+    fn flows(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        include: impl Fn(usize, &KindMetadata) -> bool,
+    ) -> Result<Vec<Flows>, ValuationError> {
+        if cashflows.n_states() != definition.n_states || cashflows.n_kinds() != definition.n_kinds
+        {
+            return Err(ValuationError::Shape);
+        }
+        let mut flows = vec![Flows::default(); cashflows.len_steps()];
+        for (kind, metadata) in definition.kinds().iter().enumerate() {
+            if !include(kind, metadata) {
+                continue;
+            }
+            for state in 0..definition.n_states {
+                for (flows, &amount) in flows.iter_mut().zip(cashflows.series(state, kind)) {
+                    let amount = metadata.direction.signed(amount).value();
+                    match metadata.timing {
+                        CashflowTiming::StartOfStep => flows.start += amount,
+                        CashflowTiming::EndOfStep => flows.end += amount,
+                    }
+                }
+            }
+        }
+        Ok(flows)
+    }
+
+    /// Net signed value at time zero of each step's cashflows on `basis`,
+    /// with the discount factors to every step date and the end date.
+    fn valued_flows(
+        &self,
+        definition: &ProductDefinition,
+        cashflows: &CashflowBuffer,
+        basis: ReserveBasis,
+    ) -> Result<(Vec<f64>, Vec<f64>), ValuationError> {
+        let in_category = |categories: &'static [CashflowCategory]| {
+            move |_: usize, metadata: &KindMetadata| valued(categories, metadata)
+        };
+        let premium = self.flows(
+            definition,
+            cashflows,
+            in_category(&[CashflowCategory::Premium]),
+        )?;
+        let benefit = self.flows(
+            definition,
+            cashflows,
+            in_category(&[CashflowCategory::Benefit]),
+        )?;
+        let gross = self.flows(definition, cashflows, |_, metadata| {
+            valued(&self.categories, metadata)
+        })?;
+        let steps = premium.len();
+        let discount = self.discount_factors(steps);
+
+        let value = |flows: &[Flows], range: std::ops::Range<usize>| -> f64 {
+            range.map(|step| flows[step].value(step, &discount)).sum()
+        };
+        // Proportion of premiums valued in each step.
+        let ratio = |range: std::ops::Range<usize>| {
+            let premiums = value(&premium, range.clone());
+            if premiums == 0.0 {
+                0.0
+            } else {
+                -value(&benefit, range) / premiums
+            }
+        };
+        let first_year = (self.frequency.periods_per_year() as usize).min(steps);
+        let net = match basis {
+            ReserveBasis::GrossPremium => (0..steps)
+                .map(|step| gross[step].value(step, &discount))
+                .collect(),
+            ReserveBasis::NetPremium | ReserveBasis::FullPreliminaryTerm => {
+                let (initial, renewal) = if basis == ReserveBasis::NetPremium {
+                    let level = ratio(0..steps);
+                    (level, level)
+                } else {
+                    (ratio(0..first_year), ratio(first_year..steps))
+                };
+                (0..steps)
+                    .map(|step| {
+                        let share = if step < first_year { initial } else { renewal };
+                        share * premium[step].value(step, &discount)
+                            + benefit[step].value(step, &discount)
+                    })
+                    .collect()
+            }
+        };
+        Ok((net, discount))
+    }
+}
+
+/// Whether a kind selected by category is valued: it is in `categories` and
+/// not a memo kind.
+fn valued(categories: &[CashflowCategory], metadata: &KindMetadata) -> bool {
+    !metadata.memo && categories.contains(&metadata.category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generate_cashflow_dates;
+    use crate::life::{MortalityTable, TermLife};
+    use crate::model::{ExpectedValueModel, Model, ModelConfig};
+    use crate::product::{Product, RequiredDataBuffer};
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+
+    fn project(product: &TermLife, steps: usize) -> (CashflowBuffer, Vec<f64>) {
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps,
+        };
+        let definition = product.definition();
+        let times = generate_cashflow_dates(config.start, steps, config.frequency).unwrap();
+        let mut cashflows =
+            CashflowBuffer::new(definition.n_states, definition.n_kinds, times).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(definition.required_data.clone(), definition.n_states).unwrap();
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        ExpectedValueModel
+            .run(product, &config, &mut rng, &mut cashflows, &mut data)
+            .unwrap();
+        let in_force = ExpectedValueModel
+            .occupancy(product, steps, &mut rng, &mut data)
+            .unwrap()
+            .chunks(definition.n_states)
+            .map(|row| row[TermLife::ACTIVE])
+            .collect();
+        (cashflows, in_force)
+    }
+
+    #[test]
+    fn net_premium_reserves_match_amlcr_and_retrospective_accumulation() {
+        // 20-year term on (50), SUSM at 5%, charged a gross premium 10% above
+        // the net premium of 313.02 for 100,000.
+        let table = MortalityTable::standard_ultimate();
+        let product = TermLife::new(table, 50, 20, 100_000.0, 1.1 * 313.022_467).unwrap();
+        let (cashflows, in_force) = project(&product, 20);
+        let valuation = Valuation::new(DiscountCurve::flat(0.05).unwrap(), Frequency::Annual);
+        let definition = product.definition();
+
+        let net = valuation
+            .prospective(definition, &cashflows, ReserveBasis::NetPremium)
+            .unwrap();
+        let per_policy = net.per_policy(&in_force).unwrap();
+        assert!(per_policy[0].value().abs() < 1e-6);
+        for (t, expected) in [(5, 1008.17), (10, 1761.83), (15, 1763.90), (19, 572.11)] {
+            assert!((per_policy[t].value() - expected).abs() < 0.01);
+        }
+        let retrospective = valuation
+            .retrospective(definition, &cashflows, ReserveBasis::NetPremium)
+            .unwrap();
+        for (p, r) in net.values().iter().zip(retrospective.values()) {
+            assert!((p.value() - r.value()).abs() < 1e-6);
+        }
+
+        // The loading makes the gross premium reserve lower by 10% of the
+        // net premium annuity, ä = 12.8428 at issue.
+        let gross = valuation
+            .prospective(definition, &cashflows, ReserveBasis::GrossPremium)
+            .unwrap();
+        assert!((gross.at(0).value() + 0.1 * 313.022_467 * 12.8428).abs() < 0.1);
+        assert_eq!(gross.times(), cashflows.times());
+        assert!(
+            valuation
+                .retrospective(definition, &cashflows, ReserveBasis::GrossPremium)
+                .unwrap()
+                .at(0)
+                == Amount::zero()
+        );
+    }
+
+    #[test]
+    fn full_preliminary_term_is_zero_after_the_first_year() {
+        let table = MortalityTable::standard_ultimate();
+        let product = TermLife::new(table, 50, 20, 100_000.0, 400.0).unwrap();
+        let (cashflows, _) = project(&product, 20);
+        let valuation = Valuation::new(DiscountCurve::flat(0.05).unwrap(), Frequency::Annual);
+        let definition = product.definition();
+        let fpt = valuation
+            .prospective(definition, &cashflows, ReserveBasis::FullPreliminaryTerm)
+            .unwrap();
+        let net = valuation
+            .prospective(definition, &cashflows, ReserveBasis::NetPremium)
+            .unwrap();
+        assert!(fpt.at(0).value().abs() < 1e-6);
+        assert!(fpt.at(1).value().abs() < 1e-6);
+        for t in 2..20 {
+            assert!(fpt.at(t) < net.at(t));
+            assert!(fpt.at(t).value() > 0.0);
+        }
+        let retrospective = valuation
+            .retrospective(definition, &cashflows, ReserveBasis::FullPreliminaryTerm)
+            .unwrap();
+        for (p, r) in fpt.values().iter().zip(retrospective.values()) {
+            assert!((p.value() - r.value()).abs() < 1e-6);
+        }
+
+        assert_eq!(net.per_policy(&[1.0]).unwrap_err(), ValuationError::Shape);
+        let other = CashflowBuffer::new(1, 1, cashflows.times().to_vec()).unwrap();
+        assert_eq!(
+            valuation
+                .prospective(definition, &other, ReserveBasis::NetPremium)
+                .unwrap_err(),
+            ValuationError::Shape
+        );
+    }
+
+    #[test]
+    fn gross_premium_basis_values_the_chosen_categories() {
+        use crate::product::{FlowDirection, RequiredDataLayout};
+
+        let definition = ProductDefinition::named(
+            vec!["active".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("policy_fee", FlowDirection::Inflow, CashflowCategory::Other),
+            ],
+            RequiredDataLayout::new(1, 0).unwrap(),
+        )
+        .unwrap();
+        let times =
+            crate::generate_cashflow_dates(Date::constant(2024, 1, 1), 2, Frequency::Annual)
+                .unwrap();
+        let mut cashflows = CashflowBuffer::new(1, 2, times).unwrap();
+        cashflows.series_mut(0, 0).fill(Amount::from_f64(10.0));
+        cashflows.series_mut(0, 1).fill(Amount::from_f64(3.0));
+
+        let valuation = Valuation::new(DiscountCurve::flat(0.0).unwrap(), Frequency::Annual);
+        let default = valuation
+            .prospective(&definition, &cashflows, ReserveBasis::GrossPremium)
+            .unwrap();
+        assert_eq!(default.at(0), Amount::from_f64(-20.0));
+        let with_fees = valuation
+            .with_categories(vec![CashflowCategory::Premium, CashflowCategory::Other])
+            .prospective(&definition, &cashflows, ReserveBasis::GrossPremium)
+            .unwrap();
+        assert_eq!(with_fees.at(0), Amount::from_f64(-26.0));
+        assert_eq!(with_fees.at(1), Amount::from_f64(-13.0));
+    }
+}
@@ -0,0 +1,321 @@
+use super::{ReserveBasis, Reserves, Valuation, ValuationError};
+use crate::Frequency;
+use crate::model::{ExpectedValueModel, Model, ModelConfig};
+use crate::product::{
+    Amount, CashflowBuffer, Product, ProductDefinition, ProductState, RequiredDataBuffer,
+};
+use crate::rng::RngCore;
+
+/// Product whose states carry a reserve per policy at every step.
+///
+/// The wrapped product sees each step's state with `reserves` set to the
+/// reserve per policy times the state's `in_force`, applied through
+/// [`ProductState::apply_reserve_change`] as the state is rolled forward.
+/// The reserve is zero past the last step.
+pub struct ReservedProduct<'a> {
+    product: &'a dyn Product,
+    reserves: Vec<Amount>,
+}
+
+impl<'a> ReservedProduct<'a> {
+    pub fn new(product: &'a dyn Product, reserves: Vec<Amount>) -> Self {
+        Self { product, reserves }
+    }
+
+    fn reserved(&self, time_index: usize, mut state: ProductState) -> ProductState {
+        let per_policy = self
+            .reserves
+            .get(time_index)
+            .copied()
+            .unwrap_or(Amount::zero());
+        let target = per_policy * state.in_force as f64;
+        state.apply_reserve_change(target - state.reserves);
+        state
+    }
+}
+
+impl Product for ReservedProduct<'_> {
+    fn definition(&self) -> &ProductDefinition {
+        self.product.definition()
+    }
+
+    fn initial_state(&self) -> ProductState {
+        self.reserved(0, self.product.initial_state())
+    }
+
+    fn generate_required_data(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        rng: &mut dyn RngCore,
+        out: &mut RequiredDataBuffer,
+    ) {
+        self.product
+            .generate_required_data(time_index, state, rng, out);
+    }
+
+    fn cashflows(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [Amount],
+    ) {
+        self.product.cashflows(time_index, state, data, out);
+    }
+
+    fn next_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        rng: &mut dyn RngCore,
+    ) -> ProductState {
+        let next = self.product.next_state(time_index, state, data, rng);
+        self.reserved(time_index + 1, next)
+    }
+
+    fn transition_probabilities(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+        out: &mut [f64],
+    ) -> bool {
+        self.product
+            .transition_probabilities(time_index, state, data, out)
+    }
+
+    fn advance_state(
+        &self,
+        time_index: usize,
+        state: &ProductState,
+        data: &RequiredDataBuffer,
+    ) -> ProductState {
+        let next = self.product.advance_state(time_index, state, data);
+        self.reserved(time_index + 1, next)
+    }
+    fn frequency(&self) -> Option<Frequency> {
+        self.product.frequency()
+    }
+}
+
+impl Valuation {
+    /// Projects `product` with the expected-value model, values its reserves
+    /// prospectively on `basis` and projects again with the reserve per policy
+    /// in force written into each step's state through [`ReservedProduct`].
+    ///
+    /// `cashflows` and `data` hold the second projection; the reserves are
+    /// valued from the first, so products whose cashflows depend on the
+    /// reserve see it one iteration behind. The first projection runs on a
+    /// clone of `rng` and the second on `rng` itself, so both see the same
+    /// draws and `rng` is left where a single projection leaves it.
+    pub fn run<R: RngCore + Clone>(
+        &self,
+        product: &dyn Product,
+        config: &ModelConfig,
+        basis: ReserveBasis,
+        rng: &mut R,
+        cashflows: &mut CashflowBuffer,
+        data: &mut RequiredDataBuffer,
+    ) -> Result<Reserves, ValuationError> {
+        if config.frequency != self.frequency {
+            return Err(ValuationError::Shape);
+        }
+        let definition = product.definition();
+        let occupancy = ExpectedValueModel
+            .run_with_occupancy(product, config, &mut rng.clone(), cashflows, data)
+            .map_err(|_| ValuationError::Model)?;
+        let reserves = self.prospective(definition, cashflows, basis)?;
+
+        let initial = product.initial_state();
+        let default_states = [initial.state_id];
+        let states = if self.in_force_states.is_empty() {
+            &default_states[..]
+        } else {
+            &self.in_force_states[..]
+        };
+        if states.iter().any(|&state| state >= definition.n_states) {
+            return Err(ValuationError::Shape);
+        }
+        let lives = initial.in_force as f64;
+        let in_force: Vec<f64> = occupancy
+            .chunks_exact(definition.n_states)
+            .map(|row| states.iter().map(|&state| row[state]).sum::<f64>() * lives)
+            .collect();
+        let reserved = ReservedProduct::new(product, reserves.per_policy(&in_force)?);
+        ExpectedValueModel
+            .run(&reserved, config, rng, cashflows, data)
+            .map_err(|_| ValuationError::Model)?;
+        Ok(reserves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::{
+        CashflowCategory, CashflowTiming, FlowDirection, KindMetadata, RequiredDataLayout,
+    };
+    use crate::rng::xoshiro256::Xoshiro256StarStar;
+    use crate::valuation::DiscountCurve;
+    use crate::{Date, Frequency, generate_cashflow_dates};
+
+    /// Premium of 20 in advance and a benefit of 100 at the end of the step
+    /// on death, with probability 0.1 in the first step rising by 0.1 a step.
+    /// The carried reserve and a uniform draw are written to the data.
+    struct Term {
+        definition: ProductDefinition,
+    }
+
+    fn q(time_index: usize) -> f64 {
+        0.1 * (time_index + 1) as f64
+    }
+
+    impl Product for Term {
+        fn definition(&self) -> &ProductDefinition {
+            &self.definition
+        }
+
+        fn initial_state(&self) -> ProductState {
+            ProductState::new(0, 2, Amount::zero())
+        }
+
+        fn generate_required_data(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            rng: &mut dyn RngCore,
+            out: &mut RequiredDataBuffer,
+        ) {
+            out.set_policy_scalar(0, state.reserves.value());
+            out.set_policy_scalar(1, crate::rng::next_uniform(rng));
+        }
+
+        fn cashflows(
+            &self,
+            time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [Amount],
+        ) {
+            let alive = if state.state_id == 0 { 1.0 } else { 0.0 };
+            let n = state.in_force as f64 * alive;
+            out[0] = Amount::from_f64(20.0 * n);
+            out[1] = Amount::from_f64(100.0 * q(time_index) * n);
+        }
+
+        fn next_state(
+            &self,
+            _time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            _rng: &mut dyn RngCore,
+        ) -> ProductState {
+            *state
+        }
+
+        fn transition_probabilities(
+            &self,
+            time_index: usize,
+            state: &ProductState,
+            _data: &RequiredDataBuffer,
+            out: &mut [f64],
+        ) -> bool {
+            if state.state_id == 0 {
+                out.copy_from_slice(&[1.0 - q(time_index), q(time_index)]);
+            } else {
+                out.copy_from_slice(&[0.0, 1.0]);
+            }
+            true
+        }
+    }
+
+    #[test]
+    fn run_writes_reserves_into_the_projected_state() {
+        let definition = ProductDefinition::named(
+            vec!["alive".into(), "dead".into()],
+            vec![
+                KindMetadata::new("premium", FlowDirection::Inflow, CashflowCategory::Premium),
+                KindMetadata::new("death", FlowDirection::Outflow, CashflowCategory::Benefit)
+                    .with_timing(CashflowTiming::EndOfStep),
+            ],
+            RequiredDataLayout::new(2, 0).unwrap(),
+        )
+        .unwrap();
+        let product = Term { definition };
+        let config = ModelConfig {
+            start: Date::constant(2024, 1, 1),
+            frequency: Frequency::Annual,
+            steps: 3,
+        };
+        let times = generate_cashflow_dates(config.start, 3, config.frequency).unwrap();
+        let mut cashflows = CashflowBuffer::new(2, 2, times.clone()).unwrap();
+        let mut data =
+            RequiredDataBuffer::new(product.definition.required_data.clone(), 2).unwrap();
+        data.enable_history(times).unwrap();
+        let valuation = Valuation::new(DiscountCurve::flat(0.0).unwrap(), Frequency::Annual);
+        let mut rng = Xoshiro256StarStar::from_seed64(1);
+        let reserves = valuation
+            .run(
+                &product,
+                &config,
+                ReserveBasis::NetPremium,
+                &mut rng,
+                &mut cashflows,
+                &mut data,
+            )
+            .unwrap();
+
+        // Survivors at the start of each step and the net premium ratio.
+        let survival = [1.0, 0.9, 0.72];
+        let benefits: Vec<f64> = (0..3).map(|t| 100.0 * q(t) * survival[t]).collect();
+        let premiums: Vec<f64> = survival.iter().map(|s| 20.0 * s).collect();
+        let ratio = benefits.iter().sum::<f64>() / premiums.iter().sum::<f64>();
+        let recorded = data.history().unwrap().policy_scalar_series(0);
+        for t in 0..3 {
+            let per_policy = (t..3)
+                .map(|i| benefits[i] - ratio * premiums[i])
+                .sum::<f64>()
+                / survival[t];
+            assert!((reserves.at(t).value() - 2.0 * survival[t] * per_policy).abs() < 1e-9);
+            assert!((recorded[t] - 2.0 * per_policy).abs() < 1e-9);
+        }
+        assert!(recorded[0].abs() < 1e-9 && recorded[1] > 0.0);
+
+        // Both projections see the draws of a single run from the same seed.
+        let mut single = Xoshiro256StarStar::from_seed64(1);
+        let mut fresh =
+            RequiredDataBuffer::new(product.definition.required_data.clone(), 2).unwrap();
+        fresh.enable_history(cashflows.times().to_vec()).unwrap();
+        ExpectedValueModel
+            .run(
+                &product,
+                &config,
+                &mut single,
+                &mut cashflows.clone(),
+                &mut fresh,
+            )
+            .unwrap();
+        assert_eq!(rng.state(), single.state());
+        assert_eq!(
+            data.history().unwrap().policy_scalar_series(1),
+            fresh.history().unwrap().policy_scalar_series(1)
+        );
+
+        let monthly = Valuation::new(DiscountCurve::flat(0.0).unwrap(), Frequency::Monthly);
+        assert_eq!(
+            monthly
+                .run(
+                    &product,
+                    &config,
+                    ReserveBasis::NetPremium,
+                    &mut rng,
+                    &mut cashflows,
+                    &mut data,
+                )
+                .unwrap_err(),
+            ValuationError::Shape
+        );
+    }
+}